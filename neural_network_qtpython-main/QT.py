@@ -6,15 +6,16 @@ import matplotlib
 matplotlib.use('Qt5Agg')
 from PyQt5.QtWidgets import (QApplication, QMainWindow, QWidget, QVBoxLayout, QHBoxLayout,
                             QPushButton, QLineEdit, QLabel, QFileDialog, QMessageBox,
-                            QProgressBar, QGroupBox, QTabWidget, QSplitter)
+                            QProgressBar, QGroupBox, QTabWidget, QSplitter, QScrollArea)
 from PyQt5.QtGui import QValidator, QPixmap
 from PyQt5 import QtGui
 from PyQt5.QtCore import Qt, pyqtSignal, QThread
 from matplotlib.backends.backend_qt5agg import FigureCanvasQTAgg as FigureCanvas
 from matplotlib.figure import Figure
-from ctypes import CDLL, c_char_p, c_int, c_double, POINTER, Structure, c_float, CFUNCTYPE
+from ctypes import CDLL, c_char_p, c_int, c_uint, c_double, c_bool, c_void_p, POINTER, Structure, c_float, CFUNCTYPE, byref
 import tempfile
 import shutil
+import threading
 import matplotlib.animation as animation
 from matplotlib.patches import Circle, Arrow
 
@@ -27,11 +28,32 @@ class PredictionResult(Structure):
     _fields_ = [
         ("predicted_class", c_int),
         ("probabilities", POINTER(c_double)),  # Changed to c_double to match Rust's f64
-        ("probabilities_len", c_int)
+        ("probabilities_len", c_int),
+        ("warnings", c_uint),  # Flags: 1 not finite, 2 below minimum, 4 above maximum, 8 out of distribution
+        ("input_warnings", c_uint * 5),
+        ("ood_score", c_double),
+        ("warning_messages", c_char_p)
     ]
 
-# Define callback type
-PROGRESS_CALLBACK = CFUNCTYPE(None, c_int, c_double, c_double)
+class TrainingProgress(Structure):
+    _fields_ = [
+        ("epoch", c_int),
+        ("total_epochs", c_int),
+        ("train_loss", c_double),
+        ("train_accuracy", c_double),
+        ("val_loss", c_double),
+        ("val_accuracy", c_double),
+        ("learning_rate", c_double),
+        ("elapsed_secs", c_double),
+        ("remaining_secs", c_double),
+        ("gradient_norm", c_double)
+    ]
+
+# Define callback type (progress, user_data); return 0 to continue, non-zero to stop training
+PROGRESS_CALLBACK = CFUNCTYPE(c_int, POINTER(TrainingProgress), c_void_p)
+
+# Training job status codes
+JOB_RUNNING, JOB_PAUSED, JOB_COMPLETED, JOB_CANCELLED, JOB_FAILED = range(5)
 
 # Define function prototypes
 rust_lib.train_model_with_progress.argtypes = [
@@ -40,9 +62,25 @@ rust_lib.train_model_with_progress.argtypes = [
     c_char_p,  # plot_path
     c_char_p,  # model_path
     POINTER(c_double),  # accuracy
-    PROGRESS_CALLBACK   # callback
+    PROGRESS_CALLBACK,  # callback
+    c_void_p            # user_data
 ]
-rust_lib.train_model_with_progress.restype = c_int
+rust_lib.train_model_with_progress.restype = c_bool
+
+rust_lib.training_job_start.argtypes = [c_char_p, c_int, c_char_p]
+rust_lib.training_job_start.restype = c_void_p
+rust_lib.training_job_status.argtypes = [c_void_p]
+rust_lib.training_job_status.restype = c_int
+rust_lib.training_job_latest_metrics.argtypes = [c_void_p, POINTER(TrainingProgress)]
+rust_lib.training_job_latest_metrics.restype = c_bool
+for name in ("training_job_pause", "training_job_resume", "training_job_cancel", "training_job_free"):
+    getattr(rust_lib, name).argtypes = [c_void_p]
+    getattr(rust_lib, name).restype = None
+rust_lib.training_job_save_model.argtypes = [c_void_p, c_char_p, POINTER(c_double)]
+rust_lib.training_job_save_model.restype = c_bool
+
+rust_lib.write_diagnostic_plots_for_model.argtypes = [c_char_p, c_char_p, c_char_p, c_bool]
+rust_lib.write_diagnostic_plots_for_model.restype = c_int
 
 rust_lib.predict_air_quality.argtypes = [
     c_double,  # pm10
@@ -54,6 +92,17 @@ rust_lib.predict_air_quality.argtypes = [
 ]
 rust_lib.predict_air_quality.restype = POINTER(PredictionResult)
 
+rust_lib.predict_air_quality_production.argtypes = [
+    c_double,  # pm10
+    c_double,  # so2
+    c_double,  # co
+    c_double,  # o3
+    c_double,  # no2
+    c_char_p,  # registry_dir
+    c_char_p   # name
+]
+rust_lib.predict_air_quality_production.restype = POINTER(PredictionResult)
+
 rust_lib.free_prediction_result.argtypes = [POINTER(PredictionResult)]
 rust_lib.free_prediction_result.restype = None
 
@@ -66,11 +115,17 @@ class TrainingThread(QThread):
         self.csv_path = csv_path
         self.epochs = epochs
         self.learning_rate = learning_rate
-        self.running = True
-        
-    def progress_callback(self, epoch, accuracy, loss):
-        self.update_progress.emit(epoch, accuracy, loss)
-        print(f"Epoch {epoch}/{self.epochs} - Accuracy: {accuracy*100:.2f}%, Loss: {loss:.4f}")
+        # stop() runs on the GUI thread while run() frees the job, so every
+        # use of self.job outside run() holds job_lock
+        self.job = None
+        self.job_lock = threading.Lock()
+        self.stop_requested = False
+        
+    def stop(self):
+        with self.job_lock:
+            self.stop_requested = True
+            if self.job:
+                rust_lib.training_job_cancel(self.job)
         
     def run(self):
         # Create paths in project directory
@@ -78,33 +133,60 @@ class TrainingThread(QThread):
         plot_path = os.path.join(project_dir, "training_plot.png")
         model_path = os.path.join(project_dir, "trained_model.bin")
         
-        accuracy = c_double(0.0)
-        
-        csv_path_bytes = self.csv_path.encode('utf-8')
-        plot_path_bytes = plot_path.encode('utf-8')
-        model_path_bytes = model_path.encode('utf-8')
-        
-        callback = PROGRESS_CALLBACK(self.progress_callback)
-        
-        success = rust_lib.train_model_with_progress(
-            csv_path_bytes,
+        job = rust_lib.training_job_start(
+            self.csv_path.encode('utf-8'),
             self.epochs,
-            plot_path_bytes,
-            model_path_bytes,
-            accuracy,
-            callback
+            plot_path.encode('utf-8')
         )
+        if not job:
+            self.training_complete.emit(False, 0.0)
+            return
+        with self.job_lock:
+            self.job = job
+            if self.stop_requested:
+                rust_lib.training_job_cancel(job)
+        
+        # Poll the background job until it finishes
+        progress = TrainingProgress()
+        last_epoch = -1
+        status = rust_lib.training_job_status(job)
+        while status in (JOB_RUNNING, JOB_PAUSED):
+            if rust_lib.training_job_latest_metrics(job, byref(progress)) and progress.epoch != last_epoch:
+                last_epoch = progress.epoch
+                self.update_progress.emit(progress.epoch, progress.train_accuracy, progress.train_loss)
+                print(f"Epoch {progress.epoch}/{progress.total_epochs} - Accuracy: {progress.train_accuracy*100:.2f}%, "
+                      f"Loss: {progress.train_loss:.4f}, Val accuracy: {progress.val_accuracy*100:.2f}%, "
+                      f"ETA: {progress.remaining_secs:.1f}s")
+            self.msleep(50)
+            status = rust_lib.training_job_status(job)
+        
+        accuracy = c_double(0.0)
+        success = status == JOB_COMPLETED and rust_lib.training_job_save_model(
+            job,
+            model_path.encode('utf-8'),
+            byref(accuracy)
+        )
+        self.cancelled = status == JOB_CANCELLED
+        
+        # Once self.job is cleared under the lock, stop() can no longer reach the job
+        with self.job_lock:
+            self.job = None
+        rust_lib.training_job_free(job)
         
         if success:
-            self.training_complete.emit(True, accuracy.value)
             self.plot_path = plot_path
             self.model_path = model_path
+            diagnostics_dir = os.path.join(project_dir, "diagnostics")
+            if rust_lib.write_diagnostic_plots_for_model(
+                model_path.encode('utf-8'),
+                self.csv_path.encode('utf-8'),
+                diagnostics_dir.encode('utf-8'),
+                False
+            ) > 0:
+                self.diagnostics_dir = diagnostics_dir
+            self.training_complete.emit(True, accuracy.value)
         else:
             self.training_complete.emit(False, 0.0)
-            if os.path.exists(plot_path):
-                os.unlink(plot_path)
-            if os.path.exists(model_path):
-                os.unlink(model_path)
 
 class MainWindow(QMainWindow):
     def __init__(self):
@@ -346,9 +428,31 @@ class MainWindow(QMainWindow):
         self.train_button.clicked.connect(self.start_training)
         self.train_button.setEnabled(False)
         
+        self.stop_button = QPushButton("Stop")
+        self.stop_button.setStyleSheet("""
+            QPushButton {
+                background-color: #616161;
+                color: white;
+                border: none;
+                padding: 10px 20px;
+                border-radius: 8px;
+                font-size: 14px;
+            }
+            QPushButton:hover {
+                background-color: #424242;
+            }
+            QPushButton:disabled {
+                background-color: #bdbdbd;
+                color: #eeeeee;
+            }
+        """)
+        self.stop_button.clicked.connect(self.stop_training)
+        self.stop_button.setEnabled(False)
+        
         layout.addLayout(epoch_layout)
         layout.addLayout(lr_layout)
         layout.addWidget(self.train_button)
+        layout.addWidget(self.stop_button)
         layout.addStretch()
         
         group.setLayout(layout)
@@ -454,9 +558,15 @@ class MainWindow(QMainWindow):
         self.nn_ax.axis('off')
         self.nn_layout.addWidget(self.nn_canvas)
         
+        # Tab 3: Diagnostic plots, filled in after training
+        self.diagnostics_scroll = QScrollArea()
+        self.diagnostics_scroll.setWidgetResizable(True)
+        self.diagnostics_scroll.setWidget(QLabel("Diagnostic plots appear here after training."))
+        
         # Add tabs
         self.tab_widget.addTab(self.plots_tab, "Training Progress")
         self.tab_widget.addTab(self.nn_tab, "Network Architecture")
+        self.tab_widget.addTab(self.diagnostics_scroll, "Diagnostics")
         
         layout.addWidget(self.tab_widget)
         group.setLayout(layout)
@@ -723,12 +833,18 @@ class MainWindow(QMainWindow):
         
         self.load_button.setEnabled(False)
         self.train_button.setEnabled(False)
+        self.stop_button.setEnabled(True)
         
         self.training_thread = TrainingThread(self.csv_path, epochs, learning_rate)
         self.training_thread.update_progress.connect(self.update_training_progress)
         self.training_thread.training_complete.connect(self.training_finished)
         self.training_thread.start()
     
+    def stop_training(self):
+        if self.training_thread is not None:
+            self.stop_button.setEnabled(False)
+            self.training_thread.stop()
+    
     def update_training_progress(self, epoch, accuracy, loss):
         epochs_total = int(self.epoch_input.text())
         progress = int((epoch / epochs_total) * 100)
@@ -781,7 +897,8 @@ class MainWindow(QMainWindow):
     def training_finished(self, success, final_accuracy):
         self.load_button.setEnabled(True)
         self.train_button.setEnabled(True)
-        self.predict_button.setEnabled(True)
+        self.stop_button.setEnabled(False)
+        self.predict_button.setEnabled(os.path.exists(self.model_path))
         
         # Stop animation when training completes
         if self.animation is not None:
@@ -795,13 +912,20 @@ class MainWindow(QMainWindow):
             
             if hasattr(self.training_thread, 'plot_path'):
                 self.display_final_plot(self.training_thread.plot_path)
+            if hasattr(self.training_thread, 'diagnostics_dir'):
+                self.display_diagnostics(self.training_thread.diagnostics_dir)
+        elif getattr(self.training_thread, 'cancelled', False):
+            QMessageBox.information(self, "Stopped", "Training was stopped. The previous model was kept.")
         else:
             QMessageBox.critical(self, "Error", "Training failed. Check your data and parameters.")
     
     def predict(self):
-        # Ensure model exists
-        self.model_path = os.path.join(os.path.dirname(os.path.abspath(__file__)), "trained_model.bin")
-        if not os.path.exists(self.model_path):
+        # Ensure model exists, preferring the production model of the registry
+        project_dir = os.path.dirname(os.path.abspath(__file__))
+        registry_dir = os.path.join(project_dir, "registry")
+        use_registry = os.path.exists(os.path.join(registry_dir, "airquality", "index.json"))
+        self.model_path = os.path.join(project_dir, "trained_model.bin")
+        if not use_registry and not os.path.exists(self.model_path):
             QMessageBox.warning(self, "Warning", "No trained model found. Please train the model first.")
             return
         
@@ -813,34 +937,43 @@ class MainWindow(QMainWindow):
             o3 = float(self.o3_input.text())
             no2 = float(self.no2_input.text())
             
-            # Convert model path to bytes for Rust
-            model_path_bytes = self.model_path.encode('utf-8')
-            
             # Call Rust prediction function
-            prediction_ptr = rust_lib.predict_air_quality(
-                c_double(pm10),
-                c_double(so2),
-                c_double(co),
-                c_double(o3),
-                c_double(no2),
-                model_path_bytes
-            )
+            reading = [c_double(pm10), c_double(so2), c_double(co), c_double(o3), c_double(no2)]
+            if use_registry:
+                prediction_ptr = rust_lib.predict_air_quality_production(
+                    *reading,
+                    registry_dir.encode('utf-8'),
+                    b"airquality"
+                )
+            else:
+                prediction_ptr = rust_lib.predict_air_quality(
+                    *reading,
+                    self.model_path.encode('utf-8')
+                )
             
             if not prediction_ptr:
                 QMessageBox.critical(self, "Error", "Prediction failed")
                 return
                 
-            prediction = prediction_ptr.contents
-            
-            # Get probabilities array
-            prob_array = np.ctypeslib.as_array(
-                prediction.probabilities,
-                shape=(prediction.probabilities_len,)
-            ).copy()
+            # Copy everything out so the result is freed whatever happens next
+            try:
+                prediction = prediction_ptr.contents
+                prob_array = np.ctypeslib.as_array(
+                    prediction.probabilities,
+                    shape=(prediction.probabilities_len,)
+                ).copy()
+                warnings = prediction.warnings
+                warning_messages = prediction.warning_messages.decode('utf-8') if prediction.warning_messages else None
+                predicted_class = prediction.predicted_class
+            finally:
+                rust_lib.free_prediction_result(prediction_ptr)
+            if warnings & 1:
+                # Values that are not finite cannot be classified
+                QMessageBox.warning(self, "Warning", warning_messages or "The reading holds values that are not numbers")
+                return
             
             # Determine category
             category = "UNKNOWN"
-            predicted_class = prediction.predicted_class
             
             if predicted_class == 0:
                 category = "BAIK"
@@ -979,8 +1112,8 @@ class MainWindow(QMainWindow):
             self.right_layout.addWidget(self.image_label)
             self.right_layout.addStretch()
             
-            # Free memory
-            rust_lib.free_prediction_result(prediction_ptr)
+            if warning_messages:
+                QMessageBox.warning(self, "Unusual Reading", f"The prediction may be unreliable:\n{warning_messages}")
             
         except ValueError:
             QMessageBox.warning(self, "Warning", "Please enter valid numbers for all input fields")
@@ -993,6 +1126,17 @@ class MainWindow(QMainWindow):
         except Exception as e:
             print(f"Error displaying final plot: {e}")
 
+    def display_diagnostics(self, diagnostics_dir):
+        container = QWidget()
+        layout = QVBoxLayout(container)
+        for name in ("confusion_matrix", "roc", "precision_recall", "calibration", "probabilities"):
+            path = os.path.join(diagnostics_dir, name + ".png")
+            if os.path.exists(path):
+                label = QLabel()
+                label.setPixmap(QPixmap(path).scaledToWidth(700, Qt.SmoothTransformation))
+                layout.addWidget(label)
+        self.diagnostics_scroll.setWidget(container)
+
 if __name__ == "__main__":
     app = QApplication(sys.argv)
     window = MainWindow()
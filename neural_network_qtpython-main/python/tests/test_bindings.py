@@ -0,0 +1,98 @@
+import os
+
+import numpy as np
+import pytest
+
+import airquality_predictor as ap
+
+CSV_PATH = os.path.join(os.path.dirname(__file__), "..", "..", "airquality.csv")
+
+
+@pytest.fixture(scope="module")
+def model(tmp_path_factory):
+    plot_path = tmp_path_factory.mktemp("plots") / "training_plot.png"
+    return ap.train(CSV_PATH, epochs=20, plot_path=str(plot_path))
+
+
+def test_predict_returns_class_and_probabilities(model):
+    cls, probs = ap.predict(model, np.array([50.0, 20.0, 25.0, 50.0, 15.0]))
+    assert 0 <= cls < len(ap.CLASSES)
+    assert probs.shape == (3,)
+    assert probs.sum() == pytest.approx(1.0)
+
+
+def test_predict_batch_matches_predict(model):
+    x = np.array([[27.0, 2.0, 19.0, 20.0, 9.0], [120.0, 40.0, 60.0, 150.0, 40.0]])
+    classes, probs = ap.predict_batch(model, x)
+    assert classes.shape == (2,)
+    assert probs.shape == (2, 3)
+    single_cls, single_probs = ap.predict(model, x[1])
+    assert classes[1] == single_cls
+    np.testing.assert_allclose(probs[1], single_probs)
+
+
+def test_evaluate_csv_and_arrays_agree(model):
+    x = np.array([[27.0, 2.0, 19.0, 20.0, 9.0], [22.0, 2.0, 16.0, 15.0, 6.0]])
+    result = ap.evaluate(model, x, np.array([0, 0]))
+    assert result["samples"] == 2
+    assert result["confusion_matrix"].shape == (3, 3)
+    assert result["confusion_matrix"].sum() == 2
+
+    result = ap.evaluate(model, CSV_PATH)
+    assert 0.0 <= result["accuracy"] <= 1.0
+
+
+def test_write_diagnostics_returns_plot_paths(model, tmp_path):
+    paths = ap.write_diagnostics(model, CSV_PATH, str(tmp_path), format="svg")
+    assert len(paths) == 5
+    assert all(p.endswith(".svg") and os.path.getsize(p) > 0 for p in paths)
+
+
+def test_write_report_embeds_svgs(model, tmp_path):
+    path = tmp_path / "report.html"
+    ap.write_report(model, CSV_PATH, str(path))
+    html = path.read_text()
+    assert "<svg" in html and "Feature importance" in html
+
+
+def test_save_and_load_round_trip(model, tmp_path):
+    path = str(tmp_path / "model.bin")
+    model.save(path)
+    loaded = ap.load_model(path)
+    assert loaded.final_accuracy == model.final_accuracy
+    np.testing.assert_allclose(loaded.mean, model.mean)
+
+
+def test_partial_fit_updates_in_place(model, tmp_path):
+    path = str(tmp_path / "model.bin")
+    model.save(path)
+    copy = ap.load_model(path)
+    replay = str(tmp_path / "model.replay")
+    report = copy.partial_fit(CSV_PATH, replay_path=replay, epochs=2, seed=1)
+    assert report["samples"] > 0 and report["replayed"] > 0
+    assert os.path.getsize(replay) > 0
+    assert not np.array_equal(ap.predict(copy, np.array([50.0, 20.0, 25.0, 50.0, 15.0]))[1],
+                              ap.predict(model, np.array([50.0, 20.0, 25.0, 50.0, 15.0]))[1])
+
+
+def test_load_production_model_needs_a_production_version(tmp_path):
+    with pytest.raises(IOError):
+        ap.load_production_model(str(tmp_path), "airquality")
+
+
+def test_errors_raise_python_exceptions(model):
+    with pytest.raises(ValueError):
+        ap.predict(model, np.array([1.0, 2.0]))
+    with pytest.raises(ValueError):
+        ap.evaluate(model, np.zeros((2, 5)))
+    with pytest.raises(OSError):
+        ap.load_model("does-not-exist.bin")
+    with pytest.raises(RuntimeError):
+        ap.train("does-not-exist.csv", epochs=1)
+
+
+def test_validate_input_flags_impossible_readings(model):
+    assert ap.validate_input(model, np.array([50.0, 20.0, 25.0, 50.0, 15.0]))["warnings"] == []
+    report = ap.validate_input(model, np.array([-5.0, 20.0, 2500.0, 50.0, 15.0]))
+    assert len(report["warnings"]) == 3
+    assert report["ood_score"] > 10
@@ -0,0 +1,56 @@
+use airquality_predictor::server::{ModelServer, ServerConfig};
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "Usage: server [--model PATH | --registry DIR [--name NAME]] [--addr HOST:PORT] [--workers N] [--reload-interval SECS] [--drift-window N]";
+
+fn parse_args() -> Result<ServerConfig, String> {
+    let mut config = ServerConfig::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--model" => config.model_path = PathBuf::from(value),
+            "--registry" => config.registry = Some(PathBuf::from(value)),
+            "--name" => config.model_name = value,
+            "--addr" => config.addr = value,
+            "--workers" => config.workers = value.parse().map_err(|_| format!("invalid worker count: {}", value))?,
+            "--drift-window" => config.drift_window = value.parse().map_err(|_| format!("invalid drift window: {}", value))?,
+            "--reload-interval" => {
+                // The file is checked at most every 100 ms, so shorter intervals change nothing
+                config.reload_interval = value.parse()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or_else(|| format!("invalid reload interval: {}", value))?
+                    .max(Duration::from_millis(100));
+            }
+            _ => return Err(format!("unknown argument: {}", flag)),
+        }
+    }
+
+    Ok(config)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Usage errors exit with 2, like the airquality CLI
+    let config = parse_args().unwrap_or_else(|e| {
+        eprintln!("error: {}\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+    let server = ModelServer::bind(config)?;
+
+    let model_path = server.state().model.read().unwrap().path.clone();
+    match server.local_addr() {
+        Some(addr) => println!("Serving {} on http://{}", model_path.display(), addr),
+        None => println!("Serving {}", model_path.display()),
+    }
+    server.run();
+
+    Ok(())
+}
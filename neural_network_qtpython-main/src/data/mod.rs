@@ -1 +1,2 @@
-pub mod preprocessing;
\ No newline at end of file
+pub mod preprocessing;
+pub mod time_series;
\ No newline at end of file
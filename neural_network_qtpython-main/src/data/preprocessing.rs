@@ -1,50 +1,237 @@
 use ndarray::{Array1, Array2, Axis};
-use csv::Reader;
+use csv::{Reader, StringRecord};
+use rand::seq::SliceRandom;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use crate::model::float::Float;
 use std::fs::File;
 use std::error::Error;
 
-#[derive(Serialize, Deserialize)]
-pub struct DataStats {
-    pub mean: Array1<f64>,
-    pub std: Array1<f64>,
+/// Input columns, in the order the network expects them.
+pub const FEATURES: [&str; 5] = ["pm10", "so2", "co", "o3", "no2"];
+
+/// Air quality categories, in the order of the network outputs.
+pub const CLASSES: [&str; 3] = ["BAIK", "SEDANG", "TIDAK SEHAT"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalization {
+    /// Subtract the mean and divide by the standard deviation of the training rows.
+    #[default]
+    Standard,
+    /// Feed raw values to the network.
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "F: Float")]
+pub struct DataStats<F: Float = f64> {
+    pub mean: Array1<F>,
+    pub std: Array1<F>,
 }
 
-pub fn load_and_preprocess_data(csv_path: &str) -> Result<(Array2<f64>, Array2<f64>, DataStats), Box<dyn Error>> {
-    // Read dataset
+impl DataStats {
+    pub fn from_data(x: &Array2<f64>) -> Self {
+        DataStats {
+            mean: x.mean_axis(Axis(0)).unwrap(),
+            std: x.std_axis(Axis(0), 1.0),
+        }
+    }
+
+    /// Statistics for the given normalization. Constant columns get a
+    /// standard deviation of 1 so they do not divide by zero.
+    pub fn fit(x: &Array2<f64>, normalization: Normalization) -> Self {
+        match normalization {
+            Normalization::Standard => {
+                let stats = Self::from_data(x);
+                DataStats {
+                    std: stats.std.mapv(|s| if s > 0.0 && s.is_finite() { s } else { 1.0 }),
+                    ..stats
+                }
+            }
+            Normalization::None => DataStats {
+                mean: Array1::zeros(x.ncols()),
+                std: Array1::ones(x.ncols()),
+            },
+        }
+    }
+}
+
+impl<F: Float> DataStats<F> {
+    pub fn normalize(&self, x: &Array2<F>) -> Array2<F> {
+        (x - &self.mean) / &self.std
+    }
+
+    /// Inverse of `normalize`.
+    pub fn denormalize(&self, x: &Array2<F>) -> Array2<F> {
+        x * &self.std + &self.mean
+    }
+
+    /// The same statistics converted to another precision.
+    pub fn cast<T: Float>(&self) -> DataStats<T> {
+        DataStats {
+            mean: self.mean.mapv(|v| T::cast(v.widen())),
+            std: self.std.mapv(|v| T::cast(v.widen())),
+        }
+    }
+}
+
+/// Normalized inputs, one-hot targets and the statistics used for normalization.
+pub type Dataset = (Array2<f64>, Array2<f64>, DataStats);
+
+/// Raw inputs and one-hot targets, split into training and validation parts.
+pub struct DataSplit {
+    pub x_train: Array2<f64>,
+    pub y_train: Array2<f64>,
+    pub x_val: Array2<f64>,
+    pub y_val: Array2<f64>,
+}
+
+pub fn load_data(csv_path: &str) -> Result<(Array2<f64>, Array2<f64>), Box<dyn Error>> {
+    let features: Vec<String> = FEATURES.iter().map(|f| f.to_string()).collect();
+    let classes: Vec<String> = CLASSES.iter().map(|c| c.to_string()).collect();
+    load_columns(csv_path, &features, "kategori", &classes)
+}
+
+/// Reads the named feature columns and one-hot encodes the `label` column.
+/// Rows with a non-numeric feature or a label outside `classes` are rejected.
+pub fn load_columns(
+    csv_path: &str,
+    features: &[String],
+    label: &str,
+    classes: &[String],
+) -> Result<(Array2<f64>, Array2<f64>), Box<dyn Error>> {
     let file = File::open(csv_path)?;
     let mut rdr = Reader::from_reader(file);
+    let feature_columns = column_indices(rdr.headers()?, features)?;
+    let label_column = column_indices(rdr.headers()?, &[label.to_string()])?[0];
+
     let mut inputs = Vec::new();
     let mut outputs = Vec::new();
+    let mut rows = 0;
 
-    for result in rdr.records() {
+    for (line, result) in rdr.records().enumerate() {
         let record = result?;
+        inputs.extend(parse_features(&record, &feature_columns, features, line + 1)?);
 
-        // Parse data
-        let pm10: f64 = record[0].parse().unwrap_or(0.0);
-        let so2: f64 = record[1].parse().unwrap_or(0.0);
-        let co: f64 = record[2].parse().unwrap_or(0.0);
-        let o3: f64 = record[3].parse().unwrap_or(0.0);
-        let no2: f64 = record[4].parse().unwrap_or(0.0);
-
-        let category = &record[5];
-        inputs.push(vec![pm10, so2, co, o3, no2]);
-        outputs.push(match category {
-            "BAIK" => vec![1.0, 0.0, 0.0],
-            "SEDANG" => vec![0.0, 1.0, 0.0],
-            "TIDAK SEHAT" => vec![0.0, 0.0, 1.0],
-            _ => vec![0.0, 0.0, 0.0]
-        });
+        let category = record.get(label_column).unwrap_or("").trim();
+        let class = classes.iter().position(|c| c == category)
+            .ok_or_else(|| format!("row {}: unknown {} '{}'", line + 1, label, category))?;
+        outputs.extend((0..classes.len()).map(|i| if i == class { 1.0 } else { 0.0 }));
+        rows += 1;
     }
 
     // Convert to ndarray
-    let x = Array2::from_shape_vec((inputs.len(), 5), inputs.concat())?;
-    let y = Array2::from_shape_vec((outputs.len(), 3), outputs.concat())?;
+    let x = Array2::from_shape_vec((rows, features.len()), inputs)?;
+    let y = Array2::from_shape_vec((rows, classes.len()), outputs)?;
+
+    Ok((x, y))
+}
+
+/// Reads the named feature columns and the numeric `targets` columns, for
+/// regression.
+pub fn load_targets(csv_path: &str, features: &[String], targets: &[String]) -> Result<(Array2<f64>, Array2<f64>), Box<dyn Error>> {
+    let file = File::open(csv_path)?;
+    let mut rdr = Reader::from_reader(file);
+    let feature_columns = column_indices(rdr.headers()?, features)?;
+    let target_columns = column_indices(rdr.headers()?, targets)?;
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut rows = 0;
+
+    for (line, result) in rdr.records().enumerate() {
+        let record = result?;
+        inputs.extend(parse_features(&record, &feature_columns, features, line + 1)?);
+        outputs.extend(parse_features(&record, &target_columns, targets, line + 1)?);
+        rows += 1;
+    }
+
+    let x = Array2::from_shape_vec((rows, features.len()), inputs)?;
+    let y = Array2::from_shape_vec((rows, targets.len()), outputs)?;
+    Ok((x, y))
+}
+
+/// Reads the named feature columns of a CSV file. A label column is not required.
+pub fn load_features(csv_path: &str, features: &[String]) -> Result<Array2<f64>, Box<dyn Error>> {
+    let file = File::open(csv_path)?;
+    let mut rdr = Reader::from_reader(file);
+    let columns = column_indices(rdr.headers()?, features)?;
+    let mut inputs = Vec::new();
+    let mut rows = 0;
+
+    for (line, result) in rdr.records().enumerate() {
+        inputs.extend(parse_features(&result?, &columns, features, line + 1)?);
+        rows += 1;
+    }
+
+    Ok(Array2::from_shape_vec((rows, features.len()), inputs)?)
+}
+
+pub(crate) fn column_indices(headers: &StringRecord, names: &[String]) -> Result<Vec<usize>, Box<dyn Error>> {
+    names.iter()
+        .map(|name| {
+            headers.iter().position(|h| h.trim() == name)
+                .ok_or_else(|| format!("missing column '{}'", name).into())
+        })
+        .collect()
+}
+
+pub(crate) fn parse_features(record: &StringRecord, columns: &[usize], names: &[String], row: usize) -> Result<Vec<f64>, Box<dyn Error>> {
+    columns.iter().zip(names)
+        .map(|(&column, name)| {
+            let value = record.get(column).unwrap_or("");
+            value.trim().parse::<f64>()
+                .map_err(|_| format!("row {}: invalid {} value '{}'", row, name, value).into())
+        })
+        .collect()
+}
+
+pub fn load_and_preprocess_data(csv_path: &str) -> Result<Dataset, Box<dyn Error>> {
+    let (x, y) = load_data(csv_path)?;
 
     // Normalize input
-    let mean = x.mean_axis(Axis(0)).unwrap();
-    let std = x.std_axis(Axis(0), 1.0);
-    let x_normalized = (x - &mean) / &std;
+    let stats = DataStats::from_data(&x);
+    let x_normalized = stats.normalize(&x);
+
+    Ok((x_normalized, y, stats))
+}
 
-    Ok((x_normalized, y, DataStats { mean, std }))
-}
\ No newline at end of file
+/// Shuffles the rows and holds out `validation_split` of them for validation.
+pub fn train_validation_split<R: Rng>(x: &Array2<f64>, y: &Array2<f64>, validation_split: f64, rng: &mut R) -> DataSplit {
+    let mut indices: Vec<usize> = (0..x.nrows()).collect();
+    indices.shuffle(rng);
+
+    let n_val = (x.nrows() as f64 * validation_split.clamp(0.0, 1.0)).round() as usize;
+    let (val_idx, train_idx) = indices.split_at(n_val);
+
+    DataSplit {
+        x_train: x.select(Axis(0), train_idx),
+        y_train: y.select(Axis(0), train_idx),
+        x_val: x.select(Axis(0), val_idx),
+        y_val: y.select(Axis(0), val_idx),
+    }
+}
+
+/// Shuffles the rows and splits them into `folds` parts. Split `i` validates
+/// on part `i` and trains on the rest.
+pub fn k_fold_splits<R: Rng>(x: &Array2<f64>, y: &Array2<f64>, folds: usize, rng: &mut R) -> Vec<DataSplit> {
+    let mut indices: Vec<usize> = (0..x.nrows()).collect();
+    indices.shuffle(rng);
+
+    let folds = folds.clamp(1, indices.len().max(1));
+    (0..folds)
+        .map(|fold| {
+            let mut val_idx = Vec::new();
+            let mut train_idx = Vec::new();
+            for (position, &row) in indices.iter().enumerate() {
+                if position % folds == fold { val_idx.push(row) } else { train_idx.push(row) }
+            }
+            DataSplit {
+                x_train: x.select(Axis(0), &train_idx),
+                y_train: y.select(Axis(0), &train_idx),
+                x_val: x.select(Axis(0), &val_idx),
+                y_val: y.select(Axis(0), &val_idx),
+            }
+        })
+        .collect()
+}
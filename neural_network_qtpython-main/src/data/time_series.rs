@@ -0,0 +1,266 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use csv::Reader;
+use ndarray::{Array2, Axis};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+
+use crate::data::preprocessing::{column_indices, parse_features, DataSplit};
+
+/// Turns a timestamped CSV file into forecasting samples: the features of
+/// each row, their values `lags` steps earlier and their mean and standard
+/// deviation over the last `rolling_windows` steps predict the label or
+/// targets `horizon` steps later.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeSeriesConfig {
+    /// Column with an RFC 3339 or `YYYY-MM-DD HH:MM[:SS]` timestamp, a date,
+    /// or Unix seconds.
+    pub timestamp: String,
+    /// Column naming the station of each row. Series of different stations
+    /// never mix; unset treats the file as one station.
+    pub station: Option<String>,
+    /// Seconds between consecutive readings of a station.
+    pub step_seconds: u64,
+    /// Steps ahead to forecast.
+    pub horizon: usize,
+    /// Earlier readings added as features, in steps.
+    pub lags: Vec<usize>,
+    /// Window lengths, in steps, of the rolling mean and standard deviation
+    /// added as features. Windows end at the current reading.
+    pub rolling_windows: Vec<usize>,
+}
+
+impl Default for TimeSeriesConfig {
+    fn default() -> Self {
+        TimeSeriesConfig {
+            timestamp: "timestamp".to_string(),
+            station: None,
+            step_seconds: 3600,
+            horizon: 1,
+            lags: Vec::new(),
+            rolling_windows: Vec::new(),
+        }
+    }
+}
+
+impl TimeSeriesConfig {
+    /// Names of the generated network inputs, in order.
+    pub fn input_names(&self, features: &[String]) -> Vec<String> {
+        let mut names = features.to_vec();
+        for lag in &self.lags {
+            names.extend(features.iter().map(|f| format!("{}_lag{}", f, lag)));
+        }
+        for window in &self.rolling_windows {
+            names.extend(features.iter().map(|f| format!("{}_mean{}", f, window)));
+            names.extend(features.iter().map(|f| format!("{}_std{}", f, window)));
+        }
+        names
+    }
+}
+
+/// What to read besides the features: one-hot encoded labels or numeric targets.
+pub enum Outputs<'a> {
+    Label { column: &'a str, classes: &'a [String] },
+    Targets(&'a [String]),
+}
+
+/// Raw readings of a timestamped CSV file, in file order.
+pub struct Series {
+    pub stations: Vec<String>,
+    /// Unix seconds.
+    pub timestamps: Vec<i64>,
+    pub features: Array2<f64>,
+    /// Labels or targets, when requested.
+    pub outputs: Option<Array2<f64>>,
+}
+
+/// Forecasting samples built from a series. `timestamps` are the times of the
+/// latest reading each sample sees, not of the forecast.
+pub struct Windows {
+    pub stations: Vec<String>,
+    pub timestamps: Vec<i64>,
+    pub x: Array2<f64>,
+    /// Labels or targets `horizon` steps ahead, when the series has them.
+    pub y: Option<Array2<f64>>,
+}
+
+pub fn load_series(
+    csv_path: &str,
+    config: &TimeSeriesConfig,
+    features: &[String],
+    outputs: Option<Outputs>,
+) -> Result<Series, Box<dyn Error>> {
+    let mut rdr = Reader::from_reader(File::open(csv_path)?);
+    let headers = rdr.headers()?.clone();
+    let feature_columns = column_indices(&headers, features)?;
+    let timestamp_column = column_indices(&headers, std::slice::from_ref(&config.timestamp))?[0];
+    let station_column = match &config.station {
+        Some(station) => Some(column_indices(&headers, std::slice::from_ref(station))?[0]),
+        None => None,
+    };
+    let output_columns = match &outputs {
+        Some(Outputs::Label { column, .. }) => column_indices(&headers, &[column.to_string()])?,
+        Some(Outputs::Targets(targets)) => column_indices(&headers, targets)?,
+        None => Vec::new(),
+    };
+
+    let mut stations = Vec::new();
+    let mut timestamps = Vec::new();
+    let mut inputs = Vec::new();
+    let mut values = Vec::new();
+    for (line, result) in rdr.records().enumerate() {
+        let record = result?;
+        let row = line + 1;
+        inputs.extend(parse_features(&record, &feature_columns, features, row)?);
+        let timestamp = record.get(timestamp_column).unwrap_or("");
+        timestamps.push(parse_timestamp(timestamp).map_err(|e| format!("row {}: {}", row, e))?);
+        stations.push(station_column.and_then(|c| record.get(c)).unwrap_or("").trim().to_string());
+
+        match &outputs {
+            Some(Outputs::Label { column, classes }) => {
+                let category = record.get(output_columns[0]).unwrap_or("").trim();
+                let class = classes.iter().position(|c| c == category)
+                    .ok_or_else(|| format!("row {}: unknown {} '{}'", row, column, category))?;
+                values.extend((0..classes.len()).map(|i| if i == class { 1.0 } else { 0.0 }));
+            }
+            Some(Outputs::Targets(targets)) => values.extend(parse_features(&record, &output_columns, targets, row)?),
+            None => {}
+        }
+    }
+
+    let rows = timestamps.len();
+    let output_size = match &outputs {
+        Some(Outputs::Label { classes, .. }) => classes.len(),
+        Some(Outputs::Targets(targets)) => targets.len(),
+        None => 0,
+    };
+    Ok(Series {
+        stations,
+        timestamps,
+        features: Array2::from_shape_vec((rows, features.len()), inputs)?,
+        outputs: match outputs {
+            Some(_) => Some(Array2::from_shape_vec((rows, output_size), values)?),
+            None => None,
+        },
+    })
+}
+
+/// Builds one sample per reading that has its full history and, when the
+/// series has outputs, a reading `horizon` steps later at the same station.
+/// Other readings are skipped, so gaps in a series only cost the samples
+/// that would need the missing readings.
+pub fn windows(series: &Series, config: &TimeSeriesConfig) -> Result<Windows, Box<dyn Error>> {
+    let step = config.step_seconds as i64;
+    let mut index: HashMap<(&str, i64), usize> = HashMap::with_capacity(series.timestamps.len());
+    for (row, (station, &timestamp)) in series.stations.iter().zip(&series.timestamps).enumerate() {
+        if index.insert((station.as_str(), timestamp), row).is_some() {
+            return Err(format!("row {}: duplicate timestamp for station '{}'", row + 1, station).into());
+        }
+    }
+    let at = |station: &str, timestamp: i64, offset: i64| index.get(&(station, timestamp + offset * step)).copied();
+
+    let features = series.features.ncols();
+    let inputs = features * (1 + config.lags.len() + 2 * config.rolling_windows.len());
+    let mut samples = Vec::new();
+    let mut x = Vec::new();
+    let mut y = Vec::new();
+    'rows: for (row, (station, &timestamp)) in series.stations.iter().zip(&series.timestamps).enumerate() {
+        let target = match &series.outputs {
+            Some(_) => match at(station, timestamp, config.horizon as i64) {
+                Some(target) => Some(target),
+                None => continue,
+            },
+            None => None,
+        };
+
+        let mut sample: Vec<f64> = Vec::with_capacity(inputs);
+        sample.extend(series.features.row(row).iter());
+        for &lag in &config.lags {
+            let Some(earlier) = at(station, timestamp, -(lag as i64)) else { continue 'rows };
+            sample.extend(series.features.row(earlier).iter());
+        }
+        for &window in &config.rolling_windows {
+            let mut rows = Vec::with_capacity(window);
+            for offset in 0..window {
+                let Some(earlier) = at(station, timestamp, -(offset as i64)) else { continue 'rows };
+                rows.push(earlier);
+            }
+            let block = series.features.select(Axis(0), &rows);
+            sample.extend(block.mean_axis(Axis(0)).unwrap().iter());
+            sample.extend(block.std_axis(Axis(0), 0.0).iter());
+        }
+
+        x.extend(sample);
+        if let (Some(outputs), Some(target)) = (&series.outputs, target) {
+            y.extend(outputs.row(target).iter());
+        }
+        samples.push(row);
+    }
+
+    let count = samples.len();
+    Ok(Windows {
+        stations: samples.iter().map(|&row| series.stations[row].clone()).collect(),
+        timestamps: samples.iter().map(|&row| series.timestamps[row]).collect(),
+        x: Array2::from_shape_vec((count, inputs), x)?,
+        y: match &series.outputs {
+            Some(outputs) => Some(Array2::from_shape_vec((count, outputs.ncols()), y)?),
+            None => None,
+        },
+    })
+}
+
+/// Splits samples in time: the latest `validation` fraction of them validates,
+/// and training samples whose forecast falls inside that period are dropped
+/// so that no validation reading leaks into training.
+pub fn chronological_split(windows: &Windows, validation: f64, config: &TimeSeriesConfig) -> DataSplit {
+    let y = windows.y.as_ref().expect("samples with outputs");
+    let mut order: Vec<usize> = (0..windows.timestamps.len()).collect();
+    order.sort_by_key(|&i| windows.timestamps[i]);
+
+    let n_val = (order.len() as f64 * validation.clamp(0.0, 1.0)).round() as usize;
+    let (train, val) = order.split_at(order.len() - n_val);
+    let lead = (config.horizon as u64 * config.step_seconds) as i64;
+    let train: Vec<usize> = match val.first() {
+        Some(&first) => {
+            let start = windows.timestamps[first];
+            train.iter().copied().filter(|&i| windows.timestamps[i] + lead < start).collect()
+        }
+        None => train.to_vec(),
+    };
+
+    DataSplit {
+        x_train: windows.x.select(Axis(0), &train),
+        y_train: y.select(Axis(0), &train),
+        x_val: windows.x.select(Axis(0), val),
+        y_val: y.select(Axis(0), val),
+    }
+}
+
+/// Parses an RFC 3339 timestamp, `YYYY-MM-DD HH:MM[:SS]` (also with a `T`),
+/// a plain date, or Unix seconds. Timestamps without an offset are taken as UTC.
+pub fn parse_timestamp(value: &str) -> Result<i64, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<i64>() {
+        return Ok(seconds);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.timestamp());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(time.and_utc().timestamp());
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
+        .map_err(|_| format!("invalid timestamp '{}'", value))
+}
+
+/// Formats Unix seconds as `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_timestamp(seconds: i64) -> String {
+    DateTime::from_timestamp(seconds, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| seconds.to_string())
+}
@@ -0,0 +1,41 @@
+use crate::ffi::c_str;
+use crate::training::diagnostics::diagnose_csv;
+use crate::utils::diagnostic_plots::write_diagnostic_plots;
+use crate::utils::io::load_model;
+use crate::utils::plot::PlotFormat;
+
+/// Evaluates the classifier at `model_path` on the labelled CSV file at
+/// `csv_path` and writes its confusion matrix, ROC, precision-recall,
+/// calibration and probability plots into `output_dir`, as SVG if `svg` is
+/// true and PNG otherwise.
+///
+/// Returns the number of plots written, or -1 on failure.
+///
+/// # Safety
+/// The path arguments must be valid nul-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn write_diagnostic_plots_for_model(
+    model_path: *const libc::c_char,
+    csv_path: *const libc::c_char,
+    output_dir: *const libc::c_char,
+    svg: bool,
+) -> i32 {
+    let (Some(model_path), Some(csv_path), Some(output_dir)) =
+        (unsafe { c_str(model_path) }, unsafe { c_str(csv_path) }, unsafe { c_str(output_dir) })
+    else {
+        println!("Failed to write diagnostic plots: invalid path argument");
+        return -1;
+    };
+
+    let format = if svg { PlotFormat::Svg } else { PlotFormat::Png };
+    let written = load_model(model_path)
+        .and_then(|model| diagnose_csv(&model, csv_path))
+        .and_then(|diagnostics| write_diagnostic_plots(&diagnostics, output_dir, format));
+    match written {
+        Ok(paths) => paths.len() as i32,
+        Err(e) => {
+            println!("Failed to write diagnostic plots: {}", e);
+            -1
+        }
+    }
+}
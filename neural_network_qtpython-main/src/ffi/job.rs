@@ -0,0 +1,161 @@
+use crate::ffi::c_str;
+use crate::ffi::training::TrainingProgress;
+use crate::training::job::{JobStatus, TrainingJob};
+use crate::training::config::TrainingConfig;
+use crate::utils::io::save_any_model;
+
+/// Starts training on a background thread and returns a job handle, or null
+/// if an argument is invalid. Release the handle with `training_job_free`.
+///
+/// # Safety
+/// The path arguments must be valid nul-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_start(
+    csv_path: *const libc::c_char,
+    epochs: i32,
+    plot_path: *const libc::c_char,
+) -> *mut TrainingJob {
+    let (Some(csv_path), Some(plot_path)) = (unsafe { c_str(csv_path) }, unsafe { c_str(plot_path) }) else {
+        println!("Failed to start training job: invalid path argument");
+        return std::ptr::null_mut();
+    };
+
+    let mut config = TrainingConfig::default();
+    config.data.path = csv_path.to_string();
+    config.schedule.epochs = epochs.max(0) as usize;
+    config.output.plot = plot_path.to_string();
+    Box::into_raw(Box::new(TrainingJob::start(config)))
+}
+
+/// Starts training as described by a TOML or JSON config file. Returns null
+/// if the file cannot be read or is invalid.
+///
+/// # Safety
+/// `config_path` must be a valid nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_start_from_config(config_path: *const libc::c_char) -> *mut TrainingJob {
+    let Some(config_path) = (unsafe { c_str(config_path) }) else {
+        println!("Failed to start training job: invalid config path argument");
+        return std::ptr::null_mut();
+    };
+
+    match TrainingConfig::from_file(config_path) {
+        Ok(config) => Box::into_raw(Box::new(TrainingJob::start(config))),
+        Err(e) => {
+            println!("Failed to start training job: {}", e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Returns the job status: 0 running, 1 paused, 2 completed, 3 cancelled,
+/// 4 failed, or -1 for a null handle.
+///
+/// # Safety
+/// `job` must be null or a live handle from `training_job_start`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_status(job: *const TrainingJob) -> i32 {
+    match unsafe { job.as_ref() } {
+        Some(job) => job.status() as i32,
+        None => -1,
+    }
+}
+
+/// Writes the latest training progress into `out`. Returns `false` if no
+/// evaluation step has run yet.
+///
+/// # Safety
+/// `job` must be null or a live handle from `training_job_start`, and `out`
+/// must be null or point to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_latest_metrics(
+    job: *const TrainingJob,
+    out: *mut TrainingProgress,
+) -> bool {
+    let (Some(job), false) = (unsafe { job.as_ref() }, out.is_null()) else {
+        return false;
+    };
+    match job.latest_metrics() {
+        Some(metrics) => {
+            unsafe { *out = TrainingProgress::from(&metrics) };
+            true
+        }
+        None => false,
+    }
+}
+
+/// # Safety
+/// `job` must be null or a live handle from `training_job_start`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_pause(job: *const TrainingJob) {
+    if let Some(job) = unsafe { job.as_ref() } {
+        job.pause();
+    }
+}
+
+/// # Safety
+/// `job` must be null or a live handle from `training_job_start`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_resume(job: *const TrainingJob) {
+    if let Some(job) = unsafe { job.as_ref() } {
+        job.resume();
+    }
+}
+
+/// # Safety
+/// `job` must be null or a live handle from `training_job_start`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_cancel(job: *const TrainingJob) {
+    if let Some(job) = unsafe { job.as_ref() } {
+        job.cancel();
+    }
+}
+
+/// Saves the model of a completed job to `model_path` and writes its final
+/// accuracy into `accuracy`. Returns `false` if the job has not completed,
+/// the model was already collected, or the file could not be written.
+///
+/// # Safety
+/// `job` must be null or a live handle from `training_job_start`,
+/// `model_path` a valid nul-terminated string and `accuracy` null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_save_model(
+    job: *const TrainingJob,
+    model_path: *const libc::c_char,
+    accuracy: *mut f64,
+) -> bool {
+    let (Some(job), Some(model_path)) = (unsafe { job.as_ref() }, unsafe { c_str(model_path) }) else {
+        return false;
+    };
+    if job.status() != JobStatus::Completed {
+        return false;
+    }
+    let Some(model) = job.take_model() else {
+        return false;
+    };
+
+    if !accuracy.is_null() {
+        unsafe { *accuracy = model.final_accuracy() };
+    }
+    match save_any_model(model_path, &model) {
+        Ok(()) => true,
+        Err(e) => {
+            println!("Failed to save model: {}", e);
+            false
+        }
+    }
+}
+
+/// Cancels the job if it is still running, waits for its thread and frees it.
+///
+/// # Safety
+/// `job` must be null or a handle from `training_job_start` that has not been
+/// freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn training_job_free(job: *mut TrainingJob) {
+    if !job.is_null() {
+        unsafe {
+            let _ = Box::from_raw(job);
+        }
+    }
+}
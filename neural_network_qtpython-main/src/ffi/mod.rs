@@ -0,0 +1,18 @@
+pub mod training;
+pub mod prediction;
+pub mod job;
+pub mod parallel;
+pub mod diagnostics;
+
+use std::ffi::CStr;
+
+/// Borrows a C string as `&str`, returning `None` for null or non-UTF-8 input.
+///
+/// # Safety
+/// `ptr` must be null or point to a valid nul-terminated string that outlives `'a`.
+pub(crate) unsafe fn c_str<'a>(ptr: *const libc::c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
@@ -0,0 +1,20 @@
+use crate::utils::parallel;
+
+/// Sets the number of threads used for training and batch prediction; 0 uses
+/// every core. Returns `false` if the thread pool could not be created.
+#[unsafe(no_mangle)]
+pub extern "C" fn set_thread_count(threads: i32) -> bool {
+    match parallel::set_threads(threads.max(0) as usize) {
+        Ok(()) => true,
+        Err(e) => {
+            println!("Failed to set thread count: {}", e);
+            false
+        }
+    }
+}
+
+/// Returns the number of threads used for training and batch prediction.
+#[unsafe(no_mangle)]
+pub extern "C" fn get_thread_count() -> i32 {
+    parallel::threads() as i32
+}
@@ -0,0 +1,158 @@
+use crate::ffi::c_str;
+use crate::training::registry::load_production_model;
+use crate::training::trainer::TrainedModel;
+use crate::training::validation::{CheckedPrediction, ValidationRules};
+use crate::utils::io::load_model;
+use std::error::Error;
+use std::ffi::CString;
+
+/// A classification as returned to C, with the warnings about its reading.
+///
+/// `warnings` and `input_warnings` hold `InputIssue` flags: 1 for a value
+/// that is not finite, 2 below the physical minimum, 4 above the maximum and
+/// 8 out of the training distribution. `ood_score` is the largest distance of
+/// an input from its training range, in training standard deviations.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiPredictionResult {
+    /// Index of the predicted class, or -1 if there is none.
+    pub predicted_class: i32,
+    pub probabilities: *mut f64,
+    pub probabilities_len: i32,
+    /// Flags of every warning, combined; 0 if the reading looks sound.
+    pub warnings: u32,
+    /// Flags per input, in the order pm10, so2, co, o3, no2.
+    pub input_warnings: [u32; 5],
+    pub ood_score: f64,
+    /// The warnings as text, one per line, or null if there are none.
+    pub warning_messages: *mut libc::c_char,
+}
+
+impl From<CheckedPrediction> for FfiPredictionResult {
+    fn from(checked: CheckedPrediction) -> Self {
+        let CheckedPrediction { prediction, validation } = checked;
+        let probabilities_len = prediction.probabilities.len() as i32;
+        let messages: Vec<String> = validation.warnings.iter().map(ToString::to_string).collect();
+        FfiPredictionResult {
+            predicted_class: prediction.class,
+            probabilities: Box::into_raw(prediction.probabilities.into_boxed_slice()) as *mut f64,
+            probabilities_len,
+            warnings: validation.flags(),
+            input_warnings: std::array::from_fn(|input| validation.input_flags(input)),
+            ood_score: validation.ood_score,
+            warning_messages: match messages.is_empty() {
+                true => std::ptr::null_mut(),
+                false => CString::new(messages.join("\n")).map_or(std::ptr::null_mut(), CString::into_raw),
+            },
+        }
+    }
+}
+
+/// Loads the model at `model_path` and classifies one reading.
+///
+/// Readings outside the physical bounds of a pollutant or far from the
+/// training data are still classified; the result carries the warnings.
+/// Returns null if the model could not be loaded. The result must be released
+/// with `free_prediction_result`.
+///
+/// # Safety
+/// `model_path` must be a valid nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn predict_air_quality(
+    pm10: f64,
+    so2: f64,
+    co: f64,
+    o3: f64,
+    no2: f64,
+    model_path: *const libc::c_char,
+) -> *mut FfiPredictionResult {
+    let Some(model_path) = (unsafe { c_str(model_path) }) else {
+        println!("Failed to read model file: invalid path argument");
+        return std::ptr::null_mut();
+    };
+
+    println!("Loading model from: {}", model_path);
+
+    predict_with(load_model(model_path), [pm10, so2, co, o3, no2])
+}
+
+/// Classifies one reading with the production version of model `name` in the
+/// registry at `registry_dir`, so that callers follow promotions and
+/// rollbacks instead of a fixed model file.
+///
+/// Returns null if there is no loadable production model. The result must be
+/// released with `free_prediction_result`.
+///
+/// # Safety
+/// `registry_dir` and `name` must be valid nul-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn predict_air_quality_production(
+    pm10: f64,
+    so2: f64,
+    co: f64,
+    o3: f64,
+    no2: f64,
+    registry_dir: *const libc::c_char,
+    name: *const libc::c_char,
+) -> *mut FfiPredictionResult {
+    let (Some(registry_dir), Some(name)) = (unsafe { c_str(registry_dir) }, unsafe { c_str(name) }) else {
+        println!("Failed to read model registry: invalid path or name argument");
+        return std::ptr::null_mut();
+    };
+
+    println!("Loading production model {} from registry: {}", name, registry_dir);
+
+    predict_with(load_production_model(registry_dir, name), [pm10, so2, co, o3, no2])
+}
+
+fn predict_with(model: Result<TrainedModel, Box<dyn Error>>, reading: [f64; 5]) -> *mut FfiPredictionResult {
+    let trained_model = match model {
+        Ok(model) => {
+            println!("Model loaded successfully");
+            println!("Model weights: {:?}", model.network);
+            println!("Normalization params - mean: {:?}, std: {:?}", model.stats.mean, model.stats.std);
+            model
+        }
+        Err(e) => {
+            println!("Failed to load model: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    if trained_model.features().len() != 5 || trained_model.is_regression() || trained_model.config.time_series.is_some() {
+        println!("Model is not a classifier of single pm10, so2, co, o3, no2 readings");
+        return std::ptr::null_mut();
+    }
+
+    let checked = match trained_model.predict_checked(&reading, &ValidationRules::default()) {
+        Ok(checked) => checked,
+        Err(e) => {
+            println!("Prediction failed: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    println!("Prediction result: {:?}", checked.prediction);
+    for warning in &checked.validation.warnings {
+        println!("Warning: {}", warning);
+    }
+
+    Box::into_raw(Box::new(checked.into()))
+}
+
+/// # Safety
+/// `result` must be null or a pointer returned by `predict_air_quality` or
+/// `predict_air_quality_production` that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn free_prediction_result(result: *mut FfiPredictionResult) {
+    if !result.is_null() {
+        unsafe {
+            let result = Box::from_raw(result);
+            let len = result.probabilities_len as usize;
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(result.probabilities, len)));
+            if !result.warning_messages.is_null() {
+                drop(CString::from_raw(result.warning_messages));
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::ffi::c_str;
+use crate::training::config::TrainingConfig;
+use crate::training::online::{partial_fit_files, PartialFitOptions, StatsUpdate};
+use crate::training::trainer::{self, EpochMetrics, TrainingControl};
+use crate::utils::io::{save_any_model, save_model};
+use std::ffi::c_void;
+
+/// Progress of a training run, passed to `ProgressCallback` and returned by
+/// `training_job_latest_metrics`. Validation values are NaN without a
+/// validation split.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TrainingProgress {
+    pub epoch: i32,
+    pub total_epochs: i32,
+    pub train_loss: f64,
+    pub train_accuracy: f64,
+    pub val_loss: f64,
+    pub val_accuracy: f64,
+    pub learning_rate: f64,
+    pub elapsed_secs: f64,
+    pub remaining_secs: f64,
+    pub gradient_norm: f64,
+}
+
+impl From<&EpochMetrics> for TrainingProgress {
+    fn from(metrics: &EpochMetrics) -> Self {
+        TrainingProgress {
+            epoch: metrics.epoch as i32,
+            total_epochs: metrics.total_epochs as i32,
+            train_loss: metrics.train_loss,
+            train_accuracy: metrics.train_accuracy,
+            val_loss: metrics.val_loss,
+            val_accuracy: metrics.val_accuracy,
+            learning_rate: metrics.learning_rate,
+            elapsed_secs: metrics.elapsed.as_secs_f64(),
+            remaining_secs: metrics.remaining.as_secs_f64(),
+            gradient_norm: metrics.gradient_norm,
+        }
+    }
+}
+
+/// Called after every evaluation, every `eval_interval` epochs and after the
+/// last one, with the current progress and the `user_data` pointer given to
+/// `train_model_with_progress`. Return 0 to keep training, non-zero to stop.
+pub type ProgressCallback = extern "C" fn(progress: *const TrainingProgress, user_data: *mut c_void) -> i32;
+
+/// Trains a model on the calling thread and saves it to `model_path`.
+///
+/// `callback` may be null. Returns `false` if training failed or was stopped
+/// by the callback; nothing is written to `model_path` in that case.
+///
+/// # Safety
+/// The path arguments must be valid nul-terminated strings and `accuracy`
+/// must be null or point to writable memory. `user_data` is passed through
+/// to the callback untouched.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn train_model_with_progress(
+    csv_path: *const libc::c_char,
+    epochs: i32,
+    plot_path: *const libc::c_char,
+    model_path: *const libc::c_char,
+    accuracy: *mut f64,
+    callback: Option<ProgressCallback>,
+    user_data: *mut c_void,
+) -> bool {
+    let (Some(csv_path), Some(plot_path), Some(model_path)) =
+        (unsafe { c_str(csv_path) }, unsafe { c_str(plot_path) }, unsafe { c_str(model_path) })
+    else {
+        println!("Training failed: invalid path argument");
+        return false;
+    };
+
+    let mut config = TrainingConfig::default();
+    config.data.path = csv_path.to_string();
+    config.schedule.epochs = epochs.max(0) as usize;
+    config.output.plot = plot_path.to_string();
+    config.output.model = model_path.to_string();
+    let result = trainer::train_model_with_progress::<f64, _>(&config, |metrics| {
+        let progress = TrainingProgress::from(metrics);
+        match callback {
+            Some(callback) if callback(&progress, user_data) != 0 => TrainingControl::Stop,
+            _ => TrainingControl::Continue,
+        }
+    });
+
+    match result {
+        Ok(model) => {
+            println!("Model training completed successfully");
+            println!("Model weights: {:?}", model.network);
+            println!("Normalization params - mean: {:?}, std: {:?}", model.stats.mean, model.stats.std);
+
+            if !accuracy.is_null() {
+                unsafe { *accuracy = model.final_accuracy };
+            }
+            save_model(model_path, &model).is_ok()
+        }
+        Err(e) => {
+            println!("Training failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Trains a model as described by a TOML or JSON config file and saves it to
+/// the config's `output.model` path.
+///
+/// # Safety
+/// `config_path` must be a valid nul-terminated string and `accuracy` must be
+/// null or point to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn train_model_from_config(config_path: *const libc::c_char, accuracy: *mut f64) -> bool {
+    let Some(config_path) = (unsafe { c_str(config_path) }) else {
+        println!("Training failed: invalid config path argument");
+        return false;
+    };
+
+    let config = match TrainingConfig::from_file(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("Training failed: {}", e);
+            return false;
+        }
+    };
+
+    match trainer::train_any_with_progress(&config, |_| TrainingControl::Continue) {
+        Ok(model) => {
+            if !accuracy.is_null() {
+                unsafe { *accuracy = model.final_accuracy() };
+            }
+            save_any_model(&config.output.model, &model).is_ok()
+        }
+        Err(e) => {
+            println!("Training failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Continues training the model at `model_path` on the labelled CSV file at
+/// `csv_path` and saves it to `output_path`, or back to `model_path` if that
+/// is null. `replay_path`, if not null, names the replay buffer of old
+/// readings mixed into every epoch; it is created from the model's original
+/// training data on first use. `epochs` and `learning_rate` fall back to
+/// their defaults when not positive, and `update_stats` moves the
+/// normalization statistics towards the new readings.
+///
+/// # Safety
+/// `model_path` and `csv_path` must be valid nul-terminated strings,
+/// `replay_path` and `output_path` null or valid nul-terminated strings, and
+/// `accuracy` null or point to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn partial_fit_model(
+    model_path: *const libc::c_char,
+    csv_path: *const libc::c_char,
+    replay_path: *const libc::c_char,
+    output_path: *const libc::c_char,
+    epochs: i32,
+    learning_rate: f64,
+    update_stats: bool,
+    accuracy: *mut f64,
+) -> bool {
+    let (Some(model_path), Some(csv_path)) = (unsafe { c_str(model_path) }, unsafe { c_str(csv_path) }) else {
+        println!("Partial fit failed: invalid path argument");
+        return false;
+    };
+    let replay_path = unsafe { c_str(replay_path) };
+    let output_path = unsafe { c_str(output_path) }.unwrap_or(model_path);
+
+    let defaults = PartialFitOptions::default();
+    let options = PartialFitOptions {
+        epochs: if epochs > 0 { epochs as usize } else { defaults.epochs },
+        learning_rate: (learning_rate > 0.0).then_some(learning_rate),
+        stats: if update_stats { StatsUpdate::Update } else { StatsUpdate::Keep },
+        ..defaults
+    };
+    match partial_fit_files(model_path, csv_path, replay_path, output_path, &options) {
+        Ok(report) => {
+            if !accuracy.is_null() {
+                unsafe { *accuracy = report.score_after };
+            }
+            true
+        }
+        Err(e) => {
+            println!("Partial fit failed: {}", e);
+            false
+        }
+    }
+}
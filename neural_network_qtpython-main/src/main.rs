@@ -1,48 +1,1251 @@
-mod model;
-mod training;
-mod utils;
-mod data;
-
-use crate::utils::input::get_input;
-use std::error::Error;
-
-fn main() -> Result<(), Box<dyn Error>> {
-    // Path configuration
-    let csv_path = "airquality.csv";
-    let plot_path = "training_plot.png";
-    let model_path = "trained_model.bin";
-    let epochs = 1000;
-
-    // Load or train model
-    let trained_model = if std::path::Path::new(model_path).exists() {
-        println!("Loading existing model...");
-        utils::io::load_model(model_path)?
-    } else {
-        println!("Training new model...");
-        let model = training::trainer::train_model(csv_path, epochs, plot_path)?;
-        utils::io::save_model(model_path, &model)?;
-        model
-    };
-
-    println!("Model ready for predictions");
-
-    // Get user input for prediction
-    println!("\nEnter air quality parameters to predict:");
-    
-    let pm10 = get_input("PM10: ");
-    let so2 = get_input("SO2: ");
-    let co = get_input("CO: ");
-    let o3 = get_input("O3: ");
-    let no2 = get_input("NO2: ");
-
-    // Make prediction
-    let prediction = trained_model.network.predict(
-        &[pm10, so2, co, o3, no2],
-        &trained_model.stats
-    );
-
-    // Display results
-    prediction.display();
-
-    Ok(())
-}
\ No newline at end of file
+use airquality_predictor::data::preprocessing::{load_features, FEATURES};
+use airquality_predictor::data::time_series::format_timestamp;
+use airquality_predictor::model::float::Precision;
+use airquality_predictor::model::init::Initializer;
+use airquality_predictor::model::layers::Activation;
+use airquality_predictor::training::backtest::backtest as run_backtest;
+use airquality_predictor::training::config::{ConfigError, TrainingConfig};
+use airquality_predictor::training::diagnostics::diagnose_csv;
+use airquality_predictor::training::evaluation::{argmax, evaluate_csv, evaluate_regression_csv};
+use airquality_predictor::training::drift::{DriftSeverity, DriftThresholds};
+use airquality_predictor::training::online::{partial_fit_files, PartialFitOptions, StatsUpdate};
+use airquality_predictor::training::registry::{Bump, ModelRegistry, ModelVersion, RegisterOptions, Stage, Version, DEFAULT_MODEL_NAME, DEFAULT_REGISTRY_DIR};
+use airquality_predictor::training::runs::{RunRecord, RunStatus, RunStore, DEFAULT_RUNS_DIR};
+use airquality_predictor::training::search::{run_search, write_leaderboard_csv, write_leaderboard_json, SearchConfig};
+use airquality_predictor::training::trainer::{train_any_with_progress, AnyModel, TrainedModel, TrainingControl};
+use airquality_predictor::training::validation::{InputIssue, ValidationRules, DEFAULT_OOD_THRESHOLD};
+use airquality_predictor::utils::input::get_input;
+use airquality_predictor::utils::diagnostic_plots::write_diagnostic_plots;
+use airquality_predictor::utils::io::{load_any_model, load_model, save_any_model};
+use airquality_predictor::utils::parallel;
+use airquality_predictor::utils::plot::PlotFormat;
+use airquality_predictor::utils::report::write_report;
+use clap::{Args, Parser, Subcommand};
+use serde_json::json;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Train and run the air quality classifier.
+#[derive(Parser)]
+#[command(name = "airquality", version)]
+struct Cli {
+    /// Print machine-readable JSON instead of text.
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Worker threads for training and batch prediction, at most 1024. Defaults to every core.
+    #[arg(long, global = true)]
+    threads: Option<usize>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Train a new model from a labelled CSV file.
+    Train(Box<TrainArgs>),
+    /// Search hyperparameters as described by a config file and save the best model.
+    Search(SearchArgs),
+    /// Classify one reading given as flags, or prompted for with --interactive.
+    Predict(PredictArgs),
+    /// Classify every row of a CSV file.
+    PredictBatch(PredictBatchArgs),
+    /// Measure accuracy, loss and the confusion matrix on a labelled CSV file.
+    Evaluate(EvaluateArgs),
+    /// Continue training a model on newly labelled readings.
+    PartialFit(PartialFitArgs),
+    /// Compare the readings of a CSV file with the model's training inputs.
+    Drift(DriftArgs),
+    /// Show the architecture, normalization statistics and metrics of a model.
+    Inspect(ModelArg),
+    /// Write a model as JSON.
+    Export(ExportArgs),
+    /// Convert a model to another floating point precision.
+    Convert(ConvertArgs),
+    /// Write the training history stored in a model as CSV, or JSON with --json.
+    History(ExportArgs),
+    /// Train a forecaster per horizon and score each on the latest readings.
+    Backtest(BacktestArgs),
+    /// Forecast from a timestamped CSV file with a time-series model.
+    Forecast(ForecastArgs),
+    /// List, compare and promote tracked training runs.
+    Runs(RunsArgs),
+    /// Register model versions, move them between stages and roll back production.
+    Registry(RegistryArgs),
+}
+
+#[derive(Args)]
+struct ModelArg {
+    /// Model file.
+    #[arg(short, long, default_value = "trained_model.bin")]
+    model: PathBuf,
+}
+
+/// Options given on the command line override the config file.
+#[derive(Args)]
+struct TrainArgs {
+    /// TOML or JSON training configuration.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Labelled training data.
+    #[arg(short, long)]
+    data: Option<String>,
+    /// Where to save the trained model.
+    #[arg(short, long)]
+    model: Option<String>,
+    /// Where to save the training plot; `.svg` for SVG output.
+    #[arg(long)]
+    plot: Option<String>,
+    /// Also write the training history to this CSV or JSON file.
+    #[arg(long)]
+    history: Option<String>,
+    /// Plot the losses on a logarithmic axis.
+    #[arg(long)]
+    log_loss: bool,
+    /// Write diagnostic plots of the validation data to this directory.
+    #[arg(long)]
+    diagnostics: Option<String>,
+    /// Format of the diagnostic plots: png or svg.
+    #[arg(long, value_parser = parse_plot_format)]
+    diagnostics_format: Option<PlotFormat>,
+    /// Write an HTML report of the run to this file.
+    #[arg(long)]
+    report: Option<String>,
+    /// Track the run in a new directory under this one ("runs" if no
+    /// directory is given) instead of writing to the model and plot paths.
+    #[arg(long, num_args = 0..=1, default_missing_value = DEFAULT_RUNS_DIR)]
+    track: Option<String>,
+    #[arg(long)]
+    epochs: Option<usize>,
+    #[arg(long)]
+    learning_rate: Option<f64>,
+    /// Rows per gradient step; 0 for full batch.
+    #[arg(long)]
+    batch_size: Option<usize>,
+    /// Inverse-time learning rate decay per epoch.
+    #[arg(long)]
+    lr_decay: Option<f64>,
+    /// L2 regularization strength.
+    #[arg(long)]
+    lambda: Option<f64>,
+    /// Fraction of hidden layer outputs dropped during training.
+    #[arg(long)]
+    dropout: Option<f64>,
+    /// Batch-normalize the output of every hidden layer.
+    #[arg(long)]
+    batch_norm: bool,
+    /// Fraction of rows held out for validation.
+    #[arg(long)]
+    validation_split: Option<f64>,
+    /// Epochs between evaluation steps.
+    #[arg(long)]
+    eval_interval: Option<usize>,
+    /// Comma-separated hidden layer sizes, e.g. 16,16.
+    #[arg(long, value_delimiter = ',')]
+    hidden_layers: Option<Vec<usize>>,
+    /// Hidden layer activation: relu, leaky_relu, tanh or sigmoid.
+    #[arg(long, value_parser = parse_activation)]
+    activation: Option<Activation>,
+    /// Weight initializer, e.g. he_normal, xavier_uniform, orthogonal or constant=0.1.
+    #[arg(long, value_parser = parse_initializer)]
+    initializer: Option<Initializer>,
+    /// Saved model to start training from.
+    #[arg(long)]
+    warm_start: Option<String>,
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Floating point precision: f32 or f64.
+    #[arg(long, value_parser = parse_precision)]
+    precision: Option<Precision>,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    #[command(flatten)]
+    model: ModelArg,
+    /// Where to save the converted model.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Target precision: f32 or f64.
+    #[arg(long, value_parser = parse_precision)]
+    precision: Precision,
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// TOML or JSON search configuration.
+    #[arg(short, long)]
+    config: PathBuf,
+}
+
+#[derive(Args)]
+struct BacktestArgs {
+    /// TOML or JSON training configuration with a time_series section.
+    #[arg(short, long)]
+    config: PathBuf,
+    /// Steps ahead to backtest, e.g. 1,3,6. Defaults to the configured horizon.
+    #[arg(long, value_delimiter = ',')]
+    horizons: Vec<usize>,
+}
+
+#[derive(Args)]
+struct ForecastArgs {
+    #[command(flatten)]
+    model: ModelArg,
+    /// Timestamped CSV file with a column for every model feature.
+    #[arg(short, long)]
+    input: PathBuf,
+    /// Output CSV file, or `-` for stdout (the default).
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct RunsArgs {
+    /// Directory holding the runs.
+    #[arg(long, default_value = DEFAULT_RUNS_DIR)]
+    runs_dir: PathBuf,
+    #[command(subcommand)]
+    command: RunsCommand,
+}
+
+#[derive(Subcommand)]
+enum RunsCommand {
+    /// List every run with its status and main metrics.
+    List,
+    /// Show the record of one run.
+    Show { id: String },
+    /// Show the metrics of two runs side by side with their difference.
+    Compare { a: String, b: String },
+    /// Copy the model of a run to the production model path.
+    Promote {
+        id: String,
+        /// Where to copy the model.
+        #[arg(short, long, default_value = "trained_model.bin")]
+        output: String,
+    },
+}
+
+#[derive(Args)]
+struct RegistryArgs {
+    /// Directory holding the registry.
+    #[arg(long, default_value = DEFAULT_REGISTRY_DIR)]
+    registry: PathBuf,
+    /// Registered model to work on.
+    #[arg(long, default_value = DEFAULT_MODEL_NAME)]
+    name: String,
+    #[command(subcommand)]
+    command: RegistryCommand,
+}
+
+#[derive(Subcommand)]
+enum RegistryCommand {
+    /// Register a model file, or the model of a tracked run, as a new version.
+    Register {
+        /// Model file to register.
+        #[arg(short, long, required_unless_present = "run", conflicts_with = "run")]
+        model: Option<PathBuf>,
+        /// Id of a completed tracked run to register.
+        #[arg(long)]
+        run: Option<String>,
+        /// Directory holding the tracked runs.
+        #[arg(long, default_value = DEFAULT_RUNS_DIR)]
+        runs_dir: PathBuf,
+        /// Version to register as. Defaults to the latest version bumped.
+        #[arg(long, conflicts_with = "bump")]
+        version: Option<Version>,
+        /// Part of the latest version to bump: major, minor or patch.
+        /// Defaults to major when the inputs or outputs changed, else minor.
+        #[arg(long, value_parser = parse_bump)]
+        bump: Option<Bump>,
+        /// Put the new version in production instead of staging.
+        #[arg(long)]
+        production: bool,
+    },
+    /// List every version with its stage and main metrics.
+    List,
+    /// Show the record of one version.
+    Show { version: Version },
+    /// Put a version in production, archiving the current production version.
+    Promote { version: Version },
+    /// Move a version to staging, production or archived.
+    Stage { version: Version, stage: Stage },
+    /// Put the previous production version back in production.
+    Rollback,
+    /// Show the production version and the path of its model file.
+    Production,
+}
+
+fn parse_severity(value: &str) -> Result<DriftSeverity, String> {
+    serde_json::from_value(json!(value)).map_err(|_| format!("unknown drift severity '{}'", value))
+}
+
+fn parse_bump(value: &str) -> Result<Bump, String> {
+    serde_json::from_value(json!(value)).map_err(|_| format!("unknown version bump '{}'", value))
+}
+
+fn parse_precision(value: &str) -> Result<Precision, String> {
+    serde_json::from_value(json!(value)).map_err(|_| format!("unknown precision '{}'", value))
+}
+
+fn parse_plot_format(value: &str) -> Result<PlotFormat, String> {
+    serde_json::from_value(json!(value)).map_err(|_| format!("unknown plot format '{}'", value))
+}
+
+fn parse_activation(value: &str) -> Result<Activation, String> {
+    serde_json::from_value(json!(value)).map_err(|_| format!("unknown activation '{}'", value))
+}
+
+/// Initializer names as in config files, with `constant=<value>` for constants.
+fn parse_initializer(value: &str) -> Result<Initializer, String> {
+    if let Some(constant) = value.strip_prefix("constant=") {
+        return constant.parse().map(Initializer::Constant).map_err(|_| format!("invalid constant '{}'", constant));
+    }
+    serde_json::from_value(json!(value)).map_err(|_| format!("unknown initializer '{}'", value))
+}
+
+#[derive(Args)]
+struct PredictArgs {
+    #[command(flatten)]
+    model: ModelArg,
+    #[arg(long, allow_negative_numbers = true, required_unless_present = "interactive")]
+    pm10: Option<f64>,
+    #[arg(long, allow_negative_numbers = true, required_unless_present = "interactive")]
+    so2: Option<f64>,
+    #[arg(long, allow_negative_numbers = true, required_unless_present = "interactive")]
+    co: Option<f64>,
+    #[arg(long, allow_negative_numbers = true, required_unless_present = "interactive")]
+    o3: Option<f64>,
+    #[arg(long, allow_negative_numbers = true, required_unless_present = "interactive")]
+    no2: Option<f64>,
+    /// Prompt on stdin for the readings not given as flags.
+    #[arg(long)]
+    interactive: bool,
+    /// Refuse readings outside the physical bounds or the training range
+    /// instead of warning about them.
+    #[arg(long)]
+    strict: bool,
+    /// Standard deviations beyond the training range from which an input is
+    /// flagged as out of distribution.
+    #[arg(long, default_value_t = DEFAULT_OOD_THRESHOLD)]
+    ood_threshold: f64,
+}
+
+#[derive(Args)]
+struct PredictBatchArgs {
+    #[command(flatten)]
+    model: ModelArg,
+    /// CSV file with a column for every model feature.
+    #[arg(short, long)]
+    input: PathBuf,
+    /// Output CSV file, or `-` for stdout (the default).
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct EvaluateArgs {
+    #[command(flatten)]
+    model: ModelArg,
+    /// Labelled data to evaluate on.
+    #[arg(short, long, default_value = "airquality.csv")]
+    data: PathBuf,
+    /// Write confusion matrix, ROC, precision-recall, calibration and
+    /// probability plots to this directory.
+    #[arg(long)]
+    diagnostics: Option<PathBuf>,
+    /// Format of the diagnostic plots: png or svg.
+    #[arg(long, value_parser = parse_plot_format, default_value = "png")]
+    diagnostics_format: PlotFormat,
+    /// Write an HTML report of the evaluation to this file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct PartialFitArgs {
+    #[command(flatten)]
+    model: ModelArg,
+    /// Newly labelled readings.
+    #[arg(short, long)]
+    data: PathBuf,
+    /// Where to save the updated model. Defaults to the model file.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Replay buffer of old readings. Defaults to the model path with a
+    /// `.replay` extension, started from the original training data.
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    /// Train on the new readings alone.
+    #[arg(long, conflicts_with = "replay")]
+    no_replay: bool,
+    /// Old readings replayed per new reading in each epoch.
+    #[arg(long, default_value_t = 1.0)]
+    replay_ratio: f64,
+    #[arg(long, default_value_t = 10)]
+    epochs: usize,
+    /// Defaults to the learning rate the model was trained with.
+    #[arg(long)]
+    learning_rate: Option<f64>,
+    /// Move the normalization statistics towards the new readings.
+    #[arg(long)]
+    update_stats: bool,
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+struct DriftArgs {
+    #[command(flatten)]
+    model: ModelArg,
+    /// Readings to check; labels are not needed.
+    #[arg(short, long)]
+    data: PathBuf,
+    /// Exit with status 5 when any feature reaches this severity: warning or alert.
+    #[arg(long, value_parser = parse_severity)]
+    fail_on: Option<DriftSeverity>,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[command(flatten)]
+    model: ModelArg,
+    /// Output JSON file, or `-` for stdout (the default).
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Exit codes: 1 for failed training or other runtime errors, 2 for usage
+/// errors and invalid option or config values, 3 for unreadable input data and 4 for model
+/// files that cannot be read or written.
+struct CliError {
+    code: u8,
+    message: String,
+}
+
+impl CliError {
+    fn failure(message: impl fmt::Display) -> Self {
+        CliError { code: 1, message: message.to_string() }
+    }
+
+    fn usage(message: impl fmt::Display) -> Self {
+        CliError { code: 2, message: message.to_string() }
+    }
+
+    fn data(message: impl fmt::Display) -> Self {
+        CliError { code: 3, message: message.to_string() }
+    }
+
+    fn model(message: impl fmt::Display) -> Self {
+        CliError { code: 4, message: message.to_string() }
+    }
+
+    fn drift(message: impl fmt::Display) -> Self {
+        CliError { code: 5, message: message.to_string() }
+    }
+}
+
+/// Unreadable config files fail like other runtime errors; malformed or
+/// invalid ones are usage errors, like the flags they stand for.
+fn config_error(error: ConfigError) -> CliError {
+    match error {
+        ConfigError::Io(..) => CliError::failure(error),
+        _ => CliError::usage(error),
+    }
+}
+
+fn path_str(path: &std::path::Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn open_model(arg: &ModelArg) -> Result<TrainedModel, CliError> {
+    load_model(&path_str(&arg.model))
+        .map_err(|e| CliError::model(format!("failed to load model {}: {}", arg.model.display(), e)))
+}
+
+fn open_any_model(arg: &ModelArg) -> Result<AnyModel, CliError> {
+    load_any_model(&path_str(&arg.model))
+        .map_err(|e| CliError::model(format!("failed to load model {}: {}", arg.model.display(), e)))
+}
+
+/// Opens `path` for writing, or stdout when it is absent or `-`.
+fn output_writer(path: &Option<PathBuf>) -> Result<Box<dyn Write>, CliError> {
+    match path {
+        Some(path) if path.as_os_str() != "-" => File::create(path)
+            .map(|f| Box::new(f) as Box<dyn Write>)
+            .map_err(|e| CliError::data(format!("failed to create {}: {}", path.display(), e))),
+        _ => Ok(Box::new(io::stdout())),
+    }
+}
+
+fn training_config(args: &TrainArgs, json: bool) -> Result<TrainingConfig, CliError> {
+    let mut config = match &args.config {
+        Some(path) => TrainingConfig::from_file(path).map_err(config_error)?,
+        None => TrainingConfig::default(),
+    };
+
+    if let Some(data) = &args.data { config.data.path = data.clone(); }
+    if let Some(model) = &args.model { config.output.model = model.clone(); }
+    if let Some(plot) = &args.plot { config.output.plot = plot.clone(); }
+    if args.history.is_some() { config.output.history = args.history.clone(); }
+    if args.log_loss { config.output.plot_options.log_loss = true; }
+    if args.diagnostics.is_some() { config.output.diagnostics = args.diagnostics.clone(); }
+    if let Some(format) = args.diagnostics_format { config.output.diagnostics_format = format; }
+    if args.report.is_some() { config.output.report = args.report.clone(); }
+    if args.track.is_some() { config.output.runs_dir = args.track.clone(); }
+    if let Some(epochs) = args.epochs { config.schedule.epochs = epochs; }
+    if let Some(rate) = args.learning_rate { config.optimizer.learning_rate = rate; }
+    if let Some(size) = args.batch_size { config.optimizer.batch_size = size; }
+    if let Some(decay) = args.lr_decay { config.schedule.lr_decay = decay; }
+    if let Some(lambda) = args.lambda { config.regularization.l2 = lambda; }
+    if let Some(dropout) = args.dropout { config.regularization.dropout = dropout; }
+    if args.batch_norm { config.architecture.batch_norm = true; }
+    if let Some(split) = args.validation_split { config.split.validation = split; }
+    if let Some(interval) = args.eval_interval { config.schedule.eval_interval = interval; }
+    if let Some(layers) = &args.hidden_layers { config.architecture.hidden_layers = layers.clone(); }
+    if let Some(activation) = args.activation { config.architecture.activation = activation; }
+    if let Some(initializer) = args.initializer { config.architecture.initializer = initializer; }
+    if args.warm_start.is_some() { config.architecture.warm_start = args.warm_start.clone(); }
+    if args.seed.is_some() { config.seed = args.seed; }
+    if let Some(precision) = args.precision { config.precision = precision; }
+    if json { config.output.log_every = 0; }
+
+    config.validate().map_err(CliError::usage)?;
+    Ok(config)
+}
+
+fn train(args: &TrainArgs, json: bool) -> Result<(), CliError> {
+    let config = training_config(args, json)?;
+    if !std::path::Path::new(&config.data.path).exists() {
+        return Err(CliError::data(format!("data file {} does not exist", config.data.path)));
+    }
+
+    if let Some(dir) = &config.output.runs_dir {
+        return train_tracked(&config, &RunStore::new(dir), json);
+    }
+
+    let model = train_any_with_progress(&config, |_| TrainingControl::Continue)
+        .map_err(|e| CliError::failure(format!("training failed: {}", e)))?;
+    save_any_model(&config.output.model, &model)
+        .map_err(|e| CliError::model(format!("failed to save model {}: {}", config.output.model, e)))?;
+
+    let seed = model.config().seed.unwrap_or_default();
+    if json {
+        println!("{}", json!({
+            "model": config.output.model,
+            "plot": config.output.plot,
+            "epochs": config.schedule.epochs,
+            "seed": seed,
+            "precision": model.precision(),
+            "final_accuracy": model.final_accuracy(),
+        }));
+    } else if model.is_regression() {
+        println!("Model saved to {} (final R² {:.4}, seed {})", config.output.model, model.final_accuracy(), seed);
+    } else {
+        println!("Model saved to {} (final accuracy {:.2}%, seed {})",
+            config.output.model, model.final_accuracy() * 100.0, seed);
+    }
+    Ok(())
+}
+
+fn train_tracked(config: &TrainingConfig, store: &RunStore, json: bool) -> Result<(), CliError> {
+    let (record, model) = store.train(config, |_| TrainingControl::Continue)
+        .map_err(|e| CliError::failure(format!("training failed: {}", e)))?;
+    let dir = store.dir(&record.id);
+    if json {
+        println!("{}", json!({ "run": record, "dir": dir, "precision": model.precision() }));
+    } else {
+        println!("Run {} saved to {}", record.id, dir.display());
+        print_metrics(&record);
+    }
+    Ok(())
+}
+
+fn runs(args: &RunsArgs, json: bool) -> Result<(), CliError> {
+    let store = RunStore::new(&args.runs_dir);
+    match &args.command {
+        RunsCommand::List => {
+            let runs = store.list().map_err(CliError::failure)?;
+            if json {
+                println!("{}", json!(runs));
+                return Ok(());
+            }
+            println!("{:<24} {:<10} {:>10} {:>10} {:>8} {:<16}", "id", "status", "val_score", "val_loss", "epochs", "dataset");
+            for run in &runs {
+                let metric = |names: &[&str]| names.iter()
+                    .find_map(|name| run.metrics.get(*name))
+                    .map_or("-".to_string(), |&v| metric_value(v));
+                let status = match (run.status, &run.promoted) {
+                    (_, Some(_)) => "promoted",
+                    (RunStatus::Running, _) => "running",
+                    (RunStatus::Completed, _) => "completed",
+                    (RunStatus::Failed, _) => "failed",
+                };
+                println!("{:<24} {:<10} {:>10} {:>10} {:>8} {:<16}", run.id, status,
+                    metric(&["val_accuracy", "val_r2"]), metric(&["val_loss"]), metric(&["epochs"]), run.dataset.hash);
+            }
+        }
+        RunsCommand::Show { id } => {
+            let record = store.get(id).map_err(CliError::failure)?;
+            if json {
+                println!("{}", json!(record));
+                return Ok(());
+            }
+            println!("Run:      {}", record.id);
+            println!("Created:  {}", record.created);
+            println!("Status:   {:?}", record.status);
+            if let Some(error) = &record.error {
+                println!("Error:    {}", error);
+            }
+            if let Some(promoted) = &record.promoted {
+                println!("Promoted: {}", promoted);
+            }
+            println!("Dataset:  {} ({} rows, {} bytes, hash {})",
+                record.dataset.path, record.dataset.rows, record.dataset.bytes, record.dataset.hash);
+            println!("Files:    {}", store.dir(id).display());
+            print_metrics(&record);
+        }
+        RunsCommand::Compare { a, b } => {
+            let diffs = store.compare(a, b).map_err(CliError::failure)?;
+            if json {
+                println!("{}", json!(diffs));
+                return Ok(());
+            }
+            let value = |v: Option<f64>| v.map_or("-".to_string(), metric_value);
+            println!("{:<16} {:>24} {:>24} {:>10}", "metric", a, b, "diff");
+            for diff in &diffs {
+                let change = diff.diff.map_or("-".to_string(), |d| format!("{:+.4}", d));
+                println!("{:<16} {:>24} {:>24} {:>10}", diff.metric, value(diff.a), value(diff.b), change);
+            }
+        }
+        RunsCommand::Promote { id, output } => {
+            let record = store.promote(id, output).map_err(CliError::model)?;
+            if json {
+                println!("{}", json!({ "run": record, "model": output }));
+            } else {
+                println!("Promoted run {} to {}", record.id, output);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_metrics(record: &RunRecord) {
+    for (name, value) in &record.metrics {
+        println!("  {:<16} {}", name, metric_value(*value));
+    }
+}
+
+fn registry(args: &RegistryArgs, json: bool) -> Result<(), CliError> {
+    let registry = ModelRegistry::new(&args.registry);
+    let name = &args.name;
+    let changed = |verb: &str, version: ModelVersion| {
+        if json {
+            println!("{}", json!({ "name": name, "version": version, "model": registry.model_path(name, version.version) }));
+        } else {
+            println!("{} {} {} ({})", verb, name, version.version, version.stage);
+        }
+    };
+    match &args.command {
+        RegistryCommand::Register { model, run, runs_dir, version, bump, production } => {
+            let options = RegisterOptions { version: *version, bump: *bump, production: *production, ..Default::default() };
+            let registered = match (model, run) {
+                (Some(model), _) => registry.register(name, &path_str(model), options).map_err(CliError::model)?,
+                (None, Some(run)) => registry.register_run(name, &RunStore::new(runs_dir), run, options).map_err(CliError::model)?,
+                (None, None) => unreachable!("clap requires --model or --run"),
+            };
+            changed("Registered", registered);
+        }
+        RegistryCommand::List => {
+            let index = registry.index(name).map_err(CliError::failure)?;
+            if json {
+                println!("{}", json!(index));
+                return Ok(());
+            }
+            println!("{:<10} {:<11} {:>10} {:>10} {:<20} {:<24}", "version", "stage", "val_score", "val_loss", "registered", "run");
+            let mut versions = index.versions;
+            versions.sort_by_key(|v| v.version);
+            for version in &versions {
+                let metric = |names: &[&str]| names.iter()
+                    .find_map(|name| version.metrics.get(*name))
+                    .map_or("-".to_string(), |&v| metric_value(v));
+                println!("{:<10} {:<11} {:>10} {:>10} {:<20} {:<24}", version.version.to_string(), version.stage.to_string(),
+                    metric(&["val_accuracy", "val_r2"]), metric(&["val_loss"]), version.registered,
+                    version.lineage.run_id.as_deref().unwrap_or("-"));
+            }
+        }
+        RegistryCommand::Show { version } => {
+            let entry = registry.get(name, *version).map_err(CliError::failure)?;
+            if json {
+                println!("{}", json!(entry));
+                return Ok(());
+            }
+            println!("Model:      {} {}", name, entry.version);
+            println!("Stage:      {} (since {})", entry.stage, entry.stage_changed);
+            println!("Registered: {}", entry.registered);
+            println!("Task:       {}", if entry.regression { "regression" } else { "classification" });
+            println!("Inputs:     {}", entry.inputs.join(", "));
+            println!("Outputs:    {}", entry.outputs.join(", "));
+            println!("Source:     {}", entry.lineage.source);
+            if let Some(run) = &entry.lineage.run_id {
+                println!("Run:        {}", run);
+            }
+            if let Some(dataset) = &entry.lineage.dataset {
+                println!("Dataset:    {} ({} rows, {} bytes, hash {})", dataset.path, dataset.rows, dataset.bytes, dataset.hash);
+            }
+            if let Some(seed) = entry.lineage.seed {
+                println!("Seed:       {}", seed);
+            }
+            if let Some(parent) = entry.lineage.parent {
+                println!("Parent:     {}", parent);
+            }
+            println!("Model file: {}", registry.model_path(name, entry.version).display());
+            for (metric, value) in &entry.metrics {
+                println!("  {:<16} {}", metric, metric_value(*value));
+            }
+        }
+        RegistryCommand::Promote { version } => {
+            changed("Promoted", registry.set_stage(name, *version, Stage::Production).map_err(CliError::failure)?);
+        }
+        RegistryCommand::Stage { version, stage } => {
+            changed("Moved", registry.set_stage(name, *version, *stage).map_err(CliError::failure)?);
+        }
+        RegistryCommand::Rollback => {
+            changed("Rolled back to", registry.rollback(name).map_err(CliError::failure)?);
+        }
+        RegistryCommand::Production => {
+            let version = registry.production(name).map_err(CliError::failure)?;
+            if json {
+                changed("", version);
+            } else {
+                println!("{} {} is in production: {}", name, version.version, registry.model_path(name, version.version).display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Counts such as epochs without decimals, anything else with four.
+fn metric_value(value: f64) -> String {
+    if value.fract() == 0.0 { format!("{}", value) } else { format!("{:.4}", value) }
+}
+
+fn search(args: &SearchArgs, json: bool) -> Result<(), CliError> {
+    let mut config = SearchConfig::from_file(&args.config).map_err(config_error)?;
+    if json {
+        config.base.output.log_every = 0;
+    }
+    if !std::path::Path::new(&config.base.data.path).exists() {
+        return Err(CliError::data(format!("data file {} does not exist", config.base.data.path)));
+    }
+
+    let outcome = run_search(&config).map_err(|e| CliError::failure(format!("search failed: {}", e)))?;
+    let model_path = &config.base.output.model;
+    save_any_model(model_path, &outcome.best)
+        .map_err(|e| CliError::model(format!("failed to save model {}: {}", model_path, e)))?;
+    if let Some(path) = &config.output.leaderboard_csv {
+        write_leaderboard_csv(path, &outcome.leaderboard)
+            .map_err(|e| CliError::failure(format!("failed to write {}: {}", path, e)))?;
+    }
+    if let Some(path) = &config.output.leaderboard_json {
+        write_leaderboard_json(path, &outcome.leaderboard)
+            .map_err(|e| CliError::failure(format!("failed to write {}: {}", path, e)))?;
+    }
+
+    let best = &outcome.leaderboard[0];
+    if json {
+        println!("{}", json!({
+            "model": model_path,
+            "trials": outcome.leaderboard.len(),
+            "best": best,
+            "final_accuracy": outcome.best.final_accuracy(),
+        }));
+    } else {
+        println!("\n{:>4} {:>6} {:>14} {:>10} {:>8} {:>6} {:>11} {:>8} {:>8}",
+            "rank", "epochs", "hidden", "lr", "l2", "batch", "activation", "val_acc", "val_loss");
+        for (rank, trial) in outcome.leaderboard.iter().take(10).enumerate() {
+            let candidate = &trial.candidate;
+            let scores = match (trial.val_accuracy, trial.val_loss) {
+                (Some(accuracy), Some(loss)) => format!("{:>7.2}% {:>8.4}", accuracy * 100.0, loss),
+                _ => format!("{:>17}", "diverged"),
+            };
+            println!("{:>4} {:>6} {:>14} {:>10} {:>8} {:>6} {:>11} {}",
+                rank + 1, trial.epochs, format!("{:?}", candidate.hidden_layers), candidate.learning_rate,
+                candidate.l2, candidate.batch_size, format!("{:?}", candidate.activation), scores);
+        }
+        println!("\nBest model saved to {} (final accuracy {:.2}%)", model_path, outcome.best.final_accuracy() * 100.0);
+    }
+    Ok(())
+}
+
+fn predict(args: &PredictArgs, json: bool) -> Result<(), CliError> {
+    let model = open_model(&args.model)?;
+    if model.is_regression() {
+        return Err(CliError::model("the model is a regressor; use predict-batch"));
+    }
+    if model.config.time_series.is_some() {
+        return Err(CliError::model("the model forecasts from a time series; use forecast"));
+    }
+    if model.features() != FEATURES {
+        return Err(CliError::model(format!(
+            "model expects features {:?}; use predict-batch for custom feature sets", model.features()
+        )));
+    }
+
+    let given = [args.pm10, args.so2, args.co, args.o3, args.no2];
+    if json && given.iter().any(Option::is_none) {
+        return Err(CliError::usage("--json requires --pm10, --so2, --co, --o3 and --no2"));
+    }
+    let reading: Vec<f64> = given.iter()
+        .zip(FEATURES)
+        .map(|(value, name)| match value {
+            Some(value) => Ok(*value),
+            None => get_input(&format!("{}: ", name.to_uppercase()))
+                .map_err(|e| CliError::usage(format!("failed to read {}: {}", name, e))),
+        })
+        .collect::<Result<_, _>>()?;
+
+    let rules = ValidationRules { ood_threshold: args.ood_threshold, ..Default::default() };
+    let checked = model.predict_checked(&reading, &rules).map_err(CliError::model)?;
+    let warnings = &checked.validation.warnings;
+    let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    if (args.strict && !warnings.is_empty()) || warnings.iter().any(|w| w.issue == InputIssue::NotFinite) {
+        return Err(CliError::data(format!("cannot classify the reading: {}", messages.join("; "))));
+    }
+
+    let prediction = &checked.prediction;
+    let classes = model.classes();
+    if json {
+        let class = prediction.class as usize;
+        println!("{}", json!({
+            "class": class,
+            "category": classes[class],
+            "probabilities": classes.iter().zip(&prediction.probabilities)
+                .map(|(name, p)| (name.clone(), json!(p)))
+                .collect::<serde_json::Map<_, _>>(),
+            "warnings": warnings,
+            "ood_score": checked.validation.ood_score,
+        }));
+    } else {
+        prediction.display(classes);
+        for message in &messages {
+            eprintln!("warning: {}", message);
+        }
+    }
+    Ok(())
+}
+
+fn predict_batch(args: &PredictBatchArgs, json: bool) -> Result<(), CliError> {
+    // Scored in the model's own precision
+    let model = open_any_model(&args.model)?;
+    if model.config().time_series.is_some() {
+        return Err(CliError::model("the model forecasts from a time series; use forecast"));
+    }
+    let features = &model.config().data.features;
+    let x = load_features(&path_str(&args.input), features)
+        .map_err(|e| CliError::data(format!("failed to read {}: {}", args.input.display(), e)))?;
+    let mut out = output_writer(&args.output)?;
+    let write_error = |e: io::Error| CliError::failure(format!("failed to write predictions: {}", e));
+    if model.is_regression() {
+        let values = model.predict_values(&x);
+        let targets = &model.config().data.targets;
+        if json {
+            let rows: Vec<_> = values.rows().into_iter()
+                .map(|row| targets.iter().zip(row).map(|(name, v)| (name.clone(), json!(v))).collect::<serde_json::Map<_, _>>())
+                .collect();
+            writeln!(out, "{}", json!(rows)).map_err(write_error)?;
+        } else {
+            let mut writer = csv::Writer::from_writer(out);
+            let mut header: Vec<String> = features.to_vec();
+            header.extend(targets.iter().map(|t| format!("predicted_{}", t)));
+            writer.write_record(&header).map_err(|e| write_error(e.into()))?;
+            for (input, row) in x.rows().into_iter().zip(values.rows()) {
+                let record: Vec<String> = input.iter().map(|v| v.to_string())
+                    .chain(row.iter().map(|v| format!("{:.6}", v)))
+                    .collect();
+                writer.write_record(&record).map_err(|e| write_error(e.into()))?;
+            }
+            writer.flush().map_err(write_error)?;
+        }
+        return Ok(());
+    }
+
+    let probabilities = model.predict_batch(&x);
+    let classes = &model.config().data.classes;
+    if json {
+        let rows: Vec<_> = probabilities.rows().into_iter()
+            .map(|row| {
+                let class = argmax(row);
+                json!({ "class": class, "category": classes[class], "probabilities": row.to_vec() })
+            })
+            .collect();
+        writeln!(out, "{}", json!(rows)).map_err(write_error)?;
+    } else {
+        let mut writer = csv::Writer::from_writer(out);
+        let mut header: Vec<String> = features.to_vec();
+        header.push("category".to_string());
+        header.extend(classes.iter().map(|c| format!("p_{}", c.to_lowercase().replace(' ', "_"))));
+        writer.write_record(&header).map_err(|e| write_error(e.into()))?;
+
+        for (input, row) in x.rows().into_iter().zip(probabilities.rows()) {
+            let mut record: Vec<String> = input.iter().map(|v| v.to_string()).collect();
+            record.push(classes[argmax(row)].clone());
+            record.extend(row.iter().map(|p| format!("{:.6}", p)));
+            writer.write_record(&record).map_err(|e| write_error(e.into()))?;
+        }
+        writer.flush().map_err(write_error)?;
+    }
+    Ok(())
+}
+
+fn backtest(args: &BacktestArgs, json: bool) -> Result<(), CliError> {
+    let mut config = TrainingConfig::from_file(&args.config).map_err(config_error)?;
+    if json {
+        config.output.log_every = 0;
+    }
+    let horizons = match (&args.horizons, &config.time_series) {
+        (horizons, _) if !horizons.is_empty() => horizons.clone(),
+        (_, Some(series)) => vec![series.horizon],
+        (_, None) => return Err(CliError::failure("the config has no time_series section")),
+    };
+    if !std::path::Path::new(&config.data.path).exists() {
+        return Err(CliError::data(format!("data file {} does not exist", config.data.path)));
+    }
+
+    let results = run_backtest(&config, &horizons).map_err(|e| CliError::failure(format!("backtest failed: {}", e)))?;
+    if json {
+        println!("{}", json!(results));
+        return Ok(());
+    }
+
+    for result in &results {
+        println!("\nHorizon {} ({} training samples)", result.horizon, result.train_samples);
+        if let Some(evaluation) = &result.classification {
+            println!("  samples: {}, accuracy: {:.2}%, loss: {:.4}",
+                evaluation.samples, evaluation.accuracy * 100.0, evaluation.loss);
+        }
+        if let Some(evaluation) = &result.regression {
+            for metrics in &evaluation.targets {
+                println!("  {}: samples: {}, rmse: {:.4}, mae: {:.4}, r2: {:.4}",
+                    metrics.target, evaluation.samples, metrics.rmse, metrics.mae, metrics.r2);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn forecast(args: &ForecastArgs, json: bool) -> Result<(), CliError> {
+    let model = open_any_model(&args.model)?;
+    let config = model.config();
+    let Some(series) = &config.time_series else {
+        return Err(CliError::model("the model does not forecast; use predict-batch"));
+    };
+    let windows = config.load_windows(&path_str(&args.input), false)
+        .map_err(|e| CliError::data(format!("failed to read {}: {}", args.input.display(), e)))?;
+    let output = if model.is_regression() { model.predict_values(&windows.x) } else { model.predict_batch(&windows.x) };
+    let lead = (series.horizon as u64 * series.step_seconds) as i64;
+    let outputs = config.data.outputs();
+
+    let mut out = output_writer(&args.output)?;
+    let write_error = |e: io::Error| CliError::failure(format!("failed to write forecasts: {}", e));
+    let category = |row: ndarray::ArrayView1<f64>| (!model.is_regression()).then(|| outputs[argmax(row)].clone());
+    if json {
+        let rows: Vec<_> = windows.stations.iter().zip(&windows.timestamps).zip(output.rows())
+            .map(|((station, &timestamp), row)| json!({
+                "station": station,
+                "timestamp": format_timestamp(timestamp),
+                "forecast_for": format_timestamp(timestamp + lead),
+                "category": category(row),
+                "values": outputs.iter().zip(row).map(|(name, v)| (name.clone(), json!(v))).collect::<serde_json::Map<_, _>>(),
+            }))
+            .collect();
+        writeln!(out, "{}", json!(rows)).map_err(write_error)?;
+        return Ok(());
+    }
+
+    let mut writer = csv::Writer::from_writer(out);
+    let mut header = vec!["station".to_string(), "timestamp".to_string(), "forecast_for".to_string()];
+    if model.is_regression() {
+        header.extend(outputs.iter().map(|t| format!("predicted_{}", t)));
+    } else {
+        header.push("category".to_string());
+        header.extend(outputs.iter().map(|c| format!("p_{}", c.to_lowercase().replace(' ', "_"))));
+    }
+    writer.write_record(&header).map_err(|e| write_error(e.into()))?;
+    for ((station, &timestamp), row) in windows.stations.iter().zip(&windows.timestamps).zip(output.rows()) {
+        let mut record = vec![station.clone(), format_timestamp(timestamp), format_timestamp(timestamp + lead)];
+        record.extend(category(row));
+        record.extend(row.iter().map(|v| format!("{:.6}", v)));
+        writer.write_record(&record).map_err(|e| write_error(e.into()))?;
+    }
+    writer.flush().map_err(write_error)?;
+    Ok(())
+}
+
+fn evaluate(args: &EvaluateArgs, json: bool) -> Result<(), CliError> {
+    let model = open_model(&args.model)?;
+    if let Some(path) = &args.report {
+        evaluation_report(&model, &args.data, path)?;
+    }
+    if model.is_regression() {
+        return evaluate_regressor(&model, args, json);
+    }
+    let evaluation = evaluate_csv(&model, &path_str(&args.data))
+        .map_err(|e| CliError::data(format!("failed to evaluate on {}: {}", args.data.display(), e)))?;
+    let plots = match &args.diagnostics {
+        Some(dir) => {
+            let diagnostics = diagnose_csv(&model, &path_str(&args.data))
+                .map_err(|e| CliError::data(format!("failed to evaluate on {}: {}", args.data.display(), e)))?;
+            write_diagnostic_plots(&diagnostics, dir, args.diagnostics_format)
+                .map_err(|e| CliError::failure(format!("failed to write diagnostic plots to {}: {}", dir.display(), e)))?
+        }
+        None => Vec::new(),
+    };
+
+    if json {
+        let mut output = json!(evaluation);
+        if args.diagnostics.is_some() {
+            output["plots"] = json!(plots);
+        }
+        println!("{}", output);
+        return Ok(());
+    }
+
+    println!("Samples:  {}", evaluation.samples);
+    println!("Accuracy: {:.2}%", evaluation.accuracy * 100.0);
+    println!("Loss:     {:.4}", evaluation.loss);
+    println!("\nConfusion matrix (rows: true, columns: predicted)");
+    print!("{:>12}", "");
+    for name in model.classes() {
+        print!("{:>12}", name);
+    }
+    println!();
+    for (name, row) in model.classes().iter().zip(&evaluation.confusion_matrix) {
+        print!("{:>12}", name);
+        for count in row {
+            print!("{:>12}", count);
+        }
+        println!();
+    }
+    for path in &plots {
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn evaluation_report(model: &TrainedModel, data: &std::path::Path, path: &std::path::Path) -> Result<(), CliError> {
+    let (x, y) = model.config.load_dataset(&path_str(data))
+        .map_err(|e| CliError::data(format!("failed to evaluate on {}: {}", data.display(), e)))?;
+    write_report(&path_str(path), model, "Evaluation report", &path_str(data), &x, &y)
+        .map_err(|e| CliError::failure(format!("failed to write report {}: {}", path.display(), e)))
+}
+
+fn partial_fit(args: &PartialFitArgs, json: bool) -> Result<(), CliError> {
+    if !args.data.exists() {
+        return Err(CliError::data(format!("data file {} does not exist", args.data.display())));
+    }
+    let output = args.output.as_ref().unwrap_or(&args.model.model);
+    let replay = match (&args.replay, args.no_replay) {
+        (_, true) => None,
+        (Some(path), false) => Some(path.clone()),
+        (None, false) => Some(args.model.model.with_extension("replay")),
+    };
+    let options = PartialFitOptions {
+        epochs: args.epochs,
+        learning_rate: args.learning_rate,
+        replay_ratio: args.replay_ratio,
+        stats: if args.update_stats { StatsUpdate::Update } else { StatsUpdate::Keep },
+        seed: args.seed,
+    };
+    let report = partial_fit_files(&path_str(&args.model.model), &path_str(&args.data),
+        replay.as_deref().map(path_str).as_deref(), &path_str(output), &options)
+        .map_err(|e| CliError::failure(format!("partial fit failed: {}", e)))?;
+
+    if json {
+        println!("{}", json!({ "model": output, "replay": replay, "report": report }));
+        return Ok(());
+    }
+    println!("Trained on {} new readings and {} replayed ones per epoch", report.samples, report.replayed);
+    println!("{:<12} {:>10} {:>10}", "", "before", "after");
+    println!("{:<12} {:>10.4} {:>10.4}", "loss", report.loss_before, report.loss_after);
+    println!("{:<12} {:>10.4} {:>10.4}", "score", report.score_before, report.score_after);
+    if report.replayed > 0 {
+        println!("{:<12} {:>10.4} {:>10.4}", "replay loss", report.replay_loss_before, report.replay_loss_after);
+    }
+    println!("Saved model to {}", output.display());
+    Ok(())
+}
+
+fn drift(args: &DriftArgs, json: bool) -> Result<(), CliError> {
+    let model = open_model(&args.model)?;
+    if !args.data.exists() {
+        return Err(CliError::data(format!("data file {} does not exist", args.data.display())));
+    }
+    let report = model.check_drift_csv(&path_str(&args.data), &DriftThresholds::default())
+        .map_err(|e| CliError::failure(format!("failed to check {} for drift: {}", args.data.display(), e)))?;
+
+    if json {
+        println!("{}", json!({ "severity": report.severity(), "report": report }));
+    } else {
+        println!("Readings: {}", report.samples);
+        println!("\n{:>12} {:>8} {:>8} {:>11} {:>9}", "feature", "psi", "ks", "mean_shift", "severity");
+        for feature in &report.features {
+            println!("{:>12} {:>8.4} {:>8.4} {:>+11.4} {:>9}", feature.feature, feature.psi, feature.ks,
+                feature.mean_shift, feature.severity.to_string());
+        }
+        for alert in &report.alerts {
+            println!("{}: {} {} = {:.4} (threshold {})", alert.severity, alert.feature, alert.metric, alert.value, alert.threshold);
+        }
+    }
+    match args.fail_on {
+        Some(level) if level > DriftSeverity::None && report.severity() >= level => {
+            Err(CliError::drift(format!("input drift reached {} level", report.severity())))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn evaluate_regressor(model: &TrainedModel, args: &EvaluateArgs, json: bool) -> Result<(), CliError> {
+    if args.diagnostics.is_some() {
+        return Err(CliError::failure("diagnostic plots need a classifier"));
+    }
+    let evaluation = evaluate_regression_csv(model, &path_str(&args.data))
+        .map_err(|e| CliError::data(format!("failed to evaluate on {}: {}", args.data.display(), e)))?;
+
+    if json {
+        println!("{}", json!(evaluation));
+        return Ok(());
+    }
+
+    println!("Samples: {}", evaluation.samples);
+    println!("\n{:>12} {:>12} {:>12} {:>8}", "target", "rmse", "mae", "r2");
+    for metrics in &evaluation.targets {
+        println!("{:>12} {:>12.4} {:>12.4} {:>8.4}", metrics.target, metrics.rmse, metrics.mae, metrics.r2);
+    }
+    Ok(())
+}
+
+fn inspect(args: &ModelArg, json: bool) -> Result<(), CliError> {
+    let stored = open_any_model(args)?;
+    let model = stored.cast::<f64>();
+    let layer_sizes = model.network.layer_sizes();
+    let parameters = model.network.parameter_count();
+
+    if json {
+        println!("{}", json!({
+            "path": args.model,
+            "features": model.features(),
+            "classes": model.classes(),
+            "targets": model.config.data.targets,
+            "layer_sizes": layer_sizes,
+            "parameters": parameters,
+            "precision": stored.precision(),
+            "normalization": { "mean": model.stats.mean.to_vec(), "std": model.stats.std.to_vec() },
+            "final_accuracy": model.final_accuracy,
+            "config": stored.config(),
+        }));
+        return Ok(());
+    }
+
+    println!("Model:          {}", args.model.display());
+    println!("Layer sizes:    {:?}", layer_sizes);
+    println!("Parameters:     {}", parameters);
+    println!("Precision:      {}", stored.precision());
+    if model.is_regression() {
+        println!("Targets:        {}", model.outputs().join(", "));
+        println!("Final R²:       {:.4}", model.final_accuracy);
+    } else {
+        println!("Final accuracy: {:.2}%", model.final_accuracy * 100.0);
+    }
+    println!("\n{:>8} {:>12} {:>12}", "feature", "mean", "std");
+    for (i, name) in model.features().iter().enumerate() {
+        println!("{:>8} {:>12.4} {:>12.4}", name, model.stats.mean[i], model.stats.std[i]);
+    }
+    let config = stored.config().to_toml_string().map_err(CliError::failure)?;
+    println!("\nTraining configuration:\n{}", config);
+    Ok(())
+}
+
+fn export(args: &ExportArgs) -> Result<(), CliError> {
+    let model = open_any_model(&args.model)?;
+    let mut out = output_writer(&args.output)?;
+    let written = match &model {
+        AnyModel::F32(model) => serde_json::to_writer_pretty(&mut out, model),
+        AnyModel::F64(model) => serde_json::to_writer_pretty(&mut out, model),
+    };
+    written.map_err(|e| CliError::failure(format!("failed to export model: {}", e)))?;
+    writeln!(out).map_err(|e| CliError::failure(format!("failed to export model: {}", e)))?;
+    Ok(())
+}
+
+fn history(args: &ExportArgs, json: bool) -> Result<(), CliError> {
+    let model = open_model(&args.model)?;
+    let out = output_writer(&args.output)?;
+    let written = if json { model.history.write_json(out) } else { model.history.write_csv(out) };
+    written.map_err(|e| CliError::failure(format!("failed to write history: {}", e)))
+}
+
+fn convert(args: &ConvertArgs, json: bool) -> Result<(), CliError> {
+    let model = open_any_model(&args.model)?;
+    let from = model.precision();
+    let converted = match args.precision {
+        Precision::F32 => AnyModel::F32(model.cast()),
+        Precision::F64 => AnyModel::F64(model.cast()),
+    };
+    save_any_model(&path_str(&args.output), &converted)
+        .map_err(|e| CliError::model(format!("failed to save model {}: {}", args.output.display(), e)))?;
+
+    if json {
+        println!("{}", json!({ "model": args.output, "from": from, "to": args.precision }));
+    } else {
+        println!("Converted {} from {} to {} and saved it to {}",
+            args.model.model.display(), from, args.precision, args.output.display());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let threads = match cli.threads {
+        Some(threads) if threads > parallel::MAX_THREADS => {
+            Err(CliError::usage(format!("invalid --threads {}: must be at most {}", threads, parallel::MAX_THREADS)))
+        }
+        Some(threads) => parallel::set_threads(threads)
+            .map_err(|e| CliError::usage(format!("invalid --threads {}: {}", threads, e))),
+        None => Ok(()),
+    };
+
+    let result = threads.and_then(|()| match &cli.command {
+        Command::Train(args) => train(args, cli.json),
+        Command::Search(args) => search(args, cli.json),
+        Command::Predict(args) => predict(args, cli.json),
+        Command::PredictBatch(args) => predict_batch(args, cli.json),
+        Command::Evaluate(args) => evaluate(args, cli.json),
+        Command::PartialFit(args) => partial_fit(args, cli.json),
+        Command::Drift(args) => drift(args, cli.json),
+        Command::Inspect(args) => inspect(args, cli.json),
+        Command::Export(args) => export(args),
+        Command::Convert(args) => convert(args, cli.json),
+        Command::History(args) => history(args, cli.json),
+        Command::Runs(args) => runs(args, cli.json),
+        Command::Registry(args) => registry(args, cli.json),
+        Command::Backtest(args) => backtest(args, cli.json),
+        Command::Forecast(args) => forecast(args, cli.json),
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if cli.json {
+                println!("{}", json!({ "error": e.message }));
+            } else {
+                eprintln!("error: {}", e.message);
+            }
+            ExitCode::from(e.code)
+        }
+    }
+}
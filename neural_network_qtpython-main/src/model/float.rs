@@ -0,0 +1,77 @@
+use ndarray::{LinalgScalar, ScalarOperand};
+use ndarray_rand::rand_distr::uniform::SampleUniform;
+use num_traits::FromPrimitive;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::ops::{AddAssign, SubAssign};
+
+/// Floating point precision of a network's parameters and arithmetic.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Precision {
+    F32,
+    #[default]
+    F64,
+}
+
+impl Display for Precision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Precision::F32 => "f32",
+            Precision::F64 => "f64",
+        })
+    }
+}
+
+/// Element type of a network, implemented for `f32` and `f64`.
+pub trait Float:
+    num_traits::Float
+    + FromPrimitive
+    + LinalgScalar
+    + ScalarOperand
+    + SampleUniform
+    + AddAssign
+    + SubAssign
+    + Sum
+    + Debug
+    + Display
+    + Serialize
+    + DeserializeOwned
+    + Send
+    + Sync
+    + 'static
+{
+    const PRECISION: Precision;
+
+    /// Converts from `f64`, rounding to the nearest representable value.
+    fn cast(value: f64) -> Self;
+
+    /// Converts to `f64` without loss.
+    fn widen(self) -> f64;
+}
+
+impl Float for f32 {
+    const PRECISION: Precision = Precision::F32;
+
+    fn cast(value: f64) -> Self {
+        value as f32
+    }
+
+    fn widen(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Float for f64 {
+    const PRECISION: Precision = Precision::F64;
+
+    fn cast(value: f64) -> Self {
+        value
+    }
+
+    fn widen(self) -> f64 {
+        self
+    }
+}
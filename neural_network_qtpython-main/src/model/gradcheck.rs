@@ -0,0 +1,98 @@
+use ndarray::Array2;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::model::layers::Layer;
+use crate::model::loss::Loss;
+use crate::model::network::NeuralNetwork;
+
+/// Comparison of the analytic gradients of one parameter array with central
+/// finite differences.
+#[derive(Debug, Clone)]
+pub struct ParameterCheck {
+    pub layer: usize,
+    /// `weights` or `bias` for dense layers, `gamma` or `beta` for batch
+    /// normalization.
+    pub parameter: &'static str,
+    pub max_absolute_error: f64,
+    /// Largest `|analytic - numeric| / max(|analytic| + |numeric|, 1e-8)`.
+    pub max_relative_error: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct GradientCheck {
+    pub parameters: Vec<ParameterCheck>,
+}
+
+impl GradientCheck {
+    pub fn max_relative_error(&self) -> f64 {
+        self.parameters.iter().map(|p| p.max_relative_error).fold(0.0, f64::max)
+    }
+
+    pub fn passed(&self, tolerance: f64) -> bool {
+        self.max_relative_error() <= tolerance
+    }
+}
+
+/// Checks `NeuralNetwork::gradients` against central differences of `loss`
+/// in training mode with step `epsilon`, for every parameter of every layer.
+/// Dropout masks come from a generator seeded with `seed`, so every loss
+/// evaluation sees the same ones. The L2 penalty is not included.
+///
+/// This evaluates the network twice per parameter and is meant for tests and
+/// debugging small networks.
+pub fn check_gradients(
+    network: &NeuralNetwork<f64>,
+    x: &Array2<f64>,
+    y: &Array2<f64>,
+    loss: &Loss,
+    epsilon: f64,
+    seed: u64,
+) -> GradientCheck {
+    let value = |network: &NeuralNetwork<f64>| {
+        let output = network.forward_training(x, &mut ChaCha8Rng::seed_from_u64(seed));
+        loss.value(&output, y)
+    };
+    let analytic = network.gradients(x, y, loss, &mut ChaCha8Rng::seed_from_u64(seed));
+
+    let mut parameters = Vec::new();
+    let mut probe = network.clone();
+    for (layer, (grad_w, grad_b)) in analytic.layers.iter().enumerate() {
+        for (index, (parameter, gradient)) in parameter_names(&network.layers[layer]).into_iter().zip([grad_w, grad_b]).enumerate() {
+            let mut check = ParameterCheck { layer, parameter, max_absolute_error: 0.0, max_relative_error: 0.0 };
+            for (position, &analytic) in gradient.indexed_iter() {
+                let original = parameter_mut(&mut probe.layers[layer], index)[position];
+                parameter_mut(&mut probe.layers[layer], index)[position] = original + epsilon;
+                let plus = value(&probe);
+                parameter_mut(&mut probe.layers[layer], index)[position] = original - epsilon;
+                let minus = value(&probe);
+                parameter_mut(&mut probe.layers[layer], index)[position] = original;
+
+                let numeric = (plus - minus) / (2.0 * epsilon);
+                let error = (analytic - numeric).abs();
+                check.max_absolute_error = check.max_absolute_error.max(error);
+                check.max_relative_error = check.max_relative_error.max(error / (analytic.abs() + numeric.abs()).max(1e-8));
+            }
+            parameters.push(check);
+        }
+    }
+    GradientCheck { parameters }
+}
+
+fn parameter_names(layer: &Layer<f64>) -> Vec<&'static str> {
+    match layer {
+        Layer::Dense(_) => vec!["weights", "bias"],
+        Layer::BatchNorm(_) => vec!["gamma", "beta"],
+        Layer::Dropout(_) => Vec::new(),
+    }
+}
+
+fn parameter_mut(layer: &mut Layer<f64>, index: usize) -> &mut Array2<f64> {
+    match (layer, index) {
+        (Layer::Dense(dense), 0) => &mut dense.weights,
+        (Layer::Dense(dense), _) => &mut dense.bias,
+        (Layer::BatchNorm(norm), 0) => &mut norm.gamma,
+        (Layer::BatchNorm(norm), _) => &mut norm.beta,
+        (Layer::Dropout(_), _) => unreachable!("dropout has no parameters"),
+    }
+}
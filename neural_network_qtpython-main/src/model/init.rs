@@ -0,0 +1,92 @@
+use ndarray::Array2;
+use ndarray_rand::rand_distr::{Normal, Uniform};
+use ndarray_rand::RandomExt;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::model::float::Float;
+use crate::model::layers::Activation;
+
+/// How the weights of a dense layer are drawn. Biases always start at zero.
+///
+/// In config files the variants are written in snake case, e.g.
+/// `"he_normal"`, and the constant one as `{ constant = 0.1 }`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Initializer {
+    /// He uniform for the ReLU family, Glorot uniform for everything else.
+    #[default]
+    Auto,
+    /// Normal with standard deviation `sqrt(2 / fan_in)`.
+    HeNormal,
+    /// Uniform in `±sqrt(6 / fan_in)`.
+    HeUniform,
+    /// Normal with standard deviation `sqrt(2 / (fan_in + fan_out))`.
+    XavierNormal,
+    /// Uniform in `±sqrt(6 / (fan_in + fan_out))`.
+    XavierUniform,
+    /// Normal with standard deviation `sqrt(1 / fan_in)`.
+    LecunNormal,
+    /// Uniform in `±sqrt(3 / fan_in)`.
+    LecunUniform,
+    /// Random orthogonal matrix, scaled by the gain of the activation.
+    Orthogonal,
+    Zeros,
+    Constant(f64),
+}
+
+impl Initializer {
+    /// The initializer `Auto` stands for with this activation.
+    pub fn resolve(self, activation: Activation) -> Self {
+        match (self, activation) {
+            (Initializer::Auto, Activation::Relu | Activation::LeakyRelu) => Initializer::HeUniform,
+            (Initializer::Auto, _) => Initializer::XavierUniform,
+            (initializer, _) => initializer,
+        }
+    }
+
+    /// Weights for a layer with `fan_in` inputs and `fan_out` units.
+    /// Values are drawn in `f64`, so both precisions see the same numbers.
+    pub fn weights<F: Float, R: Rng>(self, fan_in: usize, fan_out: usize, activation: Activation, rng: &mut R) -> Array2<F> {
+        let shape = (fan_in, fan_out);
+        let (fan_in, fan_out) = (fan_in.max(1) as f64, fan_out.max(1) as f64);
+        let normal = |std: f64, rng: &mut R| Array2::random_using(shape, Normal::new(0.0, std).unwrap(), rng);
+        let uniform = |limit: f64, rng: &mut R| Array2::random_using(shape, Uniform::new_inclusive(-limit, limit), rng);
+
+        let weights: Array2<f64> = match self.resolve(activation) {
+            Initializer::HeNormal => normal((2.0 / fan_in).sqrt(), rng),
+            Initializer::HeUniform => uniform((6.0 / fan_in).sqrt(), rng),
+            Initializer::XavierNormal => normal((2.0 / (fan_in + fan_out)).sqrt(), rng),
+            Initializer::XavierUniform => uniform((6.0 / (fan_in + fan_out)).sqrt(), rng),
+            Initializer::LecunNormal => normal((1.0 / fan_in).sqrt(), rng),
+            Initializer::LecunUniform => uniform((3.0 / fan_in).sqrt(), rng),
+            Initializer::Orthogonal => orthogonal(shape, rng) * activation.gain(),
+            Initializer::Zeros => Array2::zeros(shape),
+            Initializer::Constant(value) => Array2::from_elem(shape, value),
+            Initializer::Auto => unreachable!("resolved above"),
+        };
+        weights.mapv(F::cast)
+    }
+}
+
+/// Matrix with orthonormal rows or columns (whichever there are fewer of),
+/// from Gram-Schmidt on a Gaussian matrix.
+fn orthogonal<R: Rng>((rows, cols): (usize, usize), rng: &mut R) -> Array2<f64> {
+    let transposed = rows < cols;
+    let (long, short) = if transposed { (cols, rows) } else { (rows, cols) };
+    let mut q: Array2<f64> = Array2::random_using((long, short), Normal::new(0.0, 1.0).unwrap(), rng);
+
+    for j in 0..short {
+        for k in 0..j {
+            let projection = q.column(j).dot(&q.column(k));
+            let previous = q.column(k).to_owned();
+            q.column_mut(j).scaled_add(-projection, &previous);
+        }
+        let norm = q.column(j).dot(&q.column(j)).sqrt();
+        if norm > f64::EPSILON {
+            q.column_mut(j).mapv_inplace(|v| v / norm);
+        }
+    }
+
+    if transposed { q.reversed_axes() } else { q }
+}
@@ -1,16 +1,340 @@
-use ndarray::Array2;
+use ndarray::{Array2, Axis};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-pub fn relu(x: &Array2<f64>) -> Array2<f64> {
-    x.mapv(|v| if v > 0.0 { v } else { 0.0 })
+use crate::model::float::Float;
+
+pub fn relu<F: Float>(x: &Array2<F>) -> Array2<F> {
+    x.mapv(|v| if v > F::zero() { v } else { F::zero() })
 }
 
-pub fn relu_derivative(x: &Array2<f64>) -> Array2<f64> {
-    x.mapv(|v| if v > 0.0 { 1.0 } else { 0.0 })
+/// Derivative of ReLU. It can be given the input or the output of the
+/// activation, since both are positive at the same points.
+pub fn relu_derivative<F: Float>(x: &Array2<F>) -> Array2<F> {
+    x.mapv(|v| if v > F::zero() { F::one() } else { F::zero() })
 }
 
-pub fn softmax(x: &Array2<f64>) -> Array2<f64> {
-    let max_x = x.fold_axis(ndarray::Axis(1), f64::NEG_INFINITY, |&a, &b| a.max(b));
-    let exp_x = (x - &max_x.insert_axis(ndarray::Axis(1))).mapv(f64::exp);
+pub fn softmax<F: Float>(x: &Array2<F>) -> Array2<F> {
+    let max_x = x.fold_axis(ndarray::Axis(1), F::neg_infinity(), |&a, &b| a.max(b));
+    let exp_x = (x - &max_x.insert_axis(ndarray::Axis(1))).mapv(F::exp);
     let sum_exp_x = exp_x.sum_axis(ndarray::Axis(1)).insert_axis(ndarray::Axis(1));
     exp_x / sum_exp_x
-}
\ No newline at end of file
+}
+
+const LEAKY_RELU_SLOPE: f64 = 0.01;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    #[default]
+    Relu,
+    LeakyRelu,
+    Tanh,
+    Sigmoid,
+    /// Only valid on the output layer, where its gradient is folded into the
+    /// cross-entropy loss.
+    Softmax,
+    /// Identity, for the output layer of regression networks.
+    Linear,
+}
+
+impl Activation {
+    pub fn apply<F: Float>(&self, x: &Array2<F>) -> Array2<F> {
+        let slope = F::cast(LEAKY_RELU_SLOPE);
+        match self {
+            Activation::Relu => relu(x),
+            Activation::LeakyRelu => x.mapv(|v| if v > F::zero() { v } else { slope * v }),
+            Activation::Tanh => x.mapv(F::tanh),
+            Activation::Sigmoid => x.mapv(|v| F::one() / (F::one() + (-v).exp())),
+            Activation::Softmax => softmax(x),
+            Activation::Linear => x.clone(),
+        }
+    }
+
+    /// Derivative of the activation, expressed in terms of its output.
+    pub fn derivative<F: Float>(&self, output: &Array2<F>) -> Array2<F> {
+        let slope = F::cast(LEAKY_RELU_SLOPE);
+        match self {
+            Activation::Relu => relu_derivative(output),
+            Activation::LeakyRelu => output.mapv(|v| if v > F::zero() { F::one() } else { slope }),
+            Activation::Tanh => output.mapv(|v| F::one() - v * v),
+            Activation::Sigmoid => output.mapv(|v| v * (F::one() - v)),
+            Activation::Softmax => panic!("softmax is only supported on the output layer"),
+            Activation::Linear => output.mapv(|_| F::one()),
+        }
+    }
+
+    /// Recommended scale of orthogonal weights feeding this activation.
+    pub fn gain(&self) -> f64 {
+        match self {
+            Activation::Relu => 2f64.sqrt(),
+            Activation::LeakyRelu => (2.0 / (1.0 + LEAKY_RELU_SLOPE * LEAKY_RELU_SLOPE)).sqrt(),
+            Activation::Tanh => 5.0 / 3.0,
+            Activation::Sigmoid | Activation::Softmax | Activation::Linear => 1.0,
+        }
+    }
+}
+
+/// Fully connected layer computing `activation(x · weights + bias)`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "F: Float")]
+pub struct DenseLayer<F: Float = f64> {
+    pub weights: Array2<F>,
+    pub bias: Array2<F>,
+    pub activation: Activation,
+}
+
+impl<F: Float> DenseLayer<F> {
+    pub fn forward(&self, x: &Array2<F>) -> Array2<F> {
+        self.activation.apply(&self.logits(x))
+    }
+
+    /// Output before the activation.
+    pub fn logits(&self, x: &Array2<F>) -> Array2<F> {
+        x.dot(&self.weights) + &self.bias
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.weights.nrows()
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.weights.ncols()
+    }
+
+    /// The same layer with its parameters converted to another precision.
+    pub fn cast<T: Float>(&self) -> DenseLayer<T> {
+        DenseLayer {
+            weights: self.weights.mapv(|v| T::cast(v.widen())),
+            bias: self.bias.mapv(|v| T::cast(v.widen())),
+            activation: self.activation,
+        }
+    }
+}
+
+/// Inverted dropout: in training every input is zeroed with probability
+/// `rate` and the rest are scaled by `1 / (1 - rate)`, so inference can use
+/// the layer as the identity.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Dropout {
+    pub rate: f64,
+}
+
+impl Dropout {
+    /// Random mask the input is multiplied with during training.
+    pub fn mask<F: Float, R: Rng>(&self, shape: (usize, usize), rng: &mut R) -> Array2<F> {
+        let keep = 1.0 - self.rate;
+        let scale = F::cast(1.0 / keep);
+        Array2::from_shape_simple_fn(shape, || if rng.gen_bool(keep) { scale } else { F::zero() })
+    }
+}
+
+const BATCH_NORM_EPSILON: f64 = 1e-5;
+const BATCH_NORM_MOMENTUM: f64 = 0.1;
+
+/// Batch normalization of every input column, followed by a learned scale
+/// (`gamma`) and shift (`beta`). Training normalizes with the statistics of
+/// the batch and folds them into running averages, which inference uses.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "F: Float")]
+pub struct BatchNorm<F: Float = f64> {
+    pub gamma: Array2<F>,
+    pub beta: Array2<F>,
+    pub running_mean: Array2<F>,
+    pub running_var: Array2<F>,
+    /// Weight of each new batch in the running averages.
+    pub momentum: f64,
+    pub epsilon: f64,
+}
+
+impl<F: Float> BatchNorm<F> {
+    pub fn new(size: usize) -> Self {
+        BatchNorm {
+            gamma: Array2::ones((1, size)),
+            beta: Array2::zeros((1, size)),
+            running_mean: Array2::zeros((1, size)),
+            running_var: Array2::ones((1, size)),
+            momentum: BATCH_NORM_MOMENTUM,
+            epsilon: BATCH_NORM_EPSILON,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.gamma.ncols()
+    }
+
+    /// Inference pass, using the running statistics.
+    pub fn forward(&self, x: &Array2<F>) -> Array2<F> {
+        let inv_std = self.running_var.mapv(|v| F::one() / (v + F::cast(self.epsilon)).sqrt());
+        (x - &self.running_mean) * &inv_std * &self.gamma + &self.beta
+    }
+
+    /// Folds the statistics of one training batch into the running averages.
+    pub fn update_running(&mut self, batch: &BatchStats<F>) {
+        let momentum = F::cast(self.momentum);
+        let keep = F::one() - momentum;
+        self.running_mean = &self.running_mean * keep + &batch.mean * momentum;
+        self.running_var = &self.running_var * keep + &batch.var * momentum;
+    }
+
+    pub fn cast<T: Float>(&self) -> BatchNorm<T> {
+        let cast = |a: &Array2<F>| a.mapv(|v| T::cast(v.widen()));
+        BatchNorm {
+            gamma: cast(&self.gamma),
+            beta: cast(&self.beta),
+            running_mean: cast(&self.running_mean),
+            running_var: cast(&self.running_var),
+            momentum: self.momentum,
+            epsilon: self.epsilon,
+        }
+    }
+}
+
+/// Per-column mean and (biased) variance of a training batch.
+#[derive(Debug, Clone)]
+pub struct BatchStats<F: Float = f64> {
+    pub mean: Array2<F>,
+    pub var: Array2<F>,
+    pub rows: usize,
+}
+
+impl<F: Float> BatchStats<F> {
+    pub fn of(x: &Array2<F>) -> Self {
+        let mean = x.mean_axis(Axis(0)).unwrap().insert_axis(Axis(0));
+        let var = (x - &mean).mapv(|v| v * v).mean_axis(Axis(0)).unwrap().insert_axis(Axis(0));
+        BatchStats { mean, var, rows: x.nrows() }
+    }
+
+    /// Statistics of the two batches taken together.
+    pub fn merge(&self, other: &Self) -> Self {
+        let rows = self.rows + other.rows;
+        let (a, b) = (F::cast(self.rows as f64 / rows as f64), F::cast(other.rows as f64 / rows as f64));
+        let mean = &self.mean * a + &other.mean * b;
+        let spread = |stats: &Self| &stats.var + &(&stats.mean - &mean).mapv(|v| v * v);
+        let var = spread(self) * a + spread(other) * b;
+        BatchStats { mean, var, rows }
+    }
+}
+
+/// One layer of a `NeuralNetwork`. Dropout and batch normalization keep the
+/// width of their input and can be placed anywhere before the output layer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "F: Float", rename_all = "snake_case")]
+pub enum Layer<F: Float = f64> {
+    Dense(DenseLayer<F>),
+    Dropout(Dropout),
+    BatchNorm(BatchNorm<F>),
+}
+
+/// What a training forward pass keeps for the backward pass.
+pub(crate) enum Cache<F: Float> {
+    None,
+    Mask(Array2<F>),
+    Norm { normalized: Array2<F>, inv_std: Array2<F>, stats: BatchStats<F> },
+}
+
+impl<F: Float> Layer<F> {
+    /// Inference pass: dropout is the identity and batch normalization uses
+    /// its running statistics.
+    pub fn forward(&self, x: &Array2<F>) -> Array2<F> {
+        match self {
+            Layer::Dense(layer) => layer.forward(x),
+            Layer::Dropout(_) => x.clone(),
+            Layer::BatchNorm(layer) => layer.forward(x),
+        }
+    }
+
+    pub(crate) fn forward_train<R: Rng>(&self, x: &Array2<F>, rng: &mut R) -> (Array2<F>, Cache<F>) {
+        match self {
+            Layer::Dense(layer) => (layer.forward(x), Cache::None),
+            Layer::Dropout(dropout) if dropout.rate == 0.0 => (x.clone(), Cache::None),
+            Layer::Dropout(dropout) => {
+                let mask = dropout.mask(x.dim(), rng);
+                (x * &mask, Cache::Mask(mask))
+            }
+            Layer::BatchNorm(layer) => {
+                let stats = BatchStats::of(x);
+                let inv_std = stats.var.mapv(|v| F::one() / (v + F::cast(layer.epsilon)).sqrt());
+                let normalized = (x - &stats.mean) * &inv_std;
+                let output = &normalized * &layer.gamma + &layer.beta;
+                (output, Cache::Norm { normalized, inv_std, stats })
+            }
+        }
+    }
+
+    /// Backpropagates `grad_output`, the gradient with respect to this
+    /// layer's output, and returns the gradient with respect to its input and
+    /// the parameter gradients (empty for dropout). For a softmax layer
+    /// `grad_output` must already be the gradient before the activation.
+    pub(crate) fn backward(
+        &self,
+        input: &Array2<F>,
+        output: &Array2<F>,
+        cache: &Cache<F>,
+        grad_output: &Array2<F>,
+    ) -> (Array2<F>, (Array2<F>, Array2<F>)) {
+        match (self, cache) {
+            (Layer::Dense(layer), _) => {
+                let delta = match layer.activation {
+                    Activation::Softmax => grad_output.clone(),
+                    activation => grad_output * &activation.derivative(output),
+                };
+                let grad_w = input.t().dot(&delta);
+                let grad_b = delta.sum_axis(Axis(0)).insert_axis(Axis(0));
+                (delta.dot(&layer.weights.t()), (grad_w, grad_b))
+            }
+            (Layer::Dropout(_), Cache::Mask(mask)) => (grad_output * mask, no_parameters()),
+            (Layer::BatchNorm(layer), Cache::Norm { normalized, inv_std, .. }) => {
+                let rows = F::cast(input.nrows() as f64);
+                let grad_gamma = (grad_output * normalized).sum_axis(Axis(0)).insert_axis(Axis(0));
+                let grad_beta = grad_output.sum_axis(Axis(0)).insert_axis(Axis(0));
+                let grad_input = (grad_output * rows - &grad_beta - normalized * &grad_gamma)
+                    * &(&layer.gamma * inv_std / rows);
+                (grad_input, (grad_gamma, grad_beta))
+            }
+            _ => (grad_output.clone(), no_parameters()),
+        }
+    }
+
+    /// Takes one descent step with the parameter gradients from `backward`.
+    pub(crate) fn descend(&mut self, grad_w: &Array2<F>, grad_b: &Array2<F>, learning_rate: F) {
+        let (w, b) = match self {
+            Layer::Dense(layer) => (&mut layer.weights, &mut layer.bias),
+            Layer::BatchNorm(layer) => (&mut layer.gamma, &mut layer.beta),
+            Layer::Dropout(_) => return,
+        };
+        w.scaled_add(-learning_rate, grad_w);
+        b.scaled_add(-learning_rate, grad_b);
+    }
+
+    /// Whether every parameter and running statistic is finite.
+    pub fn is_finite(&self) -> bool {
+        let arrays: Vec<&Array2<F>> = match self {
+            Layer::Dense(layer) => vec![&layer.weights, &layer.bias],
+            Layer::Dropout(_) => Vec::new(),
+            Layer::BatchNorm(layer) => vec![&layer.gamma, &layer.beta, &layer.running_mean, &layer.running_var],
+        };
+        arrays.into_iter().flatten().all(|v| v.is_finite())
+    }
+
+    /// Number of trainable parameters.
+    pub fn parameter_count(&self) -> usize {
+        match self {
+            Layer::Dense(layer) => layer.weights.len() + layer.bias.len(),
+            Layer::Dropout(_) => 0,
+            Layer::BatchNorm(layer) => layer.gamma.len() + layer.beta.len(),
+        }
+    }
+
+    pub fn cast<T: Float>(&self) -> Layer<T> {
+        match self {
+            Layer::Dense(layer) => Layer::Dense(layer.cast()),
+            Layer::Dropout(dropout) => Layer::Dropout(*dropout),
+            Layer::BatchNorm(layer) => Layer::BatchNorm(layer.cast()),
+        }
+    }
+}
+
+fn no_parameters<F: Float>() -> (Array2<F>, Array2<F>) {
+    (Array2::zeros((0, 0)), Array2::zeros((0, 0)))
+}
@@ -0,0 +1,152 @@
+use ndarray::{Array2, Axis, Zip};
+use serde::{Deserialize, Serialize};
+
+use crate::model::float::Float;
+
+/// Training objective, averaged over the rows of a batch.
+///
+/// Classification losses take the logits of the output layer and apply the
+/// softmax themselves, using log-sum-exp so that saturated probabilities never
+/// turn into `ln(0)`. Regression losses take the network output as is.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Loss {
+    /// Softmax cross-entropy. `label_smoothing` mixes the targets with the
+    /// uniform distribution; `class_weights` scales the loss of each target
+    /// class (empty weighs every class 1).
+    CrossEntropy {
+        #[serde(default)]
+        label_smoothing: f64,
+        #[serde(default)]
+        class_weights: Vec<f64>,
+    },
+    /// Focal loss `-(1 - p)^gamma ln(p)`, which down-weights rows the network
+    /// already gets right so training concentrates on hard and rare ones.
+    Focal {
+        #[serde(default = "default_focal_gamma")]
+        gamma: f64,
+        #[serde(default)]
+        class_weights: Vec<f64>,
+    },
+    /// Mean squared error over the outputs, for regression heads.
+    Mse,
+    /// Mean absolute error over the outputs, for regression heads.
+    Mae,
+    /// Quadratic within `delta` of the target and linear beyond it, for
+    /// regression heads with outliers.
+    Huber {
+        #[serde(default = "default_huber_delta")]
+        delta: f64,
+    },
+}
+
+fn default_focal_gamma() -> f64 {
+    2.0
+}
+
+fn default_huber_delta() -> f64 {
+    1.0
+}
+
+impl Default for Loss {
+    fn default() -> Self {
+        Loss::CrossEntropy { label_smoothing: 0.0, class_weights: Vec::new() }
+    }
+}
+
+impl Loss {
+    /// Whether the loss expects logits and a softmax output layer.
+    pub fn is_classification(&self) -> bool {
+        matches!(self, Loss::CrossEntropy { .. } | Loss::Focal { .. })
+    }
+
+    /// Mean loss per row of `output`, which holds logits for classification
+    /// losses.
+    pub fn value<F: Float>(&self, output: &Array2<F>, y: &Array2<F>) -> f64 {
+        let rows = output.nrows().max(1) as f64;
+        let total = match self {
+            Loss::CrossEntropy { .. } | Loss::Focal { .. } => {
+                let log_p = log_softmax(output);
+                let gamma = F::cast(self.gamma());
+                let terms = Zip::from(&self.targets(y)).and(&log_p).map_collect(|&t, &log_p| {
+                    let q = F::one() - log_p.exp();
+                    if t == F::zero() { F::zero() } else { -t * q.max(F::zero()).powf(gamma) * log_p }
+                });
+                terms.sum().widen()
+            }
+            Loss::Mse => (output - y).mapv(|r| r * r).sum().widen() / output.ncols().max(1) as f64,
+            Loss::Mae => (output - y).mapv(F::abs).sum().widen() / output.ncols().max(1) as f64,
+            Loss::Huber { delta } => {
+                let delta = F::cast(*delta);
+                let half = F::cast(0.5);
+                let terms = (output - y).mapv(|r| {
+                    if r.abs() <= delta { half * r * r } else { delta * (r.abs() - half * delta) }
+                });
+                terms.sum().widen() / output.ncols().max(1) as f64
+            }
+        };
+        total / rows
+    }
+
+    /// Gradient of the loss summed over the rows, with respect to `output`.
+    pub fn gradient<F: Float>(&self, output: &Array2<F>, y: &Array2<F>) -> Array2<F> {
+        let cols = F::cast(output.ncols().max(1) as f64);
+        match self {
+            Loss::CrossEntropy { .. } | Loss::Focal { .. } => {
+                // With t the weighted targets and h_c = d(-(1 - p_c)^gamma ln p_c)/dp_c * p_c,
+                // the gradient for logit j is t_j h_j - p_j * sum_c t_c h_c
+                let log_p = log_softmax(output);
+                let p = log_p.mapv(F::exp);
+                let gamma = F::cast(self.gamma());
+                let h = Zip::from(&p).and(&log_p).map_collect(|&p, &log_p| {
+                    let q = (F::one() - p).max(F::zero());
+                    let slope = if gamma == F::zero() || q == F::zero() {
+                        F::zero()
+                    } else {
+                        gamma * q.powf(gamma - F::one()) * p * log_p
+                    };
+                    slope - q.powf(gamma)
+                });
+                let weighted = self.targets(y) * &h;
+                let total = weighted.sum_axis(Axis(1)).insert_axis(Axis(1));
+                weighted - &(p * &total)
+            }
+            Loss::Mse => (output - y) * (F::cast(2.0) / cols),
+            Loss::Mae => (output - y).mapv(|r| if r == F::zero() { F::zero() } else { r.signum() / cols }),
+            Loss::Huber { delta } => {
+                let delta = F::cast(*delta);
+                (output - y).mapv(|r| r.max(-delta).min(delta) / cols)
+            }
+        }
+    }
+
+    fn gamma(&self) -> f64 {
+        match self {
+            Loss::Focal { gamma, .. } => *gamma,
+            _ => 0.0,
+        }
+    }
+
+    /// Smoothed targets multiplied by the weight of their class.
+    fn targets<F: Float>(&self, y: &Array2<F>) -> Array2<F> {
+        let (smoothing, weights) = match self {
+            Loss::CrossEntropy { label_smoothing, class_weights } => (*label_smoothing, class_weights.as_slice()),
+            Loss::Focal { class_weights, .. } => (0.0, class_weights.as_slice()),
+            _ => (0.0, &[][..]),
+        };
+        let uniform = F::cast(smoothing / y.ncols().max(1) as f64);
+        let targets = y.mapv(|v| v * F::cast(1.0 - smoothing) + uniform);
+        if weights.is_empty() {
+            return targets;
+        }
+        targets * &Array2::from_shape_fn((1, y.ncols()), |(_, c)| F::cast(weights[c]))
+    }
+}
+
+/// Row-wise log-softmax, using log-sum-exp so that no term underflows.
+pub fn log_softmax<F: Float>(logits: &Array2<F>) -> Array2<F> {
+    let max = logits.fold_axis(Axis(1), F::neg_infinity(), |&a, &b| a.max(b)).insert_axis(Axis(1));
+    let shifted = logits - &max;
+    let log_sum = shifted.mapv(F::exp).sum_axis(Axis(1)).mapv(F::ln).insert_axis(Axis(1));
+    shifted - &log_sum
+}
@@ -1,2 +1,6 @@
 pub mod network;
-pub mod layers;
\ No newline at end of file
+pub mod layers;
+pub mod float;
+pub mod init;
+pub mod gradcheck;
+pub mod loss;
@@ -1,20 +1,97 @@
-use ndarray::{Array2, Axis};
-use ndarray_rand::RandomExt;
+use ndarray::{s, Array2, Axis};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
 use serde::{Serialize, Deserialize};
 
-use crate::model::layers::{relu, relu_derivative, softmax};
+use crate::model::float::Float;
+use crate::model::init::Initializer;
+use crate::model::layers::{Activation, BatchNorm, BatchStats, Cache, DenseLayer, Dropout, Layer};
+use crate::model::loss::Loss;
 use crate::data::preprocessing::DataStats;
+use crate::utils::parallel;
 
-#[derive(Serialize, Deserialize)]
-pub struct NeuralNetwork {
-    pub weights1: Array2<f64>,
-    pub bias1: Array2<f64>,
-    pub weights2: Array2<f64>,
-    pub bias2: Array2<f64>,
-    pub weights3: Array2<f64>,
-    pub bias3: Array2<f64>,
-    pub weights4: Array2<f64>,
-    pub bias4: Array2<f64>,
+/// Rows per parallel block in `predict_batch`.
+const PREDICT_BLOCK_ROWS: usize = 1024;
+
+/// Feed-forward classifier: a stack of layers ending in a dense softmax layer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "F: Float")]
+pub struct NeuralNetwork<F: Float = f64> {
+    pub layers: Vec<Layer<F>>,
+}
+
+/// Result of backpropagating one batch.
+#[derive(Debug, Clone)]
+pub struct Gradients<F: Float = f64> {
+    /// Gradients of the summed loss as `(weights, bias)` pairs per layer,
+    /// without the L2 penalty. Batch normalization layers report `(gamma,
+    /// beta)` and dropout layers empty arrays.
+    pub layers: Vec<(Array2<F>, Array2<F>)>,
+    /// Statistics seen by each batch normalization layer.
+    pub batch_stats: Vec<Option<BatchStats<F>>>,
+}
+
+impl<F: Float> Gradients<F> {
+    /// Adds the gradient of the L2 penalty `lambda / 2 * |w|^2` on the dense
+    /// weights of `network`.
+    pub fn with_l2(mut self, network: &NeuralNetwork<F>, lambda: f64) -> Self {
+        let lambda = F::cast(lambda);
+        for ((grad_w, _), layer) in self.layers.iter_mut().zip(&network.layers) {
+            if let Layer::Dense(dense) = layer {
+                grad_w.scaled_add(lambda, &dense.weights);
+            }
+        }
+        self
+    }
+
+    /// L2 norm over every parameter gradient.
+    pub fn norm(&self) -> f64 {
+        self.layers.iter()
+            .map(|(w, b)| w.mapv(|v| v * v).sum() + b.mapv(|v| v * v).sum())
+            .sum::<F>()
+            .sqrt()
+            .widen()
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.layers.iter().all(|(w, b)| w.iter().chain(b).all(|v| v.is_finite()))
+    }
+
+    /// Limits every gradient component to `[-limit, limit]`.
+    pub fn clip_value(&mut self, limit: f64) {
+        let limit = F::cast(limit);
+        for (w, b) in &mut self.layers {
+            w.mapv_inplace(|v| v.max(-limit).min(limit));
+            b.mapv_inplace(|v| v.max(-limit).min(limit));
+        }
+    }
+
+    /// Scales the gradients down so that their norm is at most `max_norm`.
+    pub fn clip_norm(&mut self, max_norm: f64) {
+        let norm = self.norm();
+        if norm > max_norm {
+            let scale = F::cast(max_norm / norm);
+            for (w, b) in &mut self.layers {
+                w.mapv_inplace(|v| v * scale);
+                b.mapv_inplace(|v| v * scale);
+            }
+        }
+    }
+
+    /// Combines the gradients of two disjoint parts of a batch.
+    fn add(mut self, other: Gradients<F>) -> Self {
+        for ((total_w, total_b), (grad_w, grad_b)) in self.layers.iter_mut().zip(other.layers) {
+            *total_w += &grad_w;
+            *total_b += &grad_b;
+        }
+        for (total, stats) in self.batch_stats.iter_mut().zip(other.batch_stats) {
+            if let (Some(total), Some(stats)) = (total.as_mut(), stats) {
+                *total = total.merge(&stats);
+            }
+        }
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -24,129 +101,303 @@ pub struct PredictionResult {
 }
 
 impl PredictionResult {
-    pub fn display(&self) {
-        let category = match self.class {
-            0 => "BAIK",
-            1 => "SEDANG",
-            2 => "TIDAK SEHAT",
-            _ => "UNKNOWN"
-        };
+    pub fn display(&self, classes: &[String]) {
+        let category = usize::try_from(self.class).ok()
+            .and_then(|class| classes.get(class))
+            .map_or("UNKNOWN", String::as_str);
         
         println!("\nPrediction Results:");
-        println!("- BAIK: {:.2}%", self.probabilities[0] * 100.0);
-        println!("- SEDANG: {:.2}%", self.probabilities[1] * 100.0);
-        println!("- TIDAK SEHAT: {:.2}%", self.probabilities[2] * 100.0);
+        for (name, probability) in classes.iter().zip(&self.probabilities) {
+            println!("- {}: {:.2}%", name, probability * 100.0);
+        }
         println!("\nPredicted air quality category: {}", category);
     }
 }
 
-impl NeuralNetwork {
-    pub fn new(
-        input_size: usize, 
-        hidden_size1: usize, 
-        hidden_size2: usize, 
-        hidden_size3: usize, 
-        output_size: usize
+impl<F: Float> NeuralNetwork<F> {
+    /// Builds a network with the given layer sizes, from the input to the
+    /// output layer. Hidden layers use `activation`, the output uses softmax.
+    /// The weights are random; use `with_rng` for reproducible ones.
+    pub fn new(layer_sizes: &[usize], activation: Activation) -> Self {
+        Self::with_rng(layer_sizes, activation, &mut rand::thread_rng())
+    }
+
+    pub fn with_rng<R: Rng>(layer_sizes: &[usize], activation: Activation, rng: &mut R) -> Self {
+        Self::with_initializers(layer_sizes, activation, &[], rng)
+    }
+
+    /// Like `with_rng`, with the weights of dense layer `i` drawn by
+    /// `initializers[i]`. Layers without an entry use `Initializer::Auto`.
+    pub fn with_initializers<R: Rng>(
+        layer_sizes: &[usize],
+        activation: Activation,
+        initializers: &[Initializer],
+        rng: &mut R,
     ) -> Self {
-        let he_init = |size: usize| (2.0 / size as f64).sqrt();
-        let weights1 = Array2::random(
-            (input_size, hidden_size1), 
-            ndarray_rand::rand_distr::Uniform::new(-he_init(input_size), he_init(input_size))
-        );
-        let bias1 = Array2::zeros((1, hidden_size1));
-        let weights2 = Array2::random(
-            (hidden_size1, hidden_size2), 
-            ndarray_rand::rand_distr::Uniform::new(-he_init(hidden_size1), he_init(hidden_size1))
-        );
-        let bias2 = Array2::zeros((1, hidden_size2));
-        let weights3 = Array2::random(
-            (hidden_size2, hidden_size3), 
-            ndarray_rand::rand_distr::Uniform::new(-he_init(hidden_size2), he_init(hidden_size2))
-        );
-        let bias3 = Array2::zeros((1, hidden_size3));
-        let weights4 = Array2::random(
-            (hidden_size3, output_size), 
-            ndarray_rand::rand_distr::Uniform::new(-he_init(hidden_size3), he_init(hidden_size3))
-        );
-        let bias4 = Array2::zeros((1, output_size));
-        
-        NeuralNetwork {
-            weights1,
-            bias1,
-            weights2,
-            bias2,
-            weights3,
-            bias3,
-            weights4,
-            bias4,
+        let layers = layer_sizes.windows(2)
+            .enumerate()
+            .map(|(i, sizes)| {
+                let (fan_in, fan_out) = (sizes[0], sizes[1]);
+                let is_output = i == layer_sizes.len() - 2;
+                let activation = if is_output { Activation::Softmax } else { activation };
+                let initializer = initializers.get(i).copied().unwrap_or_default();
+                Layer::Dense(DenseLayer {
+                    weights: initializer.weights(fan_in, fan_out, activation, rng),
+                    bias: Array2::zeros((1, fan_out)),
+                    activation,
+                })
+            })
+            .collect();
+
+        NeuralNetwork { layers }
+    }
+
+    /// Replaces the softmax of the output layer, e.g. with `Activation::Linear`
+    /// for regression.
+    pub fn with_output_activation(mut self, activation: Activation) -> Self {
+        if let Some(Layer::Dense(output)) = self.layers.iter_mut().rev().find(|layer| matches!(layer, Layer::Dense(_))) {
+            output.activation = activation;
+        }
+        self
+    }
+
+    /// Inserts batch normalization (if `batch_norm`) and then dropout (if
+    /// `dropout > 0`) after every hidden dense layer.
+    pub fn with_regularization(self, batch_norm: bool, dropout: f64) -> Self {
+        let count = self.layers.len();
+        let mut layers = Vec::with_capacity(count * 3);
+        for (i, layer) in self.layers.into_iter().enumerate() {
+            let hidden = match &layer {
+                Layer::Dense(dense) if i + 1 < count => Some(dense.output_size()),
+                _ => None,
+            };
+            layers.push(layer);
+            if let Some(size) = hidden {
+                if batch_norm {
+                    layers.push(Layer::BatchNorm(BatchNorm::new(size)));
+                }
+                if dropout > 0.0 {
+                    layers.push(Layer::Dropout(Dropout { rate: dropout }));
+                }
+            }
+        }
+        NeuralNetwork { layers }
+    }
+
+    /// Copies the parameters of `source` into this network, for warm starts.
+    /// The dense layers must have the same sizes; batch normalization layers,
+    /// including their running statistics, are copied when both networks
+    /// have them in matching sizes. Activations and dropout are kept.
+    pub fn warm_start(&mut self, source: &NeuralNetwork<F>) -> Result<(), String> {
+        if self.layer_sizes() != source.layer_sizes() {
+            return Err(format!("cannot warm start layer sizes {:?} from a model with {:?}",
+                self.layer_sizes(), source.layer_sizes()));
+        }
+
+        let source_dense = source.layers.iter().filter_map(|layer| match layer {
+            Layer::Dense(dense) => Some(dense),
+            _ => None,
+        });
+        let target_dense = self.layers.iter_mut().filter_map(|layer| match layer {
+            Layer::Dense(dense) => Some(dense),
+            _ => None,
+        });
+        for (target, source) in target_dense.zip(source_dense) {
+            target.weights.assign(&source.weights);
+            target.bias.assign(&source.bias);
+        }
+
+        let source_norms = source.layers.iter().filter_map(|layer| match layer {
+            Layer::BatchNorm(norm) => Some(norm),
+            _ => None,
+        });
+        let target_norms = self.layers.iter_mut().filter_map(|layer| match layer {
+            Layer::BatchNorm(norm) => Some(norm),
+            _ => None,
+        });
+        for (target, source) in target_norms.zip(source_norms) {
+            if target.size() == source.size() {
+                *target = source.clone();
+            }
         }
+        Ok(())
     }
 
-    pub fn forward(&self, x: &Array2<f64>) -> (Array2<f64>, Array2<f64>, Array2<f64>, Array2<f64>) {
-        let hidden_input1 = x.dot(&self.weights1) + &self.bias1;
-        let hidden_output1 = relu(&hidden_input1);
+    /// Number of units in every dense layer, from the input to the output layer.
+    pub fn layer_sizes(&self) -> Vec<usize> {
+        let dense: Vec<&DenseLayer<F>> = self.layers.iter()
+            .filter_map(|layer| match layer {
+                Layer::Dense(dense) => Some(dense),
+                _ => None,
+            })
+            .collect();
+        let mut sizes = vec![dense.first().map_or(0, |layer| layer.input_size())];
+        sizes.extend(dense.iter().map(|layer| layer.output_size()));
+        sizes
+    }
 
-        let hidden_input2 = hidden_output1.dot(&self.weights2) + &self.bias2;
-        let hidden_output2 = relu(&hidden_input2);
+    /// Number of trainable parameters.
+    pub fn parameter_count(&self) -> usize {
+        self.layers.iter().map(Layer::parameter_count).sum()
+    }
 
-        let hidden_input3 = hidden_output2.dot(&self.weights3) + &self.bias3;
-        let hidden_output3 = relu(&hidden_input3);
+    pub fn forward(&self, x: &Array2<F>) -> Array2<F> {
+        self.layers.iter().fold(x.clone(), |input, layer| layer.forward(&input))
+    }
 
-        let output_input = hidden_output3.dot(&self.weights4) + &self.bias4;
-        let output_output = softmax(&output_input);
+    /// Like `forward`, but stops before the softmax of a softmax output
+    /// layer. This is what `Loss::value` expects.
+    pub fn forward_logits(&self, x: &Array2<F>) -> Array2<F> {
+        let (last, hidden) = match self.layers.split_last() {
+            Some(split) => split,
+            None => return x.clone(),
+        };
+        let input = hidden.iter().fold(x.clone(), |input, layer| layer.forward(&input));
+        match last {
+            Layer::Dense(dense) if dense.activation == Activation::Softmax => dense.logits(&input),
+            layer => layer.forward(&input),
+        }
+    }
 
-        (hidden_output1, hidden_output2, hidden_output3, output_output)
+    /// Outputs of every layer; the last entry is the network output.
+    pub fn forward_all(&self, x: &Array2<F>) -> Vec<Array2<F>> {
+        let mut outputs: Vec<Array2<F>> = Vec::with_capacity(self.layers.len());
+        for layer in &self.layers {
+            let output = layer.forward(outputs.last().unwrap_or(x));
+            outputs.push(output);
+        }
+        outputs
     }
 
-    pub fn train(&mut self, x: &Array2<f64>, y: &Array2<f64>, learning_rate: f64, lambda: f64) {
-        let (hidden_output1, hidden_output2, hidden_output3, output_output) = self.forward(x);
+    /// Runs one gradient descent step on `loss` and returns the L2 norm of the
+    /// gradient. `rng` draws the dropout masks.
+    pub fn train<R: Rng>(&mut self, x: &Array2<F>, y: &Array2<F>, loss: &Loss, learning_rate: f64, lambda: f64, rng: &mut R) -> f64 {
+        let gradients = self.gradients(x, y, loss, rng);
+        self.apply_gradients(gradients, learning_rate, lambda)
+    }
 
-        // Backpropagation
-        let output_error = &output_output - y;
-        let output_delta = output_error;
+    /// Like `gradients`, but splits the rows into `shards` blocks whose
+    /// gradients are computed in parallel on the current rayon pool and
+    /// combined in block order, so the result only depends on `shards`, not
+    /// on scheduling. Each block gets its own generator seeded from `rng`, and
+    /// batch normalization uses the statistics of each block.
+    pub fn gradients_sharded<R: Rng>(&self, x: &Array2<F>, y: &Array2<F>, loss: &Loss, shards: usize, rng: &mut R) -> Gradients<F> {
+        let rows = x.nrows();
+        let scale = F::cast(1.0 / rows.max(1) as f64);
+        let shard_rows = rows.div_ceil(shards.max(1)).max(1);
+        let starts: Vec<(usize, u64)> = (0..rows).step_by(shard_rows).map(|start| (start, rng.next_u64())).collect();
 
-        let hidden_error3 = output_delta.dot(&self.weights4.t());
-        let hidden_delta3 = hidden_error3 * relu_derivative(&hidden_output3);
+        starts.par_iter()
+            .map(|&(start, seed)| {
+                let rows = start..(start + shard_rows).min(rows);
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let (x, y) = (x.slice(s![rows.clone(), ..]).to_owned(), y.slice(s![rows, ..]).to_owned());
+                self.backpropagate(&x, &y, loss, scale, &mut rng)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .reduce(Gradients::add)
+            .unwrap_or_else(|| self.backpropagate(x, y, loss, scale, rng))
+    }
+
+    /// Gradients of the mean `loss` over the rows of `x`, computed in
+    /// training mode: dropout masks are drawn from `rng` and batch
+    /// normalization uses the batch statistics.
+    pub fn gradients<R: Rng>(&self, x: &Array2<F>, y: &Array2<F>, loss: &Loss, rng: &mut R) -> Gradients<F> {
+        self.backpropagate(x, y, loss, F::cast(1.0 / x.nrows().max(1) as f64), rng)
+    }
 
-        let hidden_error2 = hidden_delta3.dot(&self.weights3.t());
-        let hidden_delta2 = hidden_error2 * relu_derivative(&hidden_output2);
+    /// Training mode counterpart of `forward_logits`, as seen by `gradients`.
+    /// With the same `rng` state, both draw the same dropout masks.
+    pub fn forward_training<R: Rng>(&self, x: &Array2<F>, rng: &mut R) -> Array2<F> {
+        self.forward_layers(x, rng).0.pop().unwrap()
+    }
 
-        let hidden_error1 = hidden_delta2.dot(&self.weights2.t());
-        let hidden_delta1 = hidden_error1 * relu_derivative(&hidden_output1);
+    /// Inputs of every layer followed by the network output (logits for a
+    /// softmax output layer), with what each layer needs for backpropagation.
+    fn forward_layers<R: Rng>(&self, x: &Array2<F>, rng: &mut R) -> (Vec<Array2<F>>, Vec<Cache<F>>) {
+        let mut activations = Vec::with_capacity(self.layers.len() + 1);
+        let mut caches = Vec::with_capacity(self.layers.len());
+        activations.push(x.clone());
+        for (i, layer) in self.layers.iter().enumerate() {
+            let input = activations.last().unwrap();
+            let (output, cache) = match layer {
+                Layer::Dense(dense) if i + 1 == self.layers.len() && dense.activation == Activation::Softmax => {
+                    (dense.logits(input), Cache::None)
+                }
+                layer => layer.forward_train(input, rng),
+            };
+            activations.push(output);
+            caches.push(cache);
+        }
+        (activations, caches)
+    }
 
-        // Update weights and biases
-        self.weights4 -= &(learning_rate * (hidden_output3.t().dot(&output_delta) + lambda * &self.weights4));
-        self.bias4 -= &(learning_rate * output_delta.sum_axis(ndarray::Axis(0)).insert_axis(ndarray::Axis(0)));
+    /// Gradients of the summed loss over the rows of `x`, times `scale`.
+    fn backpropagate<R: Rng>(&self, x: &Array2<F>, y: &Array2<F>, loss: &Loss, scale: F, rng: &mut R) -> Gradients<F> {
+        let (activations, caches) = self.forward_layers(x, rng);
 
-        self.weights3 -= &(learning_rate * (hidden_output2.t().dot(&hidden_delta3) + lambda * &self.weights3));
-        self.bias3 -= &(learning_rate * hidden_delta3.sum_axis(ndarray::Axis(0)).insert_axis(ndarray::Axis(0)));
+        // Backpropagation; the loss applies the softmax of a softmax output
+        // layer itself, so its gradient is already the one of the logits
+        let mut delta = loss.gradient(activations.last().unwrap(), y) * scale;
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for (i, layer) in self.layers.iter().enumerate().rev() {
+            let (grad_input, parameters) = layer.backward(&activations[i], &activations[i + 1], &caches[i], &delta);
+            layers.push(parameters);
+            delta = grad_input;
+        }
+        layers.reverse();
 
-        self.weights2 -= &(learning_rate * (hidden_output1.t().dot(&hidden_delta2) + lambda * &self.weights2));
-        self.bias2 -= &(learning_rate * hidden_delta2.sum_axis(ndarray::Axis(0)).insert_axis(ndarray::Axis(0)));
+        let batch_stats = caches.into_iter()
+            .map(|cache| match cache {
+                Cache::Norm { stats, .. } => Some(stats),
+                _ => None,
+            })
+            .collect();
+        Gradients { layers, batch_stats }
+    }
 
-        self.weights1 -= &(learning_rate * (x.t().dot(&hidden_delta1) + lambda * &self.weights1));
-        self.bias1 -= &(learning_rate * hidden_delta1.sum_axis(ndarray::Axis(0)).insert_axis(ndarray::Axis(0)));
+    /// Adds the L2 penalty to the dense weight gradients, takes one descent
+    /// step and returns the L2 norm of the full gradient.
+    pub fn apply_gradients(&mut self, gradients: Gradients<F>, learning_rate: f64, lambda: f64) -> f64 {
+        let gradients = gradients.with_l2(self, lambda);
+        let gradient_norm = gradients.norm();
+        self.descend(gradients, learning_rate);
+        gradient_norm
     }
 
-    pub fn predict(&self, input: &[f64], stats: &DataStats) -> PredictionResult {
-        // Ubah slice input menjadi Array2<f64>
-        let input = Array2::from_shape_vec((1, input.len()), input.to_vec()).unwrap();
+    /// Takes one descent step with `gradients` as they are and updates the
+    /// batch normalization running statistics.
+    pub fn descend(&mut self, gradients: Gradients<F>, learning_rate: f64) {
+        let learning_rate = F::cast(learning_rate);
+        for ((layer, (grad_w, grad_b)), stats) in self.layers.iter_mut().zip(gradients.layers).zip(gradients.batch_stats) {
+            layer.descend(&grad_w, &grad_b, learning_rate);
+            if let (Layer::BatchNorm(norm), Some(stats)) = (layer, stats) {
+                norm.update_running(&stats);
+            }
+        }
+    }
+
+    /// Whether every parameter and running statistic is finite.
+    pub fn is_finite(&self) -> bool {
+        self.layers.iter().all(Layer::is_finite)
+    }
+
+    pub fn predict(&self, input: &[f64], stats: &DataStats<F>) -> PredictionResult {
+        // Ubah slice input menjadi Array2<F>
+        let input = Array2::from_shape_vec((1, input.len()), input.iter().map(|&v| F::cast(v)).collect()).unwrap();
     
         // Normalisasi input
         let mean = stats.mean.view().insert_axis(ndarray::Axis(0));
         let std = stats.std.view().insert_axis(ndarray::Axis(0));
-        let input_normalized = (&input - &mean) / &std;
+        let input_normalized = (&input - &mean) / std;
     
         // Forward pass
-        let (_, _, _, output) = self.forward(&input_normalized);
+        let output = self.forward(&input_normalized);
     
         // Ambil hasil prediksi dan probabilitas
-        let probabilities = output.row(0).to_vec();
-        let class = probabilities.iter()
-            .enumerate()
-            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-            .map(|(index, _)| index as i32)
-            .unwrap_or(-1);
+        let probabilities: Vec<f64> = output.row(0).iter().map(|v| v.widen()).collect();
+        let class = argmax(probabilities.iter().copied()).map_or(-1, |index| index as i32);
     
         PredictionResult {
             class,
@@ -154,31 +405,58 @@ impl NeuralNetwork {
         }
     }
 
-    pub fn loss(&self, y_true: &Array2<f64>, y_pred: &Array2<f64>) -> f64 {
-        -(y_true * y_pred.mapv(f64::ln)).sum()
-    }
+    /// Normalizes a batch of raw readings and returns one row of class
+    /// probabilities per reading. Large batches are split into blocks that run
+    /// in parallel on the shared thread pool (see `utils::parallel`).
+    pub fn predict_batch(&self, inputs: &Array2<F>, stats: &DataStats<F>) -> Array2<F> {
+        let inputs = stats.normalize(inputs);
+        if inputs.nrows() <= PREDICT_BLOCK_ROWS {
+            return self.forward(&inputs);
+        }
 
-    pub fn accuracy(&self, y_true: &Array2<f64>, y_pred: &Array2<f64>) -> f64 {
-        let predictions = y_pred.map_axis(Axis(1), |row| {
-            row.iter()
-                .enumerate()
-                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-                .map(|(index, _)| index)
-                .unwrap()
-        });
-        
-        let true_labels = y_true.map_axis(Axis(1), |row| {
-            row.iter()
-                .enumerate()
-                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-                .map(|(index, _)| index)
-                .unwrap()
+        let blocks: Vec<Array2<F>> = parallel::pool().install(|| {
+            let starts: Vec<usize> = (0..inputs.nrows()).step_by(PREDICT_BLOCK_ROWS).collect();
+            starts.par_iter()
+                .map(|&start| {
+                    let end = (start + PREDICT_BLOCK_ROWS).min(inputs.nrows());
+                    self.forward(&inputs.slice(s![start..end, ..]).to_owned())
+                })
+                .collect()
         });
+        let views: Vec<_> = blocks.iter().map(Array2::view).collect();
+        ndarray::concatenate(Axis(0), &views).unwrap()
+    }
+
+    /// Mean cross-entropy per row of predicted probabilities. Probabilities
+    /// that underflowed to zero count as the smallest positive value; use
+    /// `Loss::value` on `forward_logits` for the exact loss.
+    pub fn loss(&self, y_true: &Array2<F>, y_pred: &Array2<F>) -> f64 {
+        let log_p = y_pred.mapv(|p| p.max(F::min_positive_value()).ln());
+        -(y_true * &log_p).sum().widen() / y_true.nrows().max(1) as f64
+    }
+
+    /// Fraction of rows whose largest output is the target class. Rows
+    /// without any finite output count as wrong.
+    pub fn accuracy(&self, y_true: &Array2<F>, y_pred: &Array2<F>) -> f64 {
+        let predictions = y_pred.map_axis(Axis(1), |row| argmax(row.iter().copied()));
+        let true_labels = y_true.map_axis(Axis(1), |row| argmax(row.iter().copied()));
         
         predictions.iter()
             .zip(true_labels.iter())
             .filter(|&(a, b)| a == b)
             .count() as f64 / predictions.len() as f64
     }
-    
-}
\ No newline at end of file
+
+    /// The same network with its parameters converted to another precision.
+    pub fn cast<T: Float>(&self) -> NeuralNetwork<T> {
+        NeuralNetwork { layers: self.layers.iter().map(Layer::cast).collect() }
+    }
+}
+
+/// Index of the largest value, ignoring NaN; `None` if there is none.
+fn argmax<T: num_traits::Float>(values: impl Iterator<Item = T>) -> Option<usize> {
+    values.enumerate()
+        .filter(|(_, v)| !v.is_nan())
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(index, _)| index)
+}
@@ -0,0 +1,389 @@
+use crate::data::preprocessing::CLASSES;
+use crate::training::evaluation::{self, argmax, Evaluation};
+use crate::training::config::TrainingConfig;
+use crate::training::diagnostics::diagnose_csv;
+use crate::training::online::{PartialFitOptions, ReplayBuffer, StatsUpdate};
+use crate::training::registry::{self, DEFAULT_MODEL_NAME, DEFAULT_REGISTRY_DIR};
+use crate::training::trainer::{train_any_with_progress, TrainedModel, TrainingControl};
+use crate::training::validation::{ValidationRules, DEFAULT_OOD_THRESHOLD};
+use crate::utils::io::{load_model as load_model_file, save_model};
+use crate::utils::diagnostic_plots::write_diagnostic_plots;
+use crate::utils::parallel;
+use crate::utils::plot::PlotFormat;
+use crate::utils::report;
+use ndarray::{Array1, Array2, Axis};
+use numpy::{PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Predicted class indices and the matching probability rows of a batch.
+type BatchPrediction<'py> = (Bound<'py, PyArray1<usize>>, Bound<'py, PyArray2<f64>>);
+
+// numpy may link a different `ndarray` release than this crate, so arrays
+// cross the boundary as plain buffers rather than ndarray types.
+fn to_array2(x: &PyReadonlyArray2<'_, f64>) -> Array2<f64> {
+    let view = x.as_array();
+    let (rows, cols) = view.dim();
+    Array2::from_shape_vec((rows, cols), view.iter().copied().collect()).unwrap()
+}
+
+fn to_pyarray1<'py>(py: Python<'py>, x: &Array1<f64>) -> Bound<'py, PyArray1<f64>> {
+    PyArray1::from_vec(py, x.to_vec())
+}
+
+fn to_pyarray2<'py>(py: Python<'py>, x: &Array2<f64>) -> PyResult<Bound<'py, PyArray2<f64>>> {
+    PyArray1::from_vec(py, x.iter().copied().collect()).reshape([x.nrows(), x.ncols()])
+}
+
+/// A trained network together with the normalization statistics of its training data.
+#[pyclass(name = "TrainedModel", module = "airquality_predictor")]
+pub struct PyTrainedModel {
+    inner: TrainedModel,
+}
+
+#[pymethods]
+impl PyTrainedModel {
+    #[getter]
+    fn final_accuracy(&self) -> f64 {
+        self.inner.final_accuracy
+    }
+
+    /// Seed of the training run; training again with it reproduces the model.
+    #[getter]
+    fn seed(&self) -> Option<u64> {
+        self.inner.config.seed
+    }
+
+    #[getter]
+    fn features(&self) -> Vec<String> {
+        self.inner.features().to_vec()
+    }
+
+    #[getter]
+    fn classes(&self) -> Vec<String> {
+        self.inner.classes().to_vec()
+    }
+
+    /// Regression targets; empty for classifiers.
+    #[getter]
+    fn targets(&self) -> Vec<String> {
+        self.inner.config.data.targets.clone()
+    }
+
+    /// The training configuration, as a TOML string.
+    #[getter]
+    fn config(&self) -> PyResult<String> {
+        self.inner.config.to_toml_string().map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn mean<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        to_pyarray1(py, &self.inner.stats.mean)
+    }
+
+    #[getter]
+    fn std<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        to_pyarray1(py, &self.inner.stats.std)
+    }
+
+    /// Continues training in place on the labelled CSV file at `csv_path`,
+    /// replaying old readings from the buffer at `replay_path` (started from
+    /// the original training data if missing). Returns the losses and scores
+    /// on the new readings before and after.
+    #[pyo3(signature = (csv_path, replay_path = None, epochs = 10, learning_rate = None, replay_ratio = 1.0, update_stats = false, seed = None))]
+    #[allow(clippy::too_many_arguments)]
+    fn partial_fit<'py>(
+        &mut self,
+        py: Python<'py>,
+        csv_path: &str,
+        replay_path: Option<&str>,
+        epochs: usize,
+        learning_rate: Option<f64>,
+        replay_ratio: f64,
+        update_stats: bool,
+        seed: Option<u64>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let (x, y) = self.inner.config.load_dataset(csv_path)
+            .map_err(|e| PyIOError::new_err(format!("failed to read {}: {}", csv_path, e)))?;
+        let mut replay = match replay_path {
+            Some(path) => Some(ReplayBuffer::open(path, &self.inner, seed).map_err(|e| PyIOError::new_err(e.to_string()))?),
+            None => None,
+        };
+        let stats = if update_stats { StatsUpdate::Update } else { StatsUpdate::Keep };
+        let options = PartialFitOptions { epochs, learning_rate, replay_ratio, stats, seed };
+        let report = py
+            .detach(|| self.inner.partial_fit(&x, &y, replay.as_mut(), &options).map_err(|e| e.to_string()))
+            .map_err(|e| PyRuntimeError::new_err(format!("partial fit failed: {}", e)))?;
+        if let (Some(path), Some(replay)) = (replay_path, &replay) {
+            replay.save(path).map_err(|e| PyIOError::new_err(format!("failed to save replay buffer {}: {}", path, e)))?;
+        }
+
+        let dict = PyDict::new(py);
+        dict.set_item("samples", report.samples)?;
+        dict.set_item("replayed", report.replayed)?;
+        dict.set_item("loss_before", report.loss_before)?;
+        dict.set_item("loss_after", report.loss_after)?;
+        dict.set_item("score_before", report.score_before)?;
+        dict.set_item("score_after", report.score_after)?;
+        dict.set_item("replay_loss_before", report.replay_loss_before)?;
+        dict.set_item("replay_loss_after", report.replay_loss_after)?;
+        Ok(dict)
+    }
+
+    fn save(&self, path: &str) -> PyResult<()> {
+        save_model(path, &self.inner).map_err(|e| PyIOError::new_err(format!("failed to save model to {}: {}", path, e)))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("TrainedModel(final_accuracy={:.4})", self.inner.final_accuracy)
+    }
+}
+
+impl PyTrainedModel {
+    fn n_features(&self) -> usize {
+        self.inner.stats.mean.len()
+    }
+}
+
+/// Trains a new model on a CSV file and returns it. The training plot is
+/// written to `plot_path`. Runs with the same `seed` give identical models.
+#[pyfunction]
+#[pyo3(signature = (csv_path, epochs = 1000, plot_path = "training_plot.png", validation_split = 0.2, seed = None))]
+fn train(
+    py: Python<'_>,
+    csv_path: &str,
+    epochs: usize,
+    plot_path: &str,
+    validation_split: f64,
+    seed: Option<u64>,
+) -> PyResult<PyTrainedModel> {
+    let mut config = TrainingConfig::default();
+    config.data.path = csv_path.to_string();
+    config.schedule.epochs = epochs;
+    config.output.plot = plot_path.to_string();
+    config.split.validation = validation_split;
+    config.seed = seed;
+    config.validate().map_err(|e| PyValueError::new_err(e.to_string()))?;
+    run_training(py, &config)
+}
+
+/// Trains a new model as described by a TOML or JSON config file. The model
+/// is returned, not saved.
+#[pyfunction]
+fn train_from_config(py: Python<'_>, config_path: &str) -> PyResult<PyTrainedModel> {
+    let config = TrainingConfig::from_file(config_path).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    run_training(py, &config)
+}
+
+fn run_training(py: Python<'_>, config: &TrainingConfig) -> PyResult<PyTrainedModel> {
+    // Training can take a while, so let other Python threads run meanwhile.
+    // f32 runs are widened afterwards, the Python wrapper always holds f64
+    let result = py.detach(|| {
+        train_any_with_progress(config, |_| TrainingControl::Continue)
+            .map(|model| model.cast::<f64>())
+            .map_err(|e| e.to_string())
+    });
+
+    result
+        .map(|inner| PyTrainedModel { inner })
+        .map_err(|e| PyRuntimeError::new_err(format!("training failed: {}", e)))
+}
+
+/// Loads a model saved by `TrainedModel.save` or the C API.
+#[pyfunction]
+fn load_model(path: &str) -> PyResult<PyTrainedModel> {
+    load_model_file(path)
+        .map(|inner| PyTrainedModel { inner })
+        .map_err(|e| PyIOError::new_err(format!("failed to load model from {}: {}", path, e)))
+}
+
+/// Loads the production version of a model from a model registry.
+#[pyfunction]
+#[pyo3(signature = (registry_dir = DEFAULT_REGISTRY_DIR, name = DEFAULT_MODEL_NAME))]
+fn load_production_model(registry_dir: &str, name: &str) -> PyResult<PyTrainedModel> {
+    registry::load_production_model(registry_dir, name)
+        .map(|inner| PyTrainedModel { inner })
+        .map_err(|e| PyIOError::new_err(format!("failed to load production model {} from {}: {}", name, registry_dir, e)))
+}
+
+/// Classifies one reading. Returns `(class_index, probabilities)`.
+#[pyfunction]
+fn predict<'py>(
+    py: Python<'py>,
+    model: PyRef<'_, PyTrainedModel>,
+    x: PyReadonlyArray1<'py, f64>,
+) -> PyResult<(usize, Bound<'py, PyArray1<f64>>)> {
+    let x = x.as_array();
+    if x.len() != model.n_features() {
+        return Err(PyValueError::new_err(format!(
+            "expected {} features, got {}", model.n_features(), x.len()
+        )));
+    }
+
+    let input = Array2::from_shape_vec((1, x.len()), x.iter().copied().collect()).unwrap();
+    let probabilities = model.inner.network.predict_batch(&input, &model.inner.stats).row(0).to_owned();
+    let class = argmax(probabilities.view());
+    Ok((class, to_pyarray1(py, &probabilities)))
+}
+
+/// Checks one reading against the physical bounds of each pollutant and the
+/// training range. Returns a dict with `warnings` (one message per problem)
+/// and `ood_score`, the largest distance from the training range in training
+/// standard deviations.
+#[pyfunction]
+#[pyo3(signature = (model, x, ood_threshold = DEFAULT_OOD_THRESHOLD))]
+fn validate_input<'py>(
+    py: Python<'py>,
+    model: PyRef<'_, PyTrainedModel>,
+    x: PyReadonlyArray1<'py, f64>,
+    ood_threshold: f64,
+) -> PyResult<Bound<'py, PyDict>> {
+    let reading: Vec<f64> = x.as_array().iter().copied().collect();
+    let rules = ValidationRules { ood_threshold, ..Default::default() };
+    let validation = model.inner.validate_input(&reading, &rules).map_err(PyValueError::new_err)?;
+    let dict = PyDict::new(py);
+    dict.set_item("warnings", validation.warnings.iter().map(ToString::to_string).collect::<Vec<_>>())?;
+    dict.set_item("ood_score", validation.ood_score)?;
+    Ok(dict)
+}
+
+/// Classifies a `(n, features)` batch. Returns `(class_indices, probabilities)`.
+#[pyfunction]
+fn predict_batch<'py>(
+    py: Python<'py>,
+    model: PyRef<'_, PyTrainedModel>,
+    x: PyReadonlyArray2<'py, f64>,
+) -> PyResult<BatchPrediction<'py>> {
+    let x = to_array2(&x);
+    if x.ncols() != model.n_features() {
+        return Err(PyValueError::new_err(format!(
+            "expected {} feature columns, got {}", model.n_features(), x.ncols()
+        )));
+    }
+
+    let probabilities = model.inner.network.predict_batch(&x, &model.inner.stats);
+    let classes: Vec<usize> = probabilities.axis_iter(Axis(0)).map(argmax).collect();
+    Ok((PyArray1::from_vec(py, classes), to_pyarray2(py, &probabilities)?))
+}
+
+/// Predicts the targets of a regressor for a `(n, features)` batch, in the
+/// units of the training data.
+#[pyfunction]
+fn predict_values<'py>(
+    py: Python<'py>,
+    model: PyRef<'_, PyTrainedModel>,
+    x: PyReadonlyArray2<'py, f64>,
+) -> PyResult<Bound<'py, PyArray2<f64>>> {
+    let x = to_array2(&x);
+    if !model.inner.is_regression() {
+        return Err(PyValueError::new_err("the model is a classifier; use predict_batch"));
+    }
+    if x.ncols() != model.n_features() {
+        return Err(PyValueError::new_err(format!(
+            "expected {} feature columns, got {}", model.n_features(), x.ncols()
+        )));
+    }
+    to_pyarray2(py, &model.inner.predict_values(&x))
+}
+
+/// Evaluates a model either on a CSV file (`evaluate(model, "data.csv")`) or
+/// on arrays of raw features and integer labels (`evaluate(model, x, y)`).
+#[pyfunction]
+#[pyo3(signature = (model, data, labels = None))]
+fn evaluate<'py>(
+    py: Python<'py>,
+    model: PyRef<'_, PyTrainedModel>,
+    data: &Bound<'py, PyAny>,
+    labels: Option<PyReadonlyArray1<'py, i64>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let result = if let Ok(csv_path) = data.extract::<String>() {
+        evaluation::evaluate_csv(&model.inner, &csv_path)
+            .map_err(|e| PyIOError::new_err(format!("failed to evaluate {}: {}", csv_path, e)))?
+    } else {
+        let x = to_array2(&data.extract::<PyReadonlyArray2<'py, f64>>()?);
+        let labels = labels.ok_or_else(|| PyValueError::new_err("labels are required when data is an array"))?;
+        let labels = labels.as_array();
+        if x.ncols() != model.n_features() || x.nrows() != labels.len() {
+            return Err(PyValueError::new_err(format!(
+                "expected data of shape (n, {}) and n labels, got {:?} and {}",
+                model.n_features(), x.shape(), labels.len()
+            )));
+        }
+
+        let n_classes = model.inner.classes().len();
+        let mut y = Array2::zeros((labels.len(), n_classes));
+        for (row, &label) in labels.iter().enumerate() {
+            if label < 0 || label as usize >= n_classes {
+                return Err(PyValueError::new_err(format!("label {} is out of range", label)));
+            }
+            y[[row, label as usize]] = 1.0;
+        }
+        evaluation::evaluate(&model.inner, &x, &y)
+    };
+
+    evaluation_to_dict(py, &result)
+}
+
+/// Writes confusion matrix, ROC, precision-recall, calibration and
+/// probability plots of a classifier on a labelled CSV file into
+/// `output_dir`, and returns their paths. `format` is "png" or "svg".
+#[pyfunction]
+#[pyo3(signature = (model, csv_path, output_dir, format = "png"))]
+fn write_diagnostics(model: PyRef<'_, PyTrainedModel>, csv_path: &str, output_dir: &str, format: &str) -> PyResult<Vec<String>> {
+    let format = match format {
+        "png" => PlotFormat::Png,
+        "svg" => PlotFormat::Svg,
+        _ => return Err(PyValueError::new_err(format!("unknown plot format '{}'", format))),
+    };
+    let diagnostics = diagnose_csv(&model.inner, csv_path)
+        .map_err(|e| PyIOError::new_err(format!("failed to evaluate {}: {}", csv_path, e)))?;
+    let paths = write_diagnostic_plots(&diagnostics, output_dir, format)
+        .map_err(|e| PyIOError::new_err(format!("failed to write diagnostic plots: {}", e)))?;
+    Ok(paths.iter().map(|path| path.to_string_lossy().into_owned()).collect())
+}
+
+/// Writes a self-contained HTML report of a model evaluated on a labelled CSV file.
+#[pyfunction]
+fn write_report(model: PyRef<'_, PyTrainedModel>, csv_path: &str, path: &str) -> PyResult<()> {
+    let (x, y) = model.inner.config.load_dataset(csv_path)
+        .map_err(|e| PyIOError::new_err(format!("failed to evaluate {}: {}", csv_path, e)))?;
+    report::write_report(path, &model.inner, "Evaluation report", csv_path, &x, &y)
+        .map_err(|e| PyIOError::new_err(format!("failed to write report {}: {}", path, e)))
+}
+
+fn evaluation_to_dict<'py>(py: Python<'py>, evaluation: &Evaluation) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("samples", evaluation.samples)?;
+    dict.set_item("accuracy", evaluation.accuracy)?;
+    dict.set_item("loss", evaluation.loss)?;
+    let confusion = PyArray2::from_vec2(py, &evaluation.confusion_matrix)
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    dict.set_item("confusion_matrix", confusion)?;
+    Ok(dict)
+}
+
+/// Sets the threads used for batch prediction and for training. 0 uses every core.
+#[pyfunction]
+fn set_threads(threads: usize) -> PyResult<()> {
+    parallel::set_threads(threads).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+#[pymodule]
+fn airquality_predictor(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTrainedModel>()?;
+    m.add("CLASSES", CLASSES)?;
+    m.add_function(wrap_pyfunction!(train, m)?)?;
+    m.add_function(wrap_pyfunction!(train_from_config, m)?)?;
+    m.add_function(wrap_pyfunction!(load_model, m)?)?;
+    m.add_function(wrap_pyfunction!(load_production_model, m)?)?;
+    m.add_function(wrap_pyfunction!(predict, m)?)?;
+    m.add_function(wrap_pyfunction!(predict_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_input, m)?)?;
+    m.add_function(wrap_pyfunction!(predict_values, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate, m)?)?;
+    m.add_function(wrap_pyfunction!(write_diagnostics, m)?)?;
+    m.add_function(wrap_pyfunction!(write_report, m)?)?;
+    m.add_function(wrap_pyfunction!(set_threads, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Default, Serialize, Clone)]
+pub struct RouteMetrics {
+    pub requests: u64,
+    pub errors: u64,
+    pub total_latency_ms: f64,
+    pub max_latency_ms: f64,
+}
+
+#[derive(Serialize)]
+pub struct MetricsSnapshot {
+    pub uptime_secs: f64,
+    pub requests: u64,
+    pub errors: u64,
+    pub predictions: u64,
+    pub model_reloads: u64,
+    pub failed_reloads: u64,
+    pub routes: BTreeMap<String, RouteMetrics>,
+}
+
+struct Counters {
+    predictions: u64,
+    model_reloads: u64,
+    failed_reloads: u64,
+    routes: BTreeMap<String, RouteMetrics>,
+}
+
+/// Request counters shared by all server workers.
+pub struct Metrics {
+    started: Instant,
+    counters: Mutex<Counters>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            started: Instant::now(),
+            counters: Mutex::new(Counters {
+                predictions: 0,
+                model_reloads: 0,
+                failed_reloads: 0,
+                routes: BTreeMap::new(),
+            }),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn record_request(&self, route: &str, status: u16, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let mut counters = self.counters.lock().unwrap();
+        let entry = counters.routes.entry(route.to_string()).or_default();
+        entry.requests += 1;
+        if status >= 400 {
+            entry.errors += 1;
+        }
+        entry.total_latency_ms += latency_ms;
+        entry.max_latency_ms = entry.max_latency_ms.max(latency_ms);
+    }
+
+    pub fn record_predictions(&self, count: usize) {
+        self.counters.lock().unwrap().predictions += count as u64;
+    }
+
+    pub fn record_reload(&self, succeeded: bool) {
+        let mut counters = self.counters.lock().unwrap();
+        if succeeded {
+            counters.model_reloads += 1;
+        } else {
+            counters.failed_reloads += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let counters = self.counters.lock().unwrap();
+        MetricsSnapshot {
+            uptime_secs: self.started.elapsed().as_secs_f64(),
+            requests: counters.routes.values().map(|r| r.requests).sum(),
+            errors: counters.routes.values().map(|r| r.errors).sum(),
+            predictions: counters.predictions,
+            model_reloads: counters.model_reloads,
+            failed_reloads: counters.failed_reloads,
+            routes: counters.routes.clone(),
+        }
+    }
+}
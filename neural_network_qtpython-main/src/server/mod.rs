@@ -0,0 +1,229 @@
+pub mod metrics;
+pub mod routes;
+
+use crate::server::metrics::Metrics;
+use crate::training::drift::{DriftMonitor, DriftThresholds, DEFAULT_DRIFT_WINDOW};
+use crate::training::registry::{ModelRegistry, DEFAULT_MODEL_NAME};
+use crate::training::trainer::TrainedModel;
+use crate::utils::io::load_model;
+use std::error::Error;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tiny_http::{Header, Response, Server};
+
+/// Largest request body read, in bytes; longer bodies get 413.
+pub const MAX_BODY: u64 = 10 * 1024 * 1024;
+
+pub struct ServerConfig {
+    pub addr: String,
+    pub model_path: PathBuf,
+    /// Serve the production version of `model_name` from this registry
+    /// instead of `model_path`, following promotions and rollbacks.
+    pub registry: Option<PathBuf>,
+    pub model_name: String,
+    pub workers: usize,
+    /// How often the model file is checked for changes.
+    pub reload_interval: Duration,
+    /// Latest predicted readings `GET /drift` compares with the training data.
+    pub drift_window: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            addr: "127.0.0.1:8080".to_string(),
+            model_path: PathBuf::from("trained_model.bin"),
+            registry: None,
+            model_name: DEFAULT_MODEL_NAME.to_string(),
+            workers: 4,
+            reload_interval: Duration::from_secs(2),
+            drift_window: DEFAULT_DRIFT_WINDOW,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The model file to serve right now.
+    pub fn resolve_model_path(&self) -> Result<PathBuf, Box<dyn Error>> {
+        match &self.registry {
+            Some(root) => ModelRegistry::new(root).production_model_path(&self.model_name),
+            None => Ok(self.model_path.clone()),
+        }
+    }
+}
+
+pub struct LoadedModel {
+    pub model: TrainedModel,
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
+    pub loaded_at: SystemTime,
+}
+
+impl LoadedModel {
+    fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let model = load_model(&path.to_string_lossy())?;
+        Ok(LoadedModel {
+            model,
+            path: path.to_path_buf(),
+            modified,
+            loaded_at: SystemTime::now(),
+        })
+    }
+}
+
+/// State shared by the request workers and the reload watcher.
+pub struct AppState {
+    pub config: ServerConfig,
+    pub model: RwLock<LoadedModel>,
+    pub metrics: Metrics,
+    /// Drift of the predicted readings; `None` for models without a
+    /// training input profile.
+    pub drift: Mutex<Option<DriftMonitor>>,
+}
+
+impl AppState {
+    /// Reloads the model if the file changed since it was last loaded, or if
+    /// the registry now points at another file. A file that fails to load is
+    /// reported and the current model stays in service.
+    pub fn reload_if_changed(&self) -> bool {
+        let Ok(path) = self.config.resolve_model_path() else {
+            return false;
+        };
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        {
+            let loaded = self.model.read().unwrap();
+            if modified.is_none() || (path == loaded.path && modified == loaded.modified) {
+                return false;
+            }
+        }
+
+        match LoadedModel::load(&path) {
+            Ok(loaded) => {
+                println!("Reloaded model from {}", path.display());
+                *self.drift.lock().unwrap() = drift_monitor(&loaded.model, self.config.drift_window);
+                *self.model.write().unwrap() = loaded;
+                self.metrics.record_reload(true);
+                true
+            }
+            Err(e) => {
+                println!("Failed to reload model from {}: {}", path.display(), e);
+                // Remember the file so a broken one is not retried on every tick
+                let mut loaded = self.model.write().unwrap();
+                loaded.path = path;
+                loaded.modified = modified;
+                self.metrics.record_reload(false);
+                false
+            }
+        }
+    }
+}
+
+fn drift_monitor(model: &TrainedModel, window: usize) -> Option<DriftMonitor> {
+    let profile = model.profile.clone()?;
+    Some(DriftMonitor::new(profile, DriftThresholds::default(), window))
+}
+
+/// HTTP inference server for a `TrainedModel` file.
+pub struct ModelServer {
+    http: Server,
+    state: Arc<AppState>,
+    shutdown: AtomicBool,
+}
+
+impl ModelServer {
+    /// Loads the model and binds the listening socket. Use port 0 to let the
+    /// OS pick a free port, then read it back with `local_addr`.
+    pub fn bind(config: ServerConfig) -> Result<Self, Box<dyn Error>> {
+        let path = config.resolve_model_path()?;
+        let loaded = LoadedModel::load(&path)
+            .map_err(|e| format!("failed to load model {}: {}", path.display(), e))?;
+        let http = Server::http(&config.addr).map_err(|e| format!("failed to bind {}: {}", config.addr, e))?;
+
+        Ok(ModelServer {
+            http,
+            state: Arc::new(AppState {
+                drift: Mutex::new(drift_monitor(&loaded.model, config.drift_window)),
+                config,
+                model: RwLock::new(loaded),
+                metrics: Metrics::default(),
+            }),
+            shutdown: AtomicBool::new(false),
+        })
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Serves requests until `shutdown` is called from another thread.
+    pub fn run(&self) {
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut last_check = Instant::now();
+                while !self.shutdown.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(100));
+                    if last_check.elapsed() >= self.state.config.reload_interval {
+                        self.state.reload_if_changed();
+                        last_check = Instant::now();
+                    }
+                }
+            });
+
+            for _ in 0..self.state.config.workers.max(1) {
+                scope.spawn(|| {
+                    while !self.shutdown.load(Ordering::Relaxed) {
+                        match self.http.recv_timeout(Duration::from_millis(200)) {
+                            Ok(Some(request)) => self.serve(request),
+                            Ok(None) => {}
+                            Err(e) => println!("Failed to receive request: {}", e),
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        self.http.unblock();
+    }
+
+    fn serve(&self, mut request: tiny_http::Request) {
+        let started = Instant::now();
+        let method = request.method().as_str().to_uppercase();
+        let path = request.url().split('?').next().unwrap_or("/").to_string();
+
+        let mut body = String::new();
+        let (status, reply) = match request.as_reader().take(MAX_BODY + 1).read_to_string(&mut body) {
+            Ok(read) if read as u64 > MAX_BODY => {
+                (413, serde_json::json!({ "error": format!("request body exceeds {} bytes", MAX_BODY) }))
+            }
+            Ok(_) => routes::handle(&self.state, &method, &path, &body),
+            Err(e) => (400, serde_json::json!({ "error": format!("failed to read body: {}", e) })),
+        };
+
+        let response = Response::from_string(reply.to_string())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        if let Err(e) = request.respond(response) {
+            println!("Failed to send response: {}", e);
+        }
+
+        // Unknown paths share one bucket so probes cannot grow the metrics map
+        let route = match path.as_str() {
+            "/health" | "/model" | "/metrics" | "/predict" | "/drift" => format!("{} {}", method, path),
+            _ => "other".to_string(),
+        };
+        self.state.metrics.record_request(&route, status, started.elapsed());
+    }
+}
@@ -0,0 +1,180 @@
+use crate::server::AppState;
+use crate::training::evaluation::argmax;
+use crate::training::registry::ModelRegistry;
+use crate::training::validation::ValidationRules;
+use ndarray::{Array2, Axis};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub type Reply = (u16, Value);
+
+pub fn handle(state: &AppState, method: &str, path: &str, body: &str) -> Reply {
+    match (method, path) {
+        ("GET", "/health") => health(state),
+        ("GET", "/model") => model_info(state),
+        ("GET", "/metrics") => (200, json!(state.metrics.snapshot())),
+        ("POST", "/predict") => predict(state, body),
+        ("GET", "/drift") => drift(state),
+        (_, "/health" | "/model" | "/metrics" | "/predict" | "/drift") => error(405, "method not allowed"),
+        _ => error(404, "not found"),
+    }
+}
+
+fn error(status: u16, message: &str) -> Reply {
+    (status, json!({ "error": message }))
+}
+
+fn unix_secs(time: Option<SystemTime>) -> Value {
+    time.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(Value::Null, |d| json!(d.as_secs_f64()))
+}
+
+fn health(state: &AppState) -> Reply {
+    let loaded = state.model.read().unwrap();
+    (200, json!({
+        "status": "ok",
+        "model_path": loaded.path.display().to_string(),
+        "model_loaded_at": unix_secs(Some(loaded.loaded_at)),
+    }))
+}
+
+fn model_info(state: &AppState) -> Reply {
+    let loaded = state.model.read().unwrap();
+    let model = &loaded.model;
+    (200, json!({
+        "path": loaded.path.display().to_string(),
+        "loaded_at": unix_secs(Some(loaded.loaded_at)),
+        "modified_at": unix_secs(loaded.modified),
+        "features": model.features(),
+        "classes": model.classes(),
+        "targets": model.config.data.targets,
+        "layer_sizes": model.network.layer_sizes(),
+        "config": model.config,
+        "normalization": {
+            "mean": model.stats.mean.to_vec(),
+            "std": model.stats.std.to_vec(),
+        },
+        "metrics": {
+            "final_accuracy": model.final_accuracy,
+        },
+        "registry": registry_version(state, &loaded.path),
+    }))
+}
+
+/// Drift of the latest predicted readings from the training inputs.
+fn drift(state: &AppState) -> Reply {
+    match state.drift.lock().unwrap().as_ref() {
+        Some(monitor) => {
+            let report = monitor.report();
+            (200, json!({ "severity": report.severity(), "window": state.config.drift_window, "report": report }))
+        }
+        None => error(404, "the model has no training input profile to compare with"),
+    }
+}
+
+/// The registry entry of the served model file, when serving from a registry.
+fn registry_version(state: &AppState, path: &Path) -> Value {
+    let Some(root) = &state.config.registry else {
+        return Value::Null;
+    };
+    let registry = ModelRegistry::new(root);
+    let name = &state.config.model_name;
+    registry.index(name)
+        .ok()
+        .and_then(|index| index.versions.into_iter().find(|v| registry.model_path(name, v.version) == path))
+        .map_or(Value::Null, |version| json!({ "name": name, "version": version }))
+}
+
+/// Accepts one reading (an object keyed by feature name or an array of
+/// feature values) or a batch (`{"instances": [...]}` or an array of readings).
+fn predict(state: &AppState, body: &str) -> Reply {
+    let value: Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(e) => return error(400, &format!("invalid JSON: {}", e)),
+    };
+
+    let (readings, batch) = match &value {
+        Value::Object(map) if map.contains_key("instances") => match map.get("instances") {
+            Some(Value::Array(items)) => (items.iter().collect::<Vec<_>>(), true),
+            _ => return error(400, "'instances' must be an array"),
+        },
+        Value::Array(items) if items.iter().any(|v| v.is_array() || v.is_object()) => {
+            (items.iter().collect(), true)
+        }
+        _ => (vec![&value], false),
+    };
+
+    let loaded = state.model.read().unwrap();
+    let model = &loaded.model;
+    if model.config.time_series.is_some() {
+        return error(400, "the model forecasts from a time series and cannot score single readings");
+    }
+    let features = model.features();
+    let mut inputs = Vec::with_capacity(readings.len() * features.len());
+    for (i, reading) in readings.iter().enumerate() {
+        match parse_reading(reading, features) {
+            Ok(values) => inputs.extend(values),
+            Err(e) if batch => return error(400, &format!("instance {}: {}", i, e)),
+            Err(e) => return error(400, &e),
+        }
+    }
+    let x = Array2::from_shape_vec((readings.len(), features.len()), inputs).unwrap();
+
+    let output = model.predict_values(&x);
+    let classes = model.classes();
+    let targets = &model.config.data.targets;
+    let rules = ValidationRules::default();
+    let predictions: Result<Vec<Value>, String> = output.axis_iter(Axis(0))
+        .zip(x.rows())
+        .map(|(row, reading)| {
+            let validation = model.validate_input(&reading.to_vec(), &rules)?;
+            let mut prediction = if model.is_regression() {
+                let values: Map<String, Value> = targets.iter().zip(row.iter()).map(|(name, v)| (name.clone(), json!(v))).collect();
+                json!({ "values": values })
+            } else {
+                let class = argmax(row);
+                let probabilities: Map<String, Value> = classes.iter()
+                    .zip(row.iter())
+                    .map(|(name, p)| (name.clone(), json!(p)))
+                    .collect();
+                json!({ "class": class, "category": classes[class], "probabilities": probabilities })
+            };
+            prediction["warnings"] = json!(validation.warnings);
+            prediction["ood_score"] = json!(validation.ood_score);
+            Ok(prediction)
+        })
+        .collect();
+    let mut predictions = match predictions {
+        Ok(predictions) => predictions,
+        Err(e) => return error(400, &e),
+    };
+
+    state.metrics.record_predictions(readings.len());
+    if let Some(monitor) = state.drift.lock().unwrap().as_mut() {
+        monitor.observe(&x);
+    }
+
+    if batch {
+        (200, json!({ "predictions": predictions }))
+    } else {
+        (200, predictions.remove(0))
+    }
+}
+
+fn parse_reading(value: &Value, features: &[String]) -> Result<Vec<f64>, String> {
+    match value {
+        Value::Object(map) => features.iter()
+            .map(|name| {
+                map.get(name)
+                    .and_then(Value::as_f64)
+                    .ok_or_else(|| format!("missing or non-numeric field '{}'", name))
+            })
+            .collect(),
+        Value::Array(items) if items.len() == features.len() => items.iter()
+            .map(|v| v.as_f64().ok_or_else(|| "feature values must be numbers".to_string()))
+            .collect(),
+        Value::Array(items) => Err(format!("expected {} feature values, got {}", features.len(), items.len())),
+        _ => Err(format!("a reading must be an object with fields {:?} or an array of numbers", features)),
+    }
+}
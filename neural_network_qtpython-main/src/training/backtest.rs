@@ -0,0 +1,73 @@
+use crate::model::float::{Float, Precision};
+use crate::training::config::TrainingConfig;
+use crate::training::evaluation::{evaluate, evaluate_regression, Evaluation, RegressionEvaluation};
+use crate::training::trainer::{fit, resolve_seed, training_rng, TrainingControl};
+use crate::data::time_series::chronological_split;
+use crate::utils::parallel;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Held-out metrics of a forecaster trained for one horizon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HorizonBacktest {
+    /// Steps ahead.
+    pub horizon: usize,
+    pub train_samples: usize,
+    /// Set for classifiers.
+    pub classification: Option<Evaluation>,
+    /// Set for regressors.
+    pub regression: Option<RegressionEvaluation>,
+}
+
+/// Trains one forecaster per horizon on all but the latest
+/// `split.validation` of the series and scores it on that latest part.
+/// Every horizon uses the same seed, so they differ only in what they predict.
+pub fn backtest(config: &TrainingConfig, horizons: &[usize]) -> Result<Vec<HorizonBacktest>, Box<dyn Error>> {
+    config.validate()?;
+    if config.time_series.is_none() {
+        return Err("backtesting needs a time_series section in the config".into());
+    }
+    if horizons.contains(&0) {
+        return Err("horizons must be at least 1".into());
+    }
+    if config.split.validation <= 0.0 {
+        return Err("backtesting needs split.validation > 0 to hold out the latest readings".into());
+    }
+
+    let seed = resolve_seed(config.seed);
+    let threads = match config.threads {
+        0 => parallel::threads(),
+        threads => threads,
+    };
+    horizons.iter()
+        .map(|&horizon| {
+            let mut config = TrainingConfig { seed: Some(seed), threads, ..config.clone() };
+            if let Some(series) = config.time_series.as_mut() {
+                series.horizon = horizon;
+            }
+            match config.precision {
+                Precision::F32 => backtest_horizon::<f32>(&config),
+                Precision::F64 => backtest_horizon::<f64>(&config),
+            }
+        })
+        .collect()
+}
+
+fn backtest_horizon<F: Float>(config: &TrainingConfig) -> Result<HorizonBacktest, Box<dyn Error>> {
+    let series = config.time_series.as_ref().expect("checked by backtest");
+    let split = chronological_split(&config.load_windows(&config.data.path, true)?, config.split.validation, series);
+    let (x_test, y_test) = (split.x_val.clone(), split.y_val.clone());
+    let train_samples = split.x_train.nrows();
+    if x_test.nrows() == 0 {
+        return Err(format!("horizon {} leaves no readings to test on", series.horizon).into());
+    }
+
+    let model = fit::<F, _, _>(config, split, &mut training_rng(config.seed.unwrap_or_default()), |_| TrainingControl::Continue)?;
+    let regression = config.data.is_regression();
+    Ok(HorizonBacktest {
+        horizon: series.horizon,
+        train_samples,
+        classification: (!regression).then(|| evaluate(&model, &x_test, &y_test)),
+        regression: regression.then(|| evaluate_regression(&model, &x_test, &y_test)),
+    })
+}
@@ -0,0 +1,542 @@
+use crate::data::preprocessing::{load_columns, load_targets, Normalization, CLASSES, FEATURES};
+use crate::data::time_series::{load_series, windows, Outputs, TimeSeriesConfig, Windows};
+use crate::model::float::Precision;
+use crate::model::init::Initializer;
+use crate::model::layers::Activation;
+use crate::model::loss::Loss;
+use crate::utils::parallel;
+use crate::utils::plot::{PlotFormat, PlotOptions};
+use ndarray::Array2;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Everything needed to reproduce a training run. Every section and field is
+/// optional in a config file; missing values take the defaults below.
+///
+/// ```toml
+/// seed = 42
+///
+/// [data]
+/// path = "airquality.csv"
+///
+/// [architecture]
+/// hidden_layers = [16, 16]
+/// activation = "tanh"
+///
+/// [schedule]
+/// epochs = 2000
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrainingConfig {
+    pub data: DataConfig,
+    /// Forecast from timestamped readings instead of classifying rows
+    /// independently.
+    pub time_series: Option<TimeSeriesConfig>,
+    pub preprocessing: PreprocessingConfig,
+    pub architecture: ArchitectureConfig,
+    pub optimizer: OptimizerConfig,
+    pub schedule: ScheduleConfig,
+    pub regularization: RegularizationConfig,
+    pub loss: Loss,
+    pub divergence: DivergenceConfig,
+    pub split: SplitConfig,
+    /// Seed for every random choice of the run: the train/validation split,
+    /// weight initialization, mini-batch order and dropout masks. When unset
+    /// a seed is drawn at random and recorded in the trained model.
+    pub seed: Option<u64>,
+    /// Threads computing each gradient step; 0 uses the shared pool (every
+    /// core unless changed with `utils::parallel::set_threads`). A run is
+    /// bit-identical only for the same seed and thread count.
+    pub threads: usize,
+    /// Precision of the network parameters and arithmetic.
+    pub precision: Precision,
+    pub output: OutputConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataConfig {
+    /// Labelled CSV file with a header row.
+    pub path: String,
+    /// Input columns, in the order the network sees them.
+    pub features: Vec<String>,
+    /// Column holding the class label.
+    pub label: String,
+    /// Label values, in the order of the network outputs.
+    pub classes: Vec<String>,
+    /// Numeric columns to predict. When set, the network is a regressor with
+    /// a linear output per target, and `label` and `classes` are not used.
+    pub targets: Vec<String>,
+}
+
+impl DataConfig {
+    pub fn is_regression(&self) -> bool {
+        !self.targets.is_empty()
+    }
+
+    /// Names of the network outputs: the targets of a regressor, otherwise
+    /// the classes.
+    pub fn outputs(&self) -> &[String] {
+        if self.is_regression() { &self.targets } else { &self.classes }
+    }
+
+    /// Reads the features and the one-hot labels or regression targets of a CSV file.
+    pub fn load(&self, csv_path: &str) -> Result<(Array2<f64>, Array2<f64>), Box<dyn Error>> {
+        if self.is_regression() {
+            load_targets(csv_path, &self.features, &self.targets)
+        } else {
+            load_columns(csv_path, &self.features, &self.label, &self.classes)
+        }
+    }
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        DataConfig {
+            path: "airquality.csv".to_string(),
+            features: FEATURES.iter().map(|f| f.to_string()).collect(),
+            label: "kategori".to_string(),
+            classes: CLASSES.iter().map(|c| c.to_string()).collect(),
+            targets: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreprocessingConfig {
+    pub normalization: Normalization,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchitectureConfig {
+    /// Units per hidden layer. The input and output sizes follow from the data.
+    pub hidden_layers: Vec<usize>,
+    /// Activation of the hidden layers. The output layer is softmax for
+    /// classifiers and linear for regressors.
+    pub activation: Activation,
+    /// Batch-normalize the output of every hidden layer.
+    pub batch_norm: bool,
+    /// Weight initializer of every dense layer without its own entry in
+    /// `layer_initializers`.
+    pub initializer: Initializer,
+    /// Initializers of the dense layers in order, the output layer last.
+    /// May be shorter than the number of layers.
+    pub layer_initializers: Vec<Initializer>,
+    /// Saved model to copy the initial parameters and input normalization
+    /// from. Its layer sizes and features must match this configuration.
+    pub warm_start: Option<String>,
+}
+
+impl Default for ArchitectureConfig {
+    fn default() -> Self {
+        ArchitectureConfig {
+            hidden_layers: vec![10, 10, 10],
+            activation: Activation::Relu,
+            batch_norm: false,
+            initializer: Initializer::Auto,
+            layer_initializers: Vec::new(),
+            warm_start: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OptimizerConfig {
+    /// Step size on the gradient of the loss summed over a batch, so larger
+    /// batches take larger steps.
+    pub learning_rate: f64,
+    /// Rows per gradient step; 0 trains on the whole training set at once.
+    pub batch_size: usize,
+    /// Largest gradient norm; longer gradients are scaled down. 0 disables.
+    pub clip_norm: f64,
+    /// Largest absolute value of a gradient component. 0 disables.
+    pub clip_value: f64,
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        OptimizerConfig {
+            learning_rate: 0.001,
+            batch_size: 0,
+            clip_norm: 0.0,
+            clip_value: 0.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    pub epochs: usize,
+    /// Inverse-time decay: the rate at epoch `e` is `learning_rate / (1 + lr_decay * e)`.
+    pub lr_decay: f64,
+    /// Number of epochs between evaluation steps (and progress callbacks).
+    pub eval_interval: usize,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        ScheduleConfig {
+            epochs: 1000,
+            lr_decay: 0.1,
+            eval_interval: 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegularizationConfig {
+    /// L2 regularization strength, added to the gradient of the loss summed
+    /// over a batch.
+    pub l2: f64,
+    /// Fraction of every hidden layer's outputs dropped during training.
+    pub dropout: f64,
+}
+
+impl Default for RegularizationConfig {
+    fn default() -> Self {
+        RegularizationConfig { l2: 0.01, dropout: 0.0 }
+    }
+}
+
+/// What to do when the loss, the gradients or the weights stop being finite.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergencePolicy {
+    /// Fail the run.
+    #[default]
+    Abort,
+    /// Restore the last evaluated network with a finite loss and go on.
+    Rollback,
+    /// Roll back and multiply the learning rate by `lr_factor`.
+    ReduceLr,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DivergenceConfig {
+    pub policy: DivergencePolicy,
+    pub lr_factor: f64,
+    /// Recoveries allowed before the run fails anyway.
+    pub max_recoveries: usize,
+}
+
+impl Default for DivergenceConfig {
+    fn default() -> Self {
+        DivergenceConfig {
+            policy: DivergencePolicy::Abort,
+            lr_factor: 0.5,
+            max_recoveries: 5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SplitConfig {
+    /// Fraction of the rows held out for validation.
+    pub validation: f64,
+}
+
+impl Default for SplitConfig {
+    fn default() -> Self {
+        SplitConfig { validation: 0.2 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub model: String,
+    /// Training plot; `.svg` paths are written as SVG, anything else as PNG.
+    pub plot: String,
+    pub plot_options: PlotOptions,
+    /// Also write the training history to this file, as JSON for `.json`
+    /// paths and CSV otherwise. The model stores it either way.
+    pub history: Option<String>,
+    /// Directory for the confusion matrix, ROC, precision-recall, calibration
+    /// and probability plots of the validation data. Classifiers only.
+    pub diagnostics: Option<String>,
+    pub diagnostics_format: PlotFormat,
+    /// Self-contained HTML report of the run, on the validation data.
+    pub report: Option<String>,
+    /// Track the run in a new directory under this one, which then receives
+    /// the model, plot, history and diagnostics instead of the paths above.
+    pub runs_dir: Option<String>,
+    /// Print progress every this many epochs; 0 disables printing.
+    pub log_every: usize,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig {
+            model: "trained_model.bin".to_string(),
+            plot: "training_plot.png".to_string(),
+            plot_options: PlotOptions::default(),
+            history: None,
+            diagnostics: None,
+            diagnostics_format: PlotFormat::default(),
+            report: None,
+            runs_dir: None,
+            log_every: 100,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(String, std::io::Error),
+    Parse(String),
+    /// One message per invalid field, prefixed with the field path.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "failed to read config {}: {}", path, e),
+            ConfigError::Parse(message) => write!(f, "invalid config: {}", message),
+            ConfigError::Invalid(errors) => write!(f, "invalid config: {}", errors.join("; ")),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl TrainingConfig {
+    /// Loads and validates a config file. `.json` files are parsed as JSON,
+    /// anything else as TOML.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let config: TrainingConfig = parse_file(path.as_ref())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<Self, ConfigError> {
+        let config: TrainingConfig = toml::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json_str(text: &str) -> Result<Self, ConfigError> {
+        let config: TrainingConfig = serde_json::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        toml::to_string_pretty(self).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// Checks every field and reports all problems at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, field: &str, message: &str| {
+            if !ok {
+                errors.push(format!("{}: {}", field, message));
+            }
+        };
+
+        check(!self.data.path.is_empty(), "data.path", "must not be empty");
+        check(!self.data.features.is_empty(), "data.features", "must list at least one column");
+        check(!has_duplicates(&self.data.features), "data.features", "must not contain duplicates");
+        if self.data.is_regression() {
+            check(!has_duplicates(&self.data.targets), "data.targets", "must not contain duplicates");
+            // A forecast may predict the future of an input column
+            check(self.time_series.is_some() || !self.data.targets.iter().any(|t| self.data.features.contains(t)),
+                "data.targets", "must not also be feature columns");
+        } else {
+            check(!self.data.features.contains(&self.data.label), "data.label", "must not also be a feature column");
+            check(self.data.classes.len() >= 2, "data.classes", "must list at least two classes");
+            check(!has_duplicates(&self.data.classes), "data.classes", "must not contain duplicates");
+        }
+
+        if let Some(series) = &self.time_series {
+            check(!series.timestamp.is_empty(), "time_series.timestamp", "must not be empty");
+            check(!self.data.features.contains(&series.timestamp), "time_series.timestamp",
+                "must not also be a feature column");
+            if let Some(station) = &series.station {
+                check(!self.data.features.contains(station), "time_series.station", "must not also be a feature column");
+            }
+            check(series.step_seconds > 0, "time_series.step_seconds", "must be at least 1");
+            check(series.horizon > 0, "time_series.horizon", "must be at least 1");
+            check(series.lags.iter().all(|&lag| lag > 0), "time_series.lags", "must all be at least 1");
+            check(series.rolling_windows.iter().all(|&window| window > 1), "time_series.rolling_windows",
+                "must all be at least 2");
+        }
+
+        for (i, &units) in self.architecture.hidden_layers.iter().enumerate() {
+            check(units > 0, &format!("architecture.hidden_layers[{}]", i), "must be at least 1");
+        }
+        check(self.architecture.activation != Activation::Softmax, "architecture.activation",
+            "softmax is reserved for the output layer");
+        let dense_layers = self.architecture.hidden_layers.len() + 1;
+        check(self.architecture.layer_initializers.len() <= dense_layers, "architecture.layer_initializers",
+            &format!("has more entries than the {} dense layers", dense_layers));
+        let initializers = std::iter::once(("architecture.initializer".to_string(), &self.architecture.initializer))
+            .chain(self.architecture.layer_initializers.iter().enumerate()
+                .map(|(i, init)| (format!("architecture.layer_initializers[{}]", i), init)));
+        for (field, initializer) in initializers {
+            if let Initializer::Constant(value) = initializer {
+                check(value.is_finite(), &field, "constant must be a finite number");
+            }
+        }
+        if let Some(path) = &self.architecture.warm_start {
+            check(!path.is_empty(), "architecture.warm_start", "must not be empty");
+        }
+
+        check(is_positive(self.optimizer.learning_rate), "optimizer.learning_rate", "must be a positive number");
+        check(self.optimizer.clip_norm >= 0.0 && self.optimizer.clip_norm.is_finite(), "optimizer.clip_norm",
+            "must be a non-negative number");
+        check(self.optimizer.clip_value >= 0.0 && self.optimizer.clip_value.is_finite(), "optimizer.clip_value",
+            "must be a non-negative number");
+        check(self.divergence.lr_factor > 0.0 && self.divergence.lr_factor < 1.0, "divergence.lr_factor",
+            "must be in (0, 1)");
+        check(self.threads <= parallel::MAX_THREADS, "threads", &format!("must be at most {}", parallel::MAX_THREADS));
+        check(self.schedule.epochs > 0, "schedule.epochs", "must be at least 1");
+        check(self.schedule.lr_decay >= 0.0 && self.schedule.lr_decay.is_finite(), "schedule.lr_decay",
+            "must be a non-negative number");
+        check(self.schedule.eval_interval > 0, "schedule.eval_interval", "must be at least 1");
+        check(self.regularization.l2 >= 0.0 && self.regularization.l2.is_finite(), "regularization.l2",
+            "must be a non-negative number");
+        check((0.0..1.0).contains(&self.regularization.dropout), "regularization.dropout", "must be in [0, 1)");
+
+        check(self.loss.is_classification() != self.data.is_regression(), "loss.kind",
+            if self.data.is_regression() {
+                "regression targets need mse, mae or huber"
+            } else {
+                "mse, mae and huber need data.targets"
+            });
+        let class_weights = match &self.loss {
+            Loss::CrossEntropy { label_smoothing, class_weights } => {
+                check((0.0..1.0).contains(label_smoothing), "loss.label_smoothing", "must be in [0, 1)");
+                class_weights.as_slice()
+            }
+            Loss::Focal { gamma, class_weights } => {
+                check(*gamma >= 0.0 && gamma.is_finite(), "loss.gamma", "must be a non-negative number");
+                class_weights.as_slice()
+            }
+            Loss::Huber { delta } => {
+                check(is_positive(*delta), "loss.delta", "must be a positive number");
+                &[]
+            }
+            Loss::Mse | Loss::Mae => &[],
+        };
+        check(class_weights.is_empty() || class_weights.len() == self.data.classes.len(), "loss.class_weights",
+            "must list one weight per class");
+        check(class_weights.iter().all(|&w| w >= 0.0 && w.is_finite()), "loss.class_weights",
+            "must be non-negative numbers");
+        check((0.0..1.0).contains(&self.split.validation), "split.validation", "must be in [0, 1)");
+        check(!self.output.model.is_empty(), "output.model", "must not be empty");
+        check(!self.output.plot.is_empty(), "output.plot", "must not be empty");
+        check(self.output.plot_options.width >= 200 && self.output.plot_options.height >= 200, "output.plot_options",
+            "width and height must be at least 200 pixels");
+        if let Some(path) = &self.output.history {
+            check(!path.is_empty(), "output.history", "must not be empty");
+        }
+        if let Some(dir) = &self.output.diagnostics {
+            check(!dir.is_empty(), "output.diagnostics", "must not be empty");
+            check(!self.data.is_regression(), "output.diagnostics", "diagnostic plots need a classifier");
+        }
+        if let Some(path) = &self.output.report {
+            check(!path.is_empty(), "output.report", "must not be empty");
+        }
+        if let Some(dir) = &self.output.runs_dir {
+            check(!dir.is_empty(), "output.runs_dir", "must not be empty");
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(ConfigError::Invalid(errors)) }
+    }
+
+    /// Initializer of every dense layer, the output layer last.
+    pub fn initializers(&self) -> Vec<Initializer> {
+        let architecture = &self.architecture;
+        (0..=architecture.hidden_layers.len())
+            .map(|i| architecture.layer_initializers.get(i).copied().unwrap_or(architecture.initializer))
+            .collect()
+    }
+
+    /// Learning rate and L2 strength for a step on the gradient of the mean
+    /// loss over `rows` rows, which is what backpropagation computes. Both
+    /// are configured for the summed loss, so they are rescaled by `rows`.
+    pub fn mean_loss_step(&self, learning_rate: f64, rows: usize) -> (f64, f64) {
+        let rows = rows.max(1) as f64;
+        (learning_rate * rows, self.regularization.l2 / rows)
+    }
+
+    /// Names of the network inputs: the feature columns, followed by the
+    /// lag and rolling window features of a forecaster.
+    pub fn input_names(&self) -> Vec<String> {
+        match &self.time_series {
+            Some(series) => series.input_names(&self.data.features),
+            None => self.data.features.clone(),
+        }
+    }
+
+    /// Reads the network inputs and the one-hot labels or regression targets
+    /// of a CSV file. Forecasters read them from windows of a time series.
+    pub fn load_dataset(&self, csv_path: &str) -> Result<(Array2<f64>, Array2<f64>), Box<dyn Error>> {
+        match &self.time_series {
+            Some(_) => {
+                let windows = self.load_windows(csv_path, true)?;
+                Ok((windows.x, windows.y.expect("loaded with outputs")))
+            }
+            None => self.data.load(csv_path),
+        }
+    }
+
+    /// Forecasting samples of a timestamped CSV file, with their labels or
+    /// targets if `with_outputs`. Fails unless `time_series` is set.
+    pub fn load_windows(&self, csv_path: &str, with_outputs: bool) -> Result<Windows, Box<dyn Error>> {
+        let series = self.time_series.as_ref().ok_or("the config has no time_series section")?;
+        let data = &self.data;
+        let outputs = match with_outputs {
+            false => None,
+            true if data.is_regression() => Some(Outputs::Targets(&data.targets)),
+            true => Some(Outputs::Label { column: &data.label, classes: &data.classes }),
+        };
+        windows(&load_series(csv_path, series, &data.features, outputs)?, series)
+    }
+
+    /// Sizes of all layers, from the input to the output layer.
+    pub fn layer_sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![self.input_names().len()];
+        sizes.extend(&self.architecture.hidden_layers);
+        sizes.push(self.data.outputs().len());
+        sizes
+    }
+
+    /// Activation of the output layer.
+    pub fn output_activation(&self) -> Activation {
+        if self.data.is_regression() { Activation::Linear } else { Activation::Softmax }
+    }
+}
+
+/// Parses a `.json` file as JSON and anything else as TOML, without validation.
+pub(crate) fn parse_file<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let text = fs::read_to_string(path).map_err(|e| ConfigError::Io(path.display().to_string(), e))?;
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let parsed = if is_json {
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&text).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|message| ConfigError::Parse(format!("{}: {}", path.display(), message)))
+}
+
+fn is_positive(value: f64) -> bool {
+    value > 0.0 && value.is_finite()
+}
+
+fn has_duplicates(values: &[String]) -> bool {
+    values.iter().enumerate().any(|(i, v)| values[..i].contains(v))
+}
@@ -0,0 +1,194 @@
+use crate::model::float::Float;
+use crate::training::evaluation::argmax;
+use crate::training::trainer::TrainedModel;
+use ndarray::{Array2, ArrayView1, Axis};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Bins of the reliability diagram and of the probability histograms.
+pub const CALIBRATION_BINS: usize = 10;
+pub const HISTOGRAM_BINS: usize = 20;
+
+/// A one-vs-rest curve of one class: ROC points are (false positive rate,
+/// true positive rate), precision-recall points are (recall, precision).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Curve {
+    pub class: String,
+    pub points: Vec<(f64, f64)>,
+    /// Area under the ROC curve or average precision; NaN when the class
+    /// never or always occurs.
+    pub area: f64,
+}
+
+/// Samples whose top predicted probability falls in `lower..upper`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    /// Mean top probability of the samples in the bin.
+    pub confidence: f64,
+    /// Fraction of them that were classified correctly.
+    pub accuracy: f64,
+}
+
+/// Predicted probabilities of one class, binned over `0..1`, for samples of
+/// that class and for all other samples.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbabilityHistogram {
+    pub class: String,
+    pub positives: Vec<usize>,
+    pub negatives: Vec<usize>,
+}
+
+/// Everything the diagnostic plots of a classifier show.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub classes: Vec<String>,
+    /// Rows are true classes, columns are predicted classes.
+    pub confusion_matrix: Vec<Vec<usize>>,
+    pub roc: Vec<Curve>,
+    pub precision_recall: Vec<Curve>,
+    pub calibration: Vec<CalibrationBin>,
+    /// Mean gap between confidence and accuracy, weighted by bin size.
+    pub expected_calibration_error: f64,
+    pub histograms: Vec<ProbabilityHistogram>,
+}
+
+impl Diagnostics {
+    /// Diagnostics of predicted class probabilities against one-hot labels.
+    pub fn new(classes: &[String], probabilities: &Array2<f64>, y: &Array2<f64>) -> Self {
+        let labels: Vec<usize> = y.axis_iter(Axis(0)).map(argmax).collect();
+        let predicted: Vec<usize> = probabilities.axis_iter(Axis(0)).map(argmax).collect();
+
+        let mut confusion_matrix = vec![vec![0; classes.len()]; classes.len()];
+        for (&truth, &guess) in labels.iter().zip(&predicted) {
+            confusion_matrix[truth][guess] += 1;
+        }
+
+        let mut roc = Vec::new();
+        let mut precision_recall = Vec::new();
+        let mut histograms = Vec::new();
+        for (class, name) in classes.iter().enumerate() {
+            let scores = probabilities.column(class);
+            let positive: Vec<bool> = labels.iter().map(|&label| label == class).collect();
+            let (points, area) = roc_curve(scores, &positive);
+            roc.push(Curve { class: name.clone(), points, area });
+            let (points, area) = precision_recall_curve(scores, &positive);
+            precision_recall.push(Curve { class: name.clone(), points, area });
+
+            let mut histogram = ProbabilityHistogram {
+                class: name.clone(),
+                positives: vec![0; HISTOGRAM_BINS],
+                negatives: vec![0; HISTOGRAM_BINS],
+            };
+            for (&score, &positive) in scores.iter().zip(&positive) {
+                let bin = bin_of(score, HISTOGRAM_BINS);
+                if positive { histogram.positives[bin] += 1 } else { histogram.negatives[bin] += 1 }
+            }
+            histograms.push(histogram);
+        }
+
+        let confidences: Vec<f64> = probabilities.axis_iter(Axis(0))
+            .zip(&predicted)
+            .map(|(row, &guess)| row[guess])
+            .collect();
+        let correct: Vec<bool> = labels.iter().zip(&predicted).map(|(a, b)| a == b).collect();
+        let calibration = calibration_bins(&confidences, &correct, CALIBRATION_BINS);
+        let samples = labels.len().max(1) as f64;
+        let expected_calibration_error = calibration.iter()
+            .filter(|bin| bin.count > 0)
+            .map(|bin| bin.count as f64 / samples * (bin.confidence - bin.accuracy).abs())
+            .sum();
+
+        Diagnostics {
+            classes: classes.to_vec(),
+            confusion_matrix,
+            roc,
+            precision_recall,
+            calibration,
+            expected_calibration_error,
+            histograms,
+        }
+    }
+}
+
+/// Diagnostics of a classifier on raw inputs and one-hot targets.
+pub fn diagnose<F: Float>(model: &TrainedModel<F>, x: &Array2<f64>, y: &Array2<f64>) -> Diagnostics {
+    Diagnostics::new(model.classes(), &model.predict_batch(x), y)
+}
+
+pub fn diagnose_csv<F: Float>(model: &TrainedModel<F>, csv_path: &str) -> Result<Diagnostics, Box<dyn Error>> {
+    if model.is_regression() {
+        return Err("diagnostic plots need a classifier".into());
+    }
+    let (x, y) = model.config.load_dataset(csv_path)?;
+    Ok(diagnose(model, &x, &y))
+}
+
+fn bin_of(value: f64, bins: usize) -> usize {
+    ((value.clamp(0.0, 1.0) * bins as f64) as usize).min(bins - 1)
+}
+
+/// Positive and negative counts above each distinct score, from the highest
+/// score down. Tied scores form one step.
+fn sweep(scores: ArrayView1<f64>, positive: &[bool]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..positive.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    let mut steps = Vec::new();
+    let (mut tp, mut fp) = (0, 0);
+    for (i, &sample) in order.iter().enumerate() {
+        if positive[sample] { tp += 1 } else { fp += 1 }
+        if order.get(i + 1).is_none_or(|&next| scores[next] != scores[sample]) {
+            steps.push((tp, fp));
+        }
+    }
+    steps
+}
+
+/// ROC points from (0, 0) to (1, 1) and the trapezoidal area under them.
+pub fn roc_curve(scores: ArrayView1<f64>, positive: &[bool]) -> (Vec<(f64, f64)>, f64) {
+    let positives = positive.iter().filter(|&&p| p).count();
+    let negatives = positive.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return (vec![(0.0, 0.0), (1.0, 1.0)], f64::NAN);
+    }
+    let mut points = vec![(0.0, 0.0)];
+    points.extend(sweep(scores, positive).into_iter()
+        .map(|(tp, fp)| (fp as f64 / negatives as f64, tp as f64 / positives as f64)));
+    let area = points.windows(2).map(|w| (w[1].0 - w[0].0) * (w[1].1 + w[0].1) / 2.0).sum();
+    (points, area)
+}
+
+/// Precision-recall points, starting at recall 0, and the average precision.
+pub fn precision_recall_curve(scores: ArrayView1<f64>, positive: &[bool]) -> (Vec<(f64, f64)>, f64) {
+    let positives = positive.iter().filter(|&&p| p).count();
+    if positives == 0 {
+        return (Vec::new(), f64::NAN);
+    }
+    let mut points = vec![(0.0, 1.0)];
+    points.extend(sweep(scores, positive).into_iter()
+        .map(|(tp, fp)| (tp as f64 / positives as f64, tp as f64 / (tp + fp) as f64)));
+    let area = points.windows(2).map(|w| (w[1].0 - w[0].0) * w[1].1).sum();
+    (points, area)
+}
+
+fn calibration_bins(confidences: &[f64], correct: &[bool], bins: usize) -> Vec<CalibrationBin> {
+    let mut sums = vec![(0, 0.0, 0); bins];
+    for (&confidence, &correct) in confidences.iter().zip(correct) {
+        let sum = &mut sums[bin_of(confidence, bins)];
+        sum.0 += 1;
+        sum.1 += confidence;
+        sum.2 += correct as usize;
+    }
+    sums.into_iter()
+        .enumerate()
+        .map(|(i, (count, confidence, correct))| CalibrationBin {
+            lower: i as f64 / bins as f64,
+            upper: (i + 1) as f64 / bins as f64,
+            count,
+            confidence: if count > 0 { confidence / count as f64 } else { f64::NAN },
+            accuracy: if count > 0 { correct as f64 / count as f64 } else { f64::NAN },
+        })
+        .collect()
+}
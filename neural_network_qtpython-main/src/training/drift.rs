@@ -0,0 +1,397 @@
+use crate::data::preprocessing::load_features;
+use crate::model::float::Float;
+use crate::training::trainer::TrainedModel;
+use ndarray::{Array2, ArrayView1, Axis};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+/// Bins of the histograms PSI compares. Bin edges are training deciles.
+pub const PSI_BINS: usize = 10;
+
+/// Readings the streaming monitor compares, unless asked otherwise.
+pub const DEFAULT_DRIFT_WINDOW: usize = 500;
+
+/// Floor on bin shares, so that an empty bin does not make PSI infinite.
+const MIN_SHARE: f64 = 1e-4;
+
+/// Training distribution of one network input.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FeatureProfile {
+    pub feature: String,
+    pub count: usize,
+    pub mean: f64,
+    pub std: f64,
+    /// Percentiles 0 to 100, so the first is the minimum and the last the
+    /// maximum.
+    pub percentiles: Vec<f64>,
+    /// Inner bin edges of the histogram: the distinct training deciles.
+    pub edges: Vec<f64>,
+    /// Share of training readings per bin, `edges.len() + 1` bins.
+    pub frequencies: Vec<f64>,
+}
+
+impl FeatureProfile {
+    pub fn fit(feature: &str, values: ArrayView1<f64>) -> Self {
+        let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        sorted.sort_by(f64::total_cmp);
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count.max(1) as f64;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count.saturating_sub(1).max(1) as f64;
+        let percentiles: Vec<f64> = (0..=100).map(|p| quantile(&sorted, p as f64 / 100.0)).collect();
+
+        let mut edges: Vec<f64> = (1..PSI_BINS).map(|i| percentiles[i * 100 / PSI_BINS]).collect();
+        edges.dedup();
+        let mut frequencies = vec![0.0; edges.len() + 1];
+        for &value in &sorted {
+            frequencies[bin(&edges, value)] += 1.0 / count as f64;
+        }
+        FeatureProfile { feature: feature.to_string(), count, mean, std: variance.sqrt(), percentiles, edges, frequencies }
+    }
+
+    /// The profile of these readings together with `values`. Count, mean
+    /// and standard deviation are exact; percentiles and bin shares are
+    /// those of the mixture, the old readings following `cdf`.
+    pub fn merge(&self, values: ArrayView1<f64>) -> Self {
+        let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+        sorted.sort_by(f64::total_cmp);
+        if sorted.is_empty() {
+            return self.clone();
+        }
+        if self.count == 0 {
+            return FeatureProfile::fit(&self.feature, values);
+        }
+        let (n_old, n_new) = (self.count as f64, sorted.len() as f64);
+        let total = n_old + n_new;
+        let batch_mean = sorted.iter().sum::<f64>() / n_new;
+        let delta = batch_mean - self.mean;
+        let m2 = self.std.powi(2) * (n_old - 1.0)
+            + sorted.iter().map(|v| (v - batch_mean).powi(2)).sum::<f64>()
+            + delta * delta * n_old * n_new / total;
+
+        let share = |value: f64| (n_old * self.cdf(value) + sorted.partition_point(|&v| v <= value) as f64) / total;
+        let mut points: Vec<f64> = self.percentiles.iter().chain(&sorted).copied().collect();
+        points.sort_by(f64::total_cmp);
+        points.dedup();
+        let shares: Vec<f64> = points.iter().map(|&v| share(v)).collect();
+        let percentiles: Vec<f64> = (0..=100)
+            .map(|p| {
+                let q = p as f64 / 100.0;
+                match shares.partition_point(|&s| s < q) {
+                    0 => points[0],
+                    i if i == points.len() => points[i - 1],
+                    i => points[i - 1] + (points[i] - points[i - 1]) * (q - shares[i - 1]) / (shares[i] - shares[i - 1]),
+                }
+            })
+            .collect();
+
+        let mut edges: Vec<f64> = (1..PSI_BINS).map(|i| percentiles[i * 100 / PSI_BINS]).collect();
+        edges.dedup();
+        let mut bounds = vec![0.0];
+        bounds.extend(edges.iter().map(|&edge| self.cdf(edge)));
+        bounds.push(1.0);
+        let mut frequencies: Vec<f64> = bounds.windows(2).map(|w| n_old * (w[1] - w[0]) / total).collect();
+        for &value in &sorted {
+            frequencies[bin(&edges, value)] += 1.0 / total;
+        }
+        FeatureProfile {
+            feature: self.feature.clone(),
+            count: self.count + sorted.len(),
+            mean: self.mean + delta * n_new / total,
+            std: (m2 / (total - 1.0)).sqrt(),
+            percentiles,
+            edges,
+            frequencies,
+        }
+    }
+
+    /// Share of training readings at or below `value`, interpolated between
+    /// percentiles.
+    pub fn cdf(&self, value: f64) -> f64 {
+        let p = &self.percentiles;
+        if p.is_empty() || value < p[0] {
+            return 0.0;
+        }
+        // Percentiles at or below the value; ties take the highest
+        let above = p.partition_point(|&q| q <= value);
+        if above == p.len() {
+            return 1.0;
+        }
+        let (lower, upper) = (p[above - 1], p[above]);
+        (above - 1) as f64 / 100.0 + (value - lower) / (upper - lower) / 100.0
+    }
+}
+
+/// Training distributions of every network input, stored with the model.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InputProfile {
+    pub features: Vec<FeatureProfile>,
+}
+
+impl InputProfile {
+    /// Profiles the columns of raw inputs, one per name.
+    pub fn fit(names: &[String], x: &Array2<f64>) -> Self {
+        InputProfile {
+            features: names.iter().zip(x.axis_iter(Axis(1))).map(|(name, column)| FeatureProfile::fit(name, column)).collect(),
+        }
+    }
+
+    /// The profile of the training readings together with the raw inputs
+    /// `x`, as `FeatureProfile::merge` describes.
+    pub fn merge(&self, x: &Array2<f64>) -> Self {
+        InputProfile {
+            features: self.features.iter().zip(x.axis_iter(Axis(1))).map(|(profile, column)| profile.merge(column)).collect(),
+        }
+    }
+
+    /// Compares a batch of raw inputs with the training distributions.
+    pub fn check(&self, x: &Array2<f64>, thresholds: &DriftThresholds) -> Result<DriftReport, String> {
+        if x.ncols() != self.features.len() {
+            return Err(format!("expected {} inputs per reading, got {}", self.features.len(), x.ncols()));
+        }
+        let features: Vec<FeatureDrift> = self.features.iter()
+            .zip(x.axis_iter(Axis(1)))
+            .map(|(profile, column)| feature_drift(profile, column, thresholds))
+            .collect();
+        let alerts = features.iter().flat_map(|f| f.alerts.iter().cloned()).collect();
+        Ok(DriftReport { samples: x.nrows(), features, alerts })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftSeverity {
+    None,
+    Warning,
+    Alert,
+}
+
+impl fmt::Display for DriftSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DriftSeverity::None => "none",
+            DriftSeverity::Warning => "warning",
+            DriftSeverity::Alert => "alert",
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftMetric {
+    /// Population stability index over the training decile bins.
+    Psi,
+    /// Kolmogorov-Smirnov statistic: the largest gap between the cumulative
+    /// distributions.
+    Ks,
+    /// Distance of the batch mean from the training mean, in training
+    /// standard deviations.
+    MeanShift,
+}
+
+impl fmt::Display for DriftMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DriftMetric::Psi => "psi",
+            DriftMetric::Ks => "ks",
+            DriftMetric::MeanShift => "mean_shift",
+        })
+    }
+}
+
+/// Warning and alert levels of each metric. The KS levels are raised for
+/// small batches to the critical values at 10% and 5% significance, so that
+/// sampling noise alone does not trigger them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DriftThresholds {
+    pub psi: (f64, f64),
+    pub ks: (f64, f64),
+    pub mean_shift: (f64, f64),
+    /// Batches with fewer readings are reported without alerts.
+    pub min_samples: usize,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> Self {
+        DriftThresholds { psi: (0.1, 0.25), ks: (0.1, 0.2), mean_shift: (0.5, 1.0), min_samples: 30 }
+    }
+}
+
+/// One metric of one feature past its warning or alert level.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DriftAlert {
+    pub feature: String,
+    pub metric: DriftMetric,
+    pub value: f64,
+    pub threshold: f64,
+    pub severity: DriftSeverity,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FeatureDrift {
+    pub feature: String,
+    pub psi: f64,
+    pub ks: f64,
+    /// Signed, in training standard deviations.
+    pub mean_shift: f64,
+    pub batch_mean: f64,
+    pub training_mean: f64,
+    pub severity: DriftSeverity,
+    pub alerts: Vec<DriftAlert>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DriftReport {
+    pub samples: usize,
+    pub features: Vec<FeatureDrift>,
+    pub alerts: Vec<DriftAlert>,
+}
+
+impl DriftReport {
+    /// The worst severity over all features.
+    pub fn severity(&self) -> DriftSeverity {
+        self.features.iter().map(|f| f.severity).max().unwrap_or(DriftSeverity::None)
+    }
+}
+
+fn feature_drift(profile: &FeatureProfile, column: ArrayView1<f64>, thresholds: &DriftThresholds) -> FeatureDrift {
+    let mut sorted: Vec<f64> = column.iter().copied().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(f64::total_cmp);
+    let n = sorted.len();
+    let batch_mean = if n > 0 { sorted.iter().sum::<f64>() / n as f64 } else { f64::NAN };
+    let std = if profile.std > 0.0 { profile.std } else { 1.0 };
+    let mean_shift = (batch_mean - profile.mean) / std;
+
+    let mut shares = vec![0.0; profile.frequencies.len()];
+    for &value in &sorted {
+        shares[bin(&profile.edges, value)] += 1.0 / n as f64;
+    }
+    let psi = shares.iter()
+        .zip(&profile.frequencies)
+        .map(|(&actual, &expected)| {
+            let (actual, expected) = (actual.max(MIN_SHARE), expected.max(MIN_SHARE));
+            (actual - expected) * (actual / expected).ln()
+        })
+        .sum();
+    let ks = sorted.iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let expected = profile.cdf(value);
+            ((i + 1) as f64 / n as f64 - expected).max(expected - i as f64 / n as f64)
+        })
+        .fold(0.0, f64::max);
+
+    let mut alerts = Vec::new();
+    if n >= thresholds.min_samples.max(1) {
+        let critical = |c: f64| c / (n as f64).sqrt();
+        let ks_levels = (thresholds.ks.0.max(critical(1.22)), thresholds.ks.1.max(critical(1.36)));
+        for (metric, value, (warning, alert)) in [
+            (DriftMetric::Psi, psi, thresholds.psi),
+            (DriftMetric::Ks, ks, ks_levels),
+            (DriftMetric::MeanShift, mean_shift.abs(), thresholds.mean_shift),
+        ] {
+            let (severity, threshold) = if value >= alert {
+                (DriftSeverity::Alert, alert)
+            } else if value >= warning {
+                (DriftSeverity::Warning, warning)
+            } else {
+                continue;
+            };
+            alerts.push(DriftAlert { feature: profile.feature.clone(), metric, value, threshold, severity });
+        }
+    }
+
+    FeatureDrift {
+        feature: profile.feature.clone(),
+        psi,
+        ks,
+        mean_shift,
+        batch_mean,
+        training_mean: profile.mean,
+        severity: alerts.iter().map(|a| a.severity).max().unwrap_or(DriftSeverity::None),
+        alerts,
+    }
+}
+
+impl<F: Float> TrainedModel<F> {
+    /// Compares a batch of raw network inputs with the training inputs.
+    pub fn check_drift(&self, x: &Array2<f64>, thresholds: &DriftThresholds) -> Result<DriftReport, Box<dyn Error>> {
+        let profile = self.profile.as_ref().ok_or("the model has no training input profile; retrain it to check for drift")?;
+        Ok(profile.check(x, thresholds)?)
+    }
+
+    /// Drift of the inputs of a CSV file, read like the training data.
+    pub fn check_drift_csv(&self, csv_path: &str, thresholds: &DriftThresholds) -> Result<DriftReport, Box<dyn Error>> {
+        let x = match &self.config.time_series {
+            Some(_) => self.config.load_windows(csv_path, false)?.x,
+            None => load_features(csv_path, self.features())?,
+        };
+        self.check_drift(&x, thresholds)
+    }
+}
+
+/// Watches a stream of raw inputs, comparing the latest `window` readings
+/// with the training distributions.
+pub struct DriftMonitor {
+    profile: InputProfile,
+    thresholds: DriftThresholds,
+    window: usize,
+    readings: VecDeque<Vec<f64>>,
+}
+
+impl DriftMonitor {
+    pub fn new(profile: InputProfile, thresholds: DriftThresholds, window: usize) -> Self {
+        DriftMonitor { profile, thresholds, window: window.max(1), readings: VecDeque::new() }
+    }
+
+    pub fn profile(&self) -> &InputProfile {
+        &self.profile
+    }
+
+    /// Readings currently in the window.
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    /// Adds readings to the window, dropping the oldest beyond its size.
+    /// Readings of the wrong width are ignored.
+    pub fn observe(&mut self, x: &Array2<f64>) {
+        if x.ncols() != self.profile.features.len() {
+            return;
+        }
+        for row in x.rows() {
+            if self.readings.len() == self.window {
+                self.readings.pop_front();
+            }
+            self.readings.push_back(row.to_vec());
+        }
+    }
+
+    /// Drift of the readings in the window.
+    pub fn report(&self) -> DriftReport {
+        let columns = self.profile.features.len();
+        let x = Array2::from_shape_vec((self.readings.len(), columns), self.readings.iter().flatten().copied().collect())
+            .expect("readings have one value per feature");
+        self.profile.check(&x, &self.thresholds).expect("readings have one value per feature")
+    }
+}
+
+/// Linearly interpolated quantile of sorted values, NaN if there are none.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let position = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+fn bin(edges: &[f64], value: f64) -> usize {
+    edges.partition_point(|&edge| edge <= value)
+}
@@ -0,0 +1,122 @@
+use crate::model::float::Float;
+use crate::training::trainer::TrainedModel;
+use ndarray::{Array2, ArrayView1, Axis};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub samples: usize,
+    pub accuracy: f64,
+    /// Mean cross-entropy per sample.
+    pub loss: f64,
+    /// Rows are true classes, columns are predicted classes.
+    pub confusion_matrix: Vec<Vec<usize>>,
+}
+
+/// Errors of a regressor on one target column, in the units of the target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionMetrics {
+    pub target: String,
+    pub rmse: f64,
+    pub mae: f64,
+    /// Coefficient of determination; 1 is a perfect fit and 0 is no better
+    /// than predicting the mean.
+    pub r2: f64,
+}
+
+impl RegressionMetrics {
+    pub fn of(target: &str, predicted: ArrayView1<f64>, actual: ArrayView1<f64>) -> Self {
+        let rows = actual.len().max(1) as f64;
+        let residuals = &predicted - &actual;
+        let squared = residuals.mapv(|r| r * r).sum();
+        RegressionMetrics {
+            target: target.to_string(),
+            rmse: (squared / rows).sqrt(),
+            mae: residuals.mapv(f64::abs).sum() / rows,
+            r2: r_squared(squared, actual),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionEvaluation {
+    pub samples: usize,
+    /// One entry per target, in the order of the network outputs.
+    pub targets: Vec<RegressionMetrics>,
+}
+
+/// R² of every column of `predicted` against `actual`, averaged over the
+/// columns. Standardizing both the same way leaves it unchanged.
+pub fn mean_r_squared(predicted: &Array2<f64>, actual: &Array2<f64>) -> f64 {
+    let scores = predicted.axis_iter(Axis(1)).zip(actual.axis_iter(Axis(1)))
+        .map(|(predicted, actual)| r_squared((&predicted - &actual).mapv(|r| r * r).sum(), actual));
+    scores.sum::<f64>() / actual.ncols().max(1) as f64
+}
+
+fn r_squared(residual_sum: f64, actual: ArrayView1<f64>) -> f64 {
+    let mean = actual.mean().unwrap_or(0.0);
+    let total = actual.mapv(|v| (v - mean) * (v - mean)).sum();
+    if total > 0.0 {
+        1.0 - residual_sum / total
+    } else if residual_sum > 0.0 {
+        0.0
+    } else {
+        1.0
+    }
+}
+
+/// Index of the largest value in a row of probabilities or one-hot targets.
+pub fn argmax(row: ArrayView1<f64>) -> usize {
+    row.iter()
+        .enumerate()
+        .fold((0, f64::NEG_INFINITY), |best, (i, &v)| if v > best.1 { (i, v) } else { best })
+        .0
+}
+
+/// Evaluates a model on raw (unnormalized) inputs and one-hot targets.
+pub fn evaluate<F: Float>(model: &TrainedModel<F>, x: &Array2<f64>, y: &Array2<f64>) -> Evaluation {
+    let output = model.predict_batch(x);
+    let classes = y.ncols();
+
+    let mut confusion_matrix = vec![vec![0; classes]; classes];
+    for (truth, predicted) in y.axis_iter(Axis(0)).zip(output.axis_iter(Axis(0))) {
+        confusion_matrix[argmax(truth)][argmax(predicted)] += 1;
+    }
+    let correct: usize = (0..classes).map(|class| confusion_matrix[class][class]).sum();
+
+    Evaluation {
+        samples: x.nrows(),
+        accuracy: correct as f64 / x.nrows() as f64,
+        loss: -(y * &output.mapv(f64::ln)).sum() / x.nrows().max(1) as f64,
+        confusion_matrix,
+    }
+}
+
+pub fn evaluate_csv<F: Float>(model: &TrainedModel<F>, csv_path: &str) -> Result<Evaluation, Box<dyn Error>> {
+    if model.is_regression() {
+        return Err("the model is a regressor; use evaluate_regression_csv".into());
+    }
+    let (x, y) = model.config.load_dataset(csv_path)?;
+    Ok(evaluate(model, &x, &y))
+}
+
+/// Evaluates a regressor on raw inputs and targets.
+pub fn evaluate_regression<F: Float>(model: &TrainedModel<F>, x: &Array2<f64>, y: &Array2<f64>) -> RegressionEvaluation {
+    let predicted = model.predict_values(x);
+    RegressionEvaluation {
+        samples: x.nrows(),
+        targets: model.config.data.targets.iter()
+            .zip(predicted.axis_iter(Axis(1)).zip(y.axis_iter(Axis(1))))
+            .map(|(target, (predicted, actual))| RegressionMetrics::of(target, predicted, actual))
+            .collect(),
+    }
+}
+
+pub fn evaluate_regression_csv<F: Float>(model: &TrainedModel<F>, csv_path: &str) -> Result<RegressionEvaluation, Box<dyn Error>> {
+    if !model.is_regression() {
+        return Err("the model is a classifier; use evaluate_csv".into());
+    }
+    let (x, y) = model.config.load_dataset(csv_path)?;
+    Ok(evaluate_regression(model, &x, &y))
+}
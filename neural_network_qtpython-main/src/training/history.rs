@@ -1,26 +1,104 @@
-use serde::{Serialize, Deserialize};
+use crate::training::trainer::EpochMetrics;
+use serde::{Serialize, Deserialize, Deserializer};
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
 
-#[derive(Serialize, Deserialize)]
+/// Metrics of every evaluation step of a training run, one entry per step in
+/// each list. Validation values are NaN without a validation split.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
 pub struct TrainingHistory {
     pub epochs: Vec<usize>,
+    /// Training accuracy, or mean R² for regressors.
+    #[serde(deserialize_with = "nullable")]
     pub accuracies: Vec<f64>,
+    #[serde(deserialize_with = "nullable")]
     pub losses: Vec<f64>,
+    #[serde(deserialize_with = "nullable")]
+    pub val_accuracies: Vec<f64>,
+    #[serde(deserialize_with = "nullable")]
+    pub val_losses: Vec<f64>,
+    #[serde(deserialize_with = "nullable")]
+    pub learning_rates: Vec<f64>,
+    #[serde(deserialize_with = "nullable")]
+    pub gradient_norms: Vec<f64>,
+    #[serde(deserialize_with = "nullable")]
+    pub elapsed_secs: Vec<f64>,
+    /// Whether the accuracies are R² values of a regressor.
+    pub regression: bool,
 }
 
-impl Default for TrainingHistory {
-    fn default() -> Self {
-        TrainingHistory {
-            epochs: Vec::new(),
-            accuracies: Vec::new(),
-            losses: Vec::new(),
+impl TrainingHistory {
+    pub fn record(&mut self, metrics: &EpochMetrics) {
+        self.epochs.push(metrics.epoch);
+        self.accuracies.push(metrics.train_accuracy);
+        self.losses.push(metrics.train_loss);
+        self.val_accuracies.push(metrics.val_accuracy);
+        self.val_losses.push(metrics.val_loss);
+        self.learning_rates.push(metrics.learning_rate);
+        self.gradient_norms.push(metrics.gradient_norm);
+        self.elapsed_secs.push(metrics.elapsed.as_secs_f64());
+    }
+
+    pub fn len(&self) -> usize {
+        self.epochs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.epochs.is_empty()
+    }
+
+    /// Writes one row per evaluation step. NaN values are left empty.
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), Box<dyn Error>> {
+        let mut writer = csv::Writer::from_writer(writer);
+        let score = if self.regression { ["train_r2", "val_r2"] } else { ["train_accuracy", "val_accuracy"] };
+        writer.write_record([
+            "epoch", "train_loss", score[0], "val_loss", score[1], "learning_rate", "gradient_norm", "elapsed_secs",
+        ])?;
+        let value = |values: &[f64], i: usize| match values.get(i) {
+            Some(v) if !v.is_nan() => v.to_string(),
+            _ => String::new(),
+        };
+        for (i, epoch) in self.epochs.iter().enumerate() {
+            writer.write_record([
+                epoch.to_string(),
+                value(&self.losses, i),
+                value(&self.accuracies, i),
+                value(&self.val_losses, i),
+                value(&self.val_accuracies, i),
+                value(&self.learning_rates, i),
+                value(&self.gradient_norms, i),
+                value(&self.elapsed_secs, i),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the history as JSON, with NaN values as `null`.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Writes `.json` paths as JSON and anything else as CSV.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let path = path.as_ref();
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            self.write_json(file)
+        } else {
+            self.write_csv(file)
         }
     }
 }
 
-impl TrainingHistory {
-    pub fn record(&mut self, epoch: usize, accuracy: f64, loss: f64) {
-        self.epochs.push(epoch);
-        self.accuracies.push(accuracy);
-        self.losses.push(loss);
+/// JSON has no NaN, so it is written as `null` and read back as NaN.
+fn nullable<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+    if !deserializer.is_human_readable() {
+        return Vec::deserialize(deserializer);
     }
-}
\ No newline at end of file
+    let values: Vec<Option<f64>> = Deserialize::deserialize(deserializer)?;
+    Ok(values.into_iter().map(|v| v.unwrap_or(f64::NAN)).collect())
+}
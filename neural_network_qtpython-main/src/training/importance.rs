@@ -0,0 +1,63 @@
+use crate::model::float::Float;
+use crate::training::evaluation::{argmax, mean_r_squared};
+use crate::training::trainer::TrainedModel;
+use ndarray::{Array2, ArrayView1, Axis};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How much the score of a model drops when one input column is shuffled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureImportance {
+    pub feature: String,
+    /// Mean drop in accuracy, or in mean R² for regressors.
+    pub importance: f64,
+    /// Standard deviation of the drop over the repeats.
+    pub std: f64,
+}
+
+/// Accuracy of a classifier or mean R² of a regressor on raw inputs.
+pub fn score<F: Float>(model: &TrainedModel<F>, x: &Array2<f64>, y: &Array2<f64>) -> f64 {
+    if model.is_regression() {
+        return mean_r_squared(&model.predict_values(x), y);
+    }
+    let output = model.predict_batch(x);
+    let correct = output.axis_iter(Axis(0))
+        .zip(y.axis_iter(Axis(0)))
+        .filter(|(predicted, truth)| argmax(predicted.view()) == argmax(truth.view()))
+        .count();
+    correct as f64 / x.nrows().max(1) as f64
+}
+
+/// Permutation importance of every network input, in input order. Each
+/// column is shuffled `repeats` times with the others left intact, which
+/// breaks its link to the labels without changing its distribution.
+pub fn permutation_importance<F: Float, R: Rng>(
+    model: &TrainedModel<F>,
+    x: &Array2<f64>,
+    y: &Array2<f64>,
+    repeats: usize,
+    rng: &mut R,
+) -> Vec<FeatureImportance> {
+    let baseline = score(model, x, y);
+    let repeats = repeats.max(1);
+    let mut shuffled = x.clone();
+    model.config.input_names()
+        .into_iter()
+        .enumerate()
+        .map(|(column, feature)| {
+            let mut values = x.column(column).to_vec();
+            let drops: Vec<f64> = (0..repeats)
+                .map(|_| {
+                    values.shuffle(rng);
+                    shuffled.column_mut(column).assign(&ArrayView1::from(&values));
+                    baseline - score(model, &shuffled, y)
+                })
+                .collect();
+            shuffled.column_mut(column).assign(&x.column(column));
+            let importance = drops.iter().sum::<f64>() / repeats as f64;
+            let variance = drops.iter().map(|d| (d - importance).powi(2)).sum::<f64>() / repeats as f64;
+            FeatureImportance { feature, importance, std: variance.sqrt() }
+        })
+        .collect()
+}
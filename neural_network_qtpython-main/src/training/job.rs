@@ -0,0 +1,161 @@
+use crate::training::trainer::{
+    train_any_with_progress, AnyModel, EpochMetrics, TrainingCancelled, TrainingControl,
+};
+use crate::training::config::TrainingConfig;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Running = 0,
+    Paused = 1,
+    Completed = 2,
+    Cancelled = 3,
+    Failed = 4,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Cancelled | JobStatus::Failed)
+    }
+}
+
+struct JobState {
+    status: JobStatus,
+    pause_requested: bool,
+    cancel_requested: bool,
+    latest: Option<EpochMetrics>,
+    model: Option<AnyModel>,
+    error: Option<String>,
+}
+
+struct Shared {
+    state: Mutex<JobState>,
+    wakeup: Condvar,
+}
+
+/// Handle to a training run executing on a background thread.
+///
+/// Pause and cancel requests are honoured at the next evaluation step of the
+/// training loop. Dropping the handle cancels the run and waits for the thread.
+pub struct TrainingJob {
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TrainingJob {
+    pub fn start(config: TrainingConfig) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(JobState {
+                status: JobStatus::Running,
+                pause_requested: false,
+                cancel_requested: false,
+                latest: None,
+                model: None,
+                error: None,
+            }),
+            wakeup: Condvar::new(),
+        });
+
+        let worker = Arc::clone(&shared);
+        let handle = thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                train_any_with_progress(&config, |metrics| {
+                    let mut state = worker.state.lock().unwrap();
+                    state.latest = Some(*metrics);
+                    while state.pause_requested && !state.cancel_requested {
+                        state = worker.wakeup.wait(state).unwrap();
+                    }
+                    if state.cancel_requested {
+                        TrainingControl::Stop
+                    } else {
+                        TrainingControl::Continue
+                    }
+                })
+            }));
+
+            let mut state = worker.state.lock().unwrap();
+            match result {
+                Ok(Ok(model)) => {
+                    state.status = JobStatus::Completed;
+                    state.model = Some(model);
+                }
+                Ok(Err(e)) if e.is::<TrainingCancelled>() => state.status = JobStatus::Cancelled,
+                Ok(Err(e)) => {
+                    state.status = JobStatus::Failed;
+                    state.error = Some(e.to_string());
+                }
+                Err(_) => {
+                    state.status = JobStatus::Failed;
+                    state.error = Some("training thread panicked".to_string());
+                }
+            }
+        });
+
+        TrainingJob {
+            shared,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.shared.state.lock().unwrap().status
+    }
+
+    /// Metrics from the most recent evaluation step, if one has run yet.
+    pub fn latest_metrics(&self) -> Option<EpochMetrics> {
+        self.shared.state.lock().unwrap().latest
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.shared.state.lock().unwrap().error.clone()
+    }
+
+    pub fn pause(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.status == JobStatus::Running {
+            state.pause_requested = true;
+            state.status = JobStatus::Paused;
+        }
+    }
+
+    pub fn resume(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.status == JobStatus::Paused {
+            state.pause_requested = false;
+            state.status = JobStatus::Running;
+            self.shared.wakeup.notify_all();
+        }
+    }
+
+    pub fn cancel(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.status.is_finished() {
+            state.cancel_requested = true;
+            self.shared.wakeup.notify_all();
+        }
+    }
+
+    /// Blocks until the background thread has finished and returns the final status.
+    pub fn wait(&mut self) -> JobStatus {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        self.status()
+    }
+
+    /// Takes the trained model out of a completed job. Returns `None` while the
+    /// job is still running, if it did not complete, or if the model was already taken.
+    pub fn take_model(&self) -> Option<AnyModel> {
+        self.shared.state.lock().unwrap().model.take()
+    }
+}
+
+impl Drop for TrainingJob {
+    fn drop(&mut self) {
+        self.cancel();
+        self.wait();
+    }
+}
@@ -1,2 +1,14 @@
+pub mod config;
 pub mod trainer;
-pub mod history;
\ No newline at end of file
+pub mod history;
+pub mod job;
+pub mod search;
+pub mod evaluation;
+pub mod diagnostics;
+pub mod importance;
+pub mod runs;
+pub mod registry;
+pub mod online;
+pub mod drift;
+pub mod validation;
+pub mod backtest;
\ No newline at end of file
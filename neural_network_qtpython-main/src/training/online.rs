@@ -0,0 +1,381 @@
+use crate::data::preprocessing::{DataStats, Normalization};
+use crate::model::float::Float;
+use crate::model::layers::Layer;
+use crate::training::evaluation::mean_r_squared;
+use crate::training::trainer::{
+    resolve_seed, training_rng, AnyModel, EpochMetrics, TrainedModel, TrainingDiverged, TrainingRng,
+};
+use crate::utils::io::{load_any_model, save_any_model};
+use ndarray::{Array1, Array2, Axis};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// Old readings kept by a replay buffer unless asked otherwise.
+pub const DEFAULT_REPLAY_CAPACITY: usize = 1000;
+
+/// A uniform sample of every labelled reading a model has learnt from,
+/// kept with reservoir sampling. Inputs are raw network inputs and outputs
+/// are one-hot labels or raw targets, as `TrainingConfig::load_dataset`
+/// returns them.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayBuffer {
+    pub capacity: usize,
+    /// Readings offered to the buffer so far, kept or not.
+    pub seen: u64,
+    pub x: Array2<f64>,
+    pub y: Array2<f64>,
+}
+
+impl ReplayBuffer {
+    pub fn new(capacity: usize, inputs: usize, outputs: usize) -> Self {
+        ReplayBuffer { capacity, seen: 0, x: Array2::zeros((0, inputs)), y: Array2::zeros((0, outputs)) }
+    }
+
+    /// A buffer holding a sample of the labelled CSV file a model was
+    /// trained on, normally `model.config.data.path`.
+    pub fn from_csv<F: Float, R: Rng>(model: &TrainedModel<F>, csv_path: &str, capacity: usize, rng: &mut R) -> Result<Self, Box<dyn Error>> {
+        let (x, y) = model.config.load_dataset(csv_path)?;
+        let mut buffer = ReplayBuffer::new(capacity, x.ncols(), y.ncols());
+        buffer.add(&x, &y, rng)?;
+        Ok(buffer)
+    }
+
+    /// Loads the buffer saved at `path`. Without one, starts a buffer from
+    /// the model's original training data, or an empty one if that file is
+    /// gone.
+    pub fn open<F: Float>(path: &str, model: &TrainedModel<F>, seed: Option<u64>) -> Result<Self, Box<dyn Error>> {
+        if std::path::Path::new(path).exists() {
+            return ReplayBuffer::load(path).map_err(|e| format!("failed to load replay buffer {}: {}", path, e).into());
+        }
+        let mut rng = training_rng(resolve_seed(seed));
+        Ok(ReplayBuffer::from_csv(model, &model.config.data.path, DEFAULT_REPLAY_CAPACITY, &mut rng)
+            .unwrap_or_else(|_| ReplayBuffer::new(DEFAULT_REPLAY_CAPACITY, model.stats.mean.len(), model.outputs().len())))
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.nrows()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Offers every row to the buffer. Once it is full, the `n`th reading
+    /// seen replaces a random kept one with probability `capacity / n`.
+    pub fn add<R: Rng>(&mut self, x: &Array2<f64>, y: &Array2<f64>, rng: &mut R) -> Result<(), Box<dyn Error>> {
+        if x.ncols() != self.x.ncols() || y.ncols() != self.y.ncols() || x.nrows() != y.nrows() {
+            return Err(format!("replay buffer holds {} inputs and {} outputs per reading, got {} and {}",
+                self.x.ncols(), self.y.ncols(), x.ncols(), y.ncols()).into());
+        }
+        for (row_x, row_y) in x.rows().into_iter().zip(y.rows()) {
+            self.seen += 1;
+            if self.len() < self.capacity {
+                self.x.push_row(row_x)?;
+                self.y.push_row(row_y)?;
+            } else {
+                let slot = rng.gen_range(0..self.seen);
+                if slot < self.capacity as u64 {
+                    self.x.row_mut(slot as usize).assign(&row_x);
+                    self.y.row_mut(slot as usize).assign(&row_y);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Up to `count` kept readings, drawn without replacement.
+    fn sample<R: Rng>(&self, count: usize, rng: &mut R) -> (Array2<f64>, Array2<f64>) {
+        let mut rows: Vec<usize> = (0..self.len()).collect();
+        rows.shuffle(rng);
+        rows.truncate(count);
+        (self.x.select(Axis(0), &rows), self.y.select(Axis(0), &rows))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, bincode::serialize(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(bincode::deserialize(&std::fs::read(path)?)?)
+    }
+}
+
+/// What `partial_fit` does with the input normalization statistics.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsUpdate {
+    /// Keep the statistics the model was trained with, so new readings are
+    /// scaled exactly like the original training data.
+    #[default]
+    Keep,
+    /// Move the mean and standard deviation to those of every reading seen:
+    /// the current statistics count for the replay buffer's `seen` readings
+    /// and are merged with the new batch. The first layer is rescaled so
+    /// that the model predicts the same as before until it trains on the
+    /// batch. Needs a replay buffer; regression target statistics are kept.
+    Update,
+}
+
+#[derive(Debug, Clone)]
+pub struct PartialFitOptions {
+    /// Passes over the new batch and its replayed readings.
+    pub epochs: usize,
+    /// Defaults to the learning rate the model was trained with.
+    pub learning_rate: Option<f64>,
+    /// Replayed old readings per new reading in each epoch. Replaying keeps
+    /// a small batch from overwriting what the model learnt before.
+    pub replay_ratio: f64,
+    pub stats: StatsUpdate,
+    /// Seed of the replay sampling, batch order and dropout masks.
+    pub seed: Option<u64>,
+}
+
+impl Default for PartialFitOptions {
+    fn default() -> Self {
+        PartialFitOptions { epochs: 10, learning_rate: None, replay_ratio: 1.0, stats: StatsUpdate::Keep, seed: None }
+    }
+}
+
+/// Losses before and after a `partial_fit`. Replay values are NaN without
+/// replayed readings.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct PartialFitReport {
+    pub samples: usize,
+    /// Old readings replayed in each epoch.
+    pub replayed: usize,
+    pub loss_before: f64,
+    pub loss_after: f64,
+    /// Accuracy, or the mean R² for regressors, on the new batch.
+    pub score_before: f64,
+    pub score_after: f64,
+    pub replay_loss_before: f64,
+    pub replay_loss_after: f64,
+}
+
+impl AnyModel {
+    /// `TrainedModel::partial_fit` in the model's own precision.
+    pub fn partial_fit(
+        &mut self,
+        x: &Array2<f64>,
+        y: &Array2<f64>,
+        replay: Option<&mut ReplayBuffer>,
+        options: &PartialFitOptions,
+    ) -> Result<PartialFitReport, Box<dyn Error>> {
+        match self {
+            AnyModel::F32(model) => model.partial_fit(x, y, replay, options),
+            AnyModel::F64(model) => model.partial_fit(x, y, replay, options),
+        }
+    }
+}
+
+/// Updates the model file at `model_path` with the labelled CSV file at
+/// `csv_path` and saves it to `output_path`. With `replay_path`, the replay
+/// buffer stored there is used and saved back, as `ReplayBuffer::open`
+/// describes.
+pub fn partial_fit_files(
+    model_path: &str,
+    csv_path: &str,
+    replay_path: Option<&str>,
+    output_path: &str,
+    options: &PartialFitOptions,
+) -> Result<PartialFitReport, Box<dyn Error>> {
+    let mut model = load_any_model(model_path).map_err(|e| format!("failed to load model {}: {}", model_path, e))?;
+    let (x, y) = model.config().load_dataset(csv_path).map_err(|e| format!("failed to read {}: {}", csv_path, e))?;
+    let mut replay = match replay_path {
+        Some(path) => Some(ReplayBuffer::open(path, &model.cast::<f64>(), options.seed)?),
+        None => None,
+    };
+
+    let report = model.partial_fit(&x, &y, replay.as_mut(), options)?;
+    save_any_model(output_path, &model).map_err(|e| format!("failed to save model {}: {}", output_path, e))?;
+    if let (Some(path), Some(replay)) = (replay_path, &replay) {
+        replay.save(path).map_err(|e| format!("failed to save replay buffer {}: {}", path, e))?;
+    }
+    Ok(report)
+}
+
+impl<F: Float> TrainedModel<F> {
+    /// Continues training on a batch of labelled readings: raw inputs and
+    /// one-hot labels, or raw targets for regressors. Each epoch mixes in
+    /// readings replayed from `replay`, which then takes in the new batch.
+    /// The input profile takes in the batch too, the history gains one
+    /// entry with the loss and score on the batch, and `final_accuracy`
+    /// becomes that score. The embedded config still describes the
+    /// original training. The model is left unchanged if training diverges.
+    pub fn partial_fit(
+        &mut self,
+        x: &Array2<f64>,
+        y: &Array2<f64>,
+        replay: Option<&mut ReplayBuffer>,
+        options: &PartialFitOptions,
+    ) -> Result<PartialFitReport, Box<dyn Error>> {
+        let (inputs, outputs) = (self.stats.mean.len(), self.outputs().len());
+        if x.nrows() == 0 || x.nrows() != y.nrows() || x.ncols() != inputs || y.ncols() != outputs {
+            return Err(format!("partial_fit needs rows of {} inputs and {} outputs, got {} rows of {} and {} rows of {}",
+                inputs, outputs, x.nrows(), x.ncols(), y.nrows(), y.ncols()).into());
+        }
+        if !x.iter().chain(y).all(|v| v.is_finite()) {
+            return Err("partial_fit needs finite inputs and outputs".into());
+        }
+        if replay.as_ref().is_some_and(|r| r.x.ncols() != inputs || r.y.ncols() != outputs) {
+            return Err("the replay buffer was built for another model".into());
+        }
+        if options.replay_ratio < 0.0 || !options.replay_ratio.is_finite() {
+            return Err("replay_ratio must be a non-negative number".into());
+        }
+
+        let started = Instant::now();
+        let mut rng = training_rng(resolve_seed(options.seed));
+        let mut updated = self.clone();
+        if options.stats == StatsUpdate::Update && self.config.preprocessing.normalization != Normalization::None {
+            let seen = replay.as_ref().ok_or("updating the normalization statistics needs a replay buffer")?.seen;
+            updated.merge_stats(x, seen)?;
+        }
+
+        let replayed = replay.as_ref()
+            .map_or(0, |r| ((x.nrows() as f64 * options.replay_ratio).round() as usize).min(r.len()));
+        // Losses on old readings show how much the model forgot
+        let held = replay.as_ref().filter(|_| replayed > 0).map(|r| r.sample(replayed, &mut rng));
+        let before = updated.losses(x, y);
+        let replay_before = held.as_ref().map_or((f64::NAN, f64::NAN), |(x, y)| updated.losses(x, y));
+
+        let learning_rate = options.learning_rate.unwrap_or(self.config.optimizer.learning_rate);
+        for epoch in 0..options.epochs {
+            let (mut batch_x, mut batch_y) = (x.clone(), y.clone());
+            if let Some(replay) = replay.as_ref().filter(|_| replayed > 0) {
+                let (old_x, old_y) = replay.sample(replayed, &mut rng);
+                batch_x.append(Axis(0), old_x.view())?;
+                batch_y.append(Axis(0), old_y.view())?;
+            }
+            if !updated.step(&batch_x, &batch_y, learning_rate, &mut rng) {
+                return Err(Box::new(TrainingDiverged { epoch, reason: "gradients" }));
+            }
+        }
+        if !updated.is_finite() {
+            return Err(Box::new(TrainingDiverged { epoch: options.epochs.saturating_sub(1), reason: "weights" }));
+        }
+
+        let after = updated.losses(x, y);
+        let replay_after = held.as_ref().map_or((f64::NAN, f64::NAN), |(x, y)| updated.losses(x, y));
+        updated.profile = updated.profile.as_ref().map(|profile| profile.merge(x));
+        updated.final_accuracy = after.1;
+        if options.epochs > 0 {
+            let history = &updated.history;
+            let first = history.epochs.last().map_or(0, |&epoch| epoch + 1);
+            let elapsed = history.elapsed_secs.last().map_or(Duration::ZERO, |&secs| Duration::from_secs_f64(secs));
+            updated.history.record(&EpochMetrics {
+                epoch: first + options.epochs - 1,
+                total_epochs: first + options.epochs,
+                train_loss: after.0,
+                train_accuracy: after.1,
+                val_loss: f64::NAN,
+                val_accuracy: f64::NAN,
+                learning_rate,
+                elapsed: elapsed + started.elapsed(),
+                remaining: Duration::ZERO,
+                gradient_norm: f64::NAN,
+            });
+        }
+        *self = updated;
+        if let Some(replay) = replay {
+            replay.add(x, y, &mut rng)?;
+        }
+
+        Ok(PartialFitReport {
+            samples: x.nrows(),
+            replayed,
+            loss_before: before.0,
+            loss_after: after.0,
+            score_before: before.1,
+            score_after: after.1,
+            replay_loss_before: replay_before.0,
+            replay_loss_after: replay_after.0,
+        })
+    }
+
+    /// Network inputs and training targets of raw readings.
+    fn scaled(&self, x: &Array2<f64>, y: &Array2<f64>) -> (Array2<F>, Array2<F>) {
+        let y = y.mapv(F::cast);
+        let y = match &self.target_stats {
+            Some(stats) => stats.normalize(&y),
+            None => y,
+        };
+        (self.stats.normalize(&x.mapv(F::cast)), y)
+    }
+
+    /// Loss and score on raw readings.
+    fn losses(&self, x: &Array2<f64>, y: &Array2<f64>) -> (f64, f64) {
+        let (x, y) = self.scaled(x, y);
+        let output = self.network.forward_logits(&x);
+        let score = match self.target_stats {
+            Some(_) => mean_r_squared(&output.mapv(F::widen), &y.mapv(F::widen)),
+            None => self.network.accuracy(&y, &output),
+        };
+        (self.config.loss.value(&output, &y), score)
+    }
+
+    /// One pass over raw readings in mini-batches of the configured size.
+    /// Returns false if the gradients stopped being finite.
+    fn step(&mut self, x: &Array2<f64>, y: &Array2<f64>, learning_rate: f64, rng: &mut TrainingRng) -> bool {
+        let (x, y) = self.scaled(x, y);
+        let optimizer = &self.config.optimizer;
+        let batch_size = match optimizer.batch_size {
+            0 => x.nrows(),
+            size => size.min(x.nrows()),
+        };
+        let mut order: Vec<usize> = (0..x.nrows()).collect();
+        order.shuffle(rng);
+        for batch in order.chunks(batch_size) {
+            let (x_batch, y_batch) = (x.select(Axis(0), batch), y.select(Axis(0), batch));
+            let (learning_rate, l2) = self.config.mean_loss_step(learning_rate, batch.len());
+            let mut gradients = self.network.gradients(&x_batch, &y_batch, &self.config.loss, rng)
+                .with_l2(&self.network, l2);
+            if !gradients.is_finite() {
+                return false;
+            }
+            if optimizer.clip_value > 0.0 {
+                gradients.clip_value(optimizer.clip_value);
+            }
+            if optimizer.clip_norm > 0.0 {
+                gradients.clip_norm(optimizer.clip_norm);
+            }
+            self.network.descend(gradients, learning_rate);
+        }
+        true
+    }
+
+    /// Merges the input statistics, taken to describe `seen` readings, with
+    /// those of `x` and rescales the first dense layer to match. Standard
+    /// deviations are sample ones, like those of `DataStats::fit`.
+    fn merge_stats(&mut self, x: &Array2<f64>, seen: u64) -> Result<(), Box<dyn Error>> {
+        let old = self.stats.cast::<f64>();
+        let (n_old, n_new) = (seen as f64, x.nrows() as f64);
+        let total = n_old + n_new;
+        let batch_mean = x.mean_axis(Axis(0)).unwrap();
+        let batch_var = x.var_axis(Axis(0), 0.0);
+        let delta = &batch_mean - &old.mean;
+        let mean = &old.mean + &(&delta * (n_new / total));
+        let m2 = old.std.mapv(|s| s * s) * (n_old - 1.0).max(0.0) + batch_var * n_new
+            + delta.mapv(|d| d * d) * (n_old * n_new / total);
+        let std: Array1<f64> = (m2 / (total - 1.0).max(1.0)).mapv(|v| if v > 0.0 && v.is_finite() { v.sqrt() } else { 1.0 });
+        let merged = DataStats { mean, std };
+
+        // x_old = (x - m) / s = x_new * s' / s + (m' - m) / s, so the first
+        // layer sees the same values through rescaled weights
+        let Some(Layer::Dense(first)) = self.network.layers.first_mut() else {
+            return Err("updating the normalization statistics needs a dense first layer".into());
+        };
+        let scale = &merged.std / &old.std;
+        let shift = (&merged.mean - &old.mean) / &old.std;
+        let weights = first.weights.mapv(F::widen);
+        let bias = first.bias.mapv(F::widen) + shift.insert_axis(Axis(0)).dot(&weights);
+        first.weights = (&weights * &scale.insert_axis(Axis(1))).mapv(F::cast);
+        first.bias = bias.mapv(F::cast);
+        self.stats = merged.cast();
+        Ok(())
+    }
+}
@@ -0,0 +1,400 @@
+use crate::data::time_series::format_timestamp;
+use crate::training::runs::{model_metrics, DatasetFingerprint, RunStatus, RunStore, MODEL_FILE};
+use crate::training::trainer::TrainedModel;
+use crate::utils::io::{load_any_model, load_model};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_REGISTRY_DIR: &str = "registry";
+pub const DEFAULT_MODEL_NAME: &str = "airquality";
+
+/// File listing every version of a registered model, inside its directory.
+pub const INDEX_FILE: &str = "index.json";
+
+/// A semantic version, written `major.minor.patch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const FIRST: Version = Version { major: 1, minor: 0, patch: 0 };
+
+    pub fn bump(self, bump: Bump) -> Version {
+        let Version { major, minor, patch } = self;
+        match bump {
+            Bump::Major => Version { major: major + 1, minor: 0, patch: 0 },
+            Bump::Minor => Version { major, minor: minor + 1, patch: 0 },
+            Bump::Patch => Version { major, minor, patch: patch + 1 },
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().trim_start_matches('v').split('.').collect();
+        let part = |i: usize| parts.get(i).and_then(|p| p.parse::<u32>().ok());
+        match (parts.len(), part(0), part(1), part(2)) {
+            (3, Some(major), Some(minor), Some(patch)) => Ok(Version { major, minor, patch }),
+            _ => Err(format!("invalid version '{}', expected MAJOR.MINOR.PATCH", s)),
+        }
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Which part of the version a new registration increments.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Staging,
+    Production,
+    Archived,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Staging => "staging",
+            Stage::Production => "production",
+            Stage::Archived => "archived",
+        })
+    }
+}
+
+impl FromStr for Stage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::json!(s)).map_err(|_| format!("unknown stage '{}'", s))
+    }
+}
+
+/// Where a registered model came from.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Lineage {
+    /// Model file the version was copied from.
+    pub source: String,
+    /// Tracked run that produced the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<DatasetFingerprint>,
+    pub seed: Option<u64>,
+    /// Latest version registered before this one.
+    pub parent: Option<Version>,
+}
+
+/// One registered version of a model.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModelVersion {
+    pub version: Version,
+    pub stage: Stage,
+    /// Registration time, `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub registered: String,
+    /// When the stage last changed.
+    pub stage_changed: String,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub regression: bool,
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    pub lineage: Lineage,
+}
+
+/// Everything the registry records about a model, stored as `index.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ModelIndex {
+    pub versions: Vec<ModelVersion>,
+    /// Versions in the order they were put in production; the last one is
+    /// in production, and rollback returns to the one before it.
+    #[serde(default)]
+    pub production_history: Vec<Version>,
+}
+
+impl ModelIndex {
+    pub fn get(&self, version: Version) -> Option<&ModelVersion> {
+        self.versions.iter().find(|v| v.version == version)
+    }
+
+    pub fn latest(&self) -> Option<&ModelVersion> {
+        self.versions.iter().max_by_key(|v| v.version)
+    }
+
+    pub fn production(&self) -> Option<&ModelVersion> {
+        self.versions.iter().find(|v| v.stage == Stage::Production)
+    }
+}
+
+/// How to register a model. Without an explicit version the latest one is
+/// bumped: by `bump` if given, otherwise by a major step when the inputs or
+/// outputs changed and a minor step when they did not.
+#[derive(Debug, Clone, Default)]
+pub struct RegisterOptions {
+    pub version: Option<Version>,
+    pub bump: Option<Bump>,
+    /// Put the new version straight into production instead of staging.
+    pub production: bool,
+    pub run_id: Option<String>,
+    pub dataset: Option<DatasetFingerprint>,
+}
+
+/// A directory of named models, each holding its versions as
+/// `<name>/<version>/model.bin` next to a `<name>/index.json`.
+pub struct ModelRegistry {
+    root: PathBuf,
+}
+
+impl ModelRegistry {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        ModelRegistry { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Where version `version` of `name` is stored. `name` is not checked
+    /// here; every function that reads or writes the registry checks it.
+    pub fn model_path(&self, name: &str, version: Version) -> PathBuf {
+        self.root.join(name).join(version.to_string()).join(MODEL_FILE)
+    }
+
+    /// Names of the registered models, sorted.
+    pub fn names(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if entry.path().join(INDEX_FILE).is_file() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn index(&self, name: &str) -> Result<ModelIndex, Box<dyn Error>> {
+        check_name(name)?;
+        let path = self.root.join(name).join(INDEX_FILE);
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("model {} not found in {}: {}", name, self.root.display(), e))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn get(&self, name: &str, version: Version) -> Result<ModelVersion, Box<dyn Error>> {
+        self.index(name)?
+            .get(version)
+            .cloned()
+            .ok_or_else(|| format!("model {} has no version {}", name, version).into())
+    }
+
+    /// Copies the model file at `path` into the registry as a new version.
+    pub fn register(&self, name: &str, path: &str, options: RegisterOptions) -> Result<ModelVersion, Box<dyn Error>> {
+        check_name(name)?;
+        let model = load_any_model(path).map_err(|e| format!("failed to load model {}: {}", path, e))?;
+        let mut index = if self.root.join(name).join(INDEX_FILE).exists() { self.index(name)? } else { ModelIndex::default() };
+
+        let config = model.config();
+        let inputs = config.data.features.clone();
+        let outputs = config.data.outputs().to_vec();
+        let parent = index.latest().map(|v| v.version);
+        let version = match (options.version, index.latest()) {
+            (Some(version), _) => version,
+            (None, None) => Version::FIRST,
+            (None, Some(latest)) => {
+                let changed = latest.inputs != inputs || latest.outputs != outputs;
+                let bump = options.bump.unwrap_or(if changed { Bump::Major } else { Bump::Minor });
+                latest.version.bump(bump)
+            }
+        };
+        if index.get(version).is_some() {
+            return Err(format!("model {} already has version {}", name, version).into());
+        }
+
+        let destination = self.model_path(name, version);
+        fs::create_dir_all(destination.parent().unwrap())?;
+        fs::copy(path, &destination)
+            .map_err(|e| format!("failed to copy {} to {}: {}", path, destination.display(), e))?;
+
+        let now = format_timestamp(now());
+        index.versions.push(ModelVersion {
+            version,
+            stage: Stage::Staging,
+            registered: now.clone(),
+            stage_changed: now,
+            inputs,
+            outputs,
+            regression: model.is_regression(),
+            metrics: model_metrics(&model),
+            lineage: Lineage {
+                source: path.to_string(),
+                run_id: options.run_id,
+                dataset: options.dataset,
+                seed: config.seed,
+                parent,
+            },
+        });
+        if options.production {
+            promote(&mut index, version);
+        }
+        self.save(name, &index)?;
+        Ok(index.get(version).unwrap().clone())
+    }
+
+    /// Registers the model of a completed tracked run, with the run's
+    /// metrics and dataset fingerprint.
+    pub fn register_run(&self, name: &str, runs: &RunStore, id: &str, options: RegisterOptions) -> Result<ModelVersion, Box<dyn Error>> {
+        let record = runs.get(id)?;
+        if record.status != RunStatus::Completed {
+            return Err(format!("run {} did not complete", id).into());
+        }
+        let path = runs.dir(id).join(MODEL_FILE);
+        let options = RegisterOptions { run_id: Some(record.id), dataset: Some(record.dataset), ..options };
+        let mut registered = self.register(name, &path.to_string_lossy(), options)?;
+
+        // The run also knows how long training took
+        let mut index = self.index(name)?;
+        let entry = index.versions.iter_mut().find(|v| v.version == registered.version).unwrap();
+        entry.metrics.extend(record.metrics);
+        registered = entry.clone();
+        self.save(name, &index)?;
+        Ok(registered)
+    }
+
+    /// Moves a version to `stage`. Putting a version in production archives
+    /// the one it replaces; taking the production version out of production
+    /// leaves the model without one.
+    pub fn set_stage(&self, name: &str, version: Version, stage: Stage) -> Result<ModelVersion, Box<dyn Error>> {
+        let mut index = self.index(name)?;
+        if index.get(version).is_none() {
+            return Err(format!("model {} has no version {}", name, version).into());
+        }
+        if stage == Stage::Production {
+            promote(&mut index, version);
+        } else {
+            set(&mut index, version, stage);
+        }
+        self.save(name, &index)?;
+        Ok(index.get(version).unwrap().clone())
+    }
+
+    /// Archives the production version and puts the version that was in
+    /// production before it back.
+    pub fn rollback(&self, name: &str) -> Result<ModelVersion, Box<dyn Error>> {
+        let mut index = self.index(name)?;
+        let current = index.production().map(|v| v.version)
+            .ok_or_else(|| format!("model {} has no production version", name))?;
+        // Versions archived by hand since they were in production still count
+        let previous = index.production_history.iter().rev()
+            .copied()
+            .find(|&v| v != current && index.get(v).is_some())
+            .ok_or_else(|| format!("model {} has no earlier production version to roll back to", name))?;
+
+        set(&mut index, current, Stage::Archived);
+        set(&mut index, previous, Stage::Production);
+        while index.production_history.last().is_some_and(|&v| v != previous) {
+            index.production_history.pop();
+        }
+        self.save(name, &index)?;
+        Ok(index.get(previous).unwrap().clone())
+    }
+
+    pub fn production(&self, name: &str) -> Result<ModelVersion, Box<dyn Error>> {
+        self.index(name)?
+            .production()
+            .cloned()
+            .ok_or_else(|| format!("model {} has no production version", name).into())
+    }
+
+    pub fn production_model_path(&self, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        Ok(self.model_path(name, self.production(name)?.version))
+    }
+
+    /// Writes the index through a temporary file so that readers such as a
+    /// serving process never see it half written.
+    fn save(&self, name: &str, index: &ModelIndex) -> Result<(), Box<dyn Error>> {
+        check_name(name)?;
+        let dir = self.root.join(name);
+        fs::create_dir_all(&dir)?;
+        let temp = dir.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&temp, serde_json::to_string_pretty(index)?)?;
+        fs::rename(temp, dir.join(INDEX_FILE))?;
+        Ok(())
+    }
+}
+
+/// Loads the production version of model `name` from the registry at `root`.
+pub fn load_production_model(root: impl AsRef<Path>, name: &str) -> Result<TrainedModel, Box<dyn Error>> {
+    let path = ModelRegistry::new(root.as_ref()).production_model_path(name)?;
+    load_model(&path.to_string_lossy())
+}
+
+fn check_name(name: &str) -> Result<(), Box<dyn Error>> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) || name.starts_with('.') {
+        return Err(format!("invalid model name '{}': use letters, digits, '-', '_' and '.'", name).into());
+    }
+    Ok(())
+}
+
+fn set(index: &mut ModelIndex, version: Version, stage: Stage) {
+    let changed = format_timestamp(now());
+    if let Some(entry) = index.versions.iter_mut().find(|v| v.version == version)
+        && entry.stage != stage
+    {
+        entry.stage = stage;
+        entry.stage_changed = changed;
+    }
+}
+
+fn promote(index: &mut ModelIndex, version: Version) {
+    if let Some(current) = index.production().map(|v| v.version)
+        && current != version
+    {
+        set(index, current, Stage::Archived);
+    }
+    set(index, version, Stage::Production);
+    if index.production_history.last() != Some(&version) {
+        index.production_history.push(version);
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
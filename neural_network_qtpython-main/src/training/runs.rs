@@ -0,0 +1,284 @@
+use crate::data::time_series::format_timestamp;
+use crate::training::config::TrainingConfig;
+use crate::training::history::TrainingHistory;
+use crate::training::trainer::{train_any_with_progress, AnyModel, EpochMetrics, TrainingControl};
+use crate::utils::io::save_any_model;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_RUNS_DIR: &str = "runs";
+
+/// File names inside a run directory.
+pub const RUN_FILE: &str = "run.json";
+pub const CONFIG_FILE: &str = "config.toml";
+pub const MODEL_FILE: &str = "model.bin";
+pub const PLOT_FILE: &str = "training_plot.png";
+pub const HISTORY_FILE: &str = "history.csv";
+pub const DIAGNOSTICS_DIR: &str = "diagnostics";
+
+/// Identifies the exact contents of a dataset file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DatasetFingerprint {
+    pub path: String,
+    pub bytes: u64,
+    /// Data rows, not counting the header.
+    pub rows: usize,
+    /// 64-bit FNV-1a hash of the file, in hex.
+    pub hash: String,
+}
+
+impl DatasetFingerprint {
+    pub fn of(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut reader = BufReader::new(fs::File::open(path)?);
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut bytes = 0;
+        let mut lines = 0;
+        let mut last = b'\n';
+        loop {
+            let buffer = reader.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+            for &byte in buffer {
+                hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+                lines += (byte == b'\n') as usize;
+            }
+            last = buffer[buffer.len() - 1];
+            bytes += buffer.len() as u64;
+            let consumed = buffer.len();
+            reader.consume(consumed);
+        }
+        // A last line without a newline still counts
+        if last != b'\n' {
+            lines += 1;
+        }
+        Ok(DatasetFingerprint { path: path.to_string(), bytes, rows: lines.saturating_sub(1), hash: format!("{:016x}", hash) })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// What a run directory records about its run, stored as `run.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunRecord {
+    pub id: String,
+    /// Start time, `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub created: String,
+    pub status: RunStatus,
+    /// Why a failed run failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub dataset: DatasetFingerprint,
+    pub seed: Option<u64>,
+    /// Final metrics by name: `train_accuracy`, `val_accuracy`, `train_loss`,
+    /// `val_loss` (`train_r2`/`val_r2` for regressors), `epochs` and `seconds`.
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    /// When the run's model was last promoted, if it is the promoted one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub promoted: Option<String>,
+}
+
+/// One row of a comparison between two runs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MetricDiff {
+    pub metric: String,
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+    /// `b - a`, when both runs have the metric.
+    pub diff: Option<f64>,
+}
+
+/// A directory of runs, one subdirectory per run named by its id.
+pub struct RunStore {
+    root: PathBuf,
+}
+
+impl RunStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        RunStore { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn dir(&self, id: &str) -> PathBuf {
+        self.root.join(id)
+    }
+
+    /// Trains a model as described by `config` in a new run directory that
+    /// receives the config, dataset fingerprint, history, metrics, plots and
+    /// model. The config's own output paths are ignored. Failed runs keep
+    /// their directory, marked as failed.
+    pub fn train<C>(&self, config: &TrainingConfig, on_progress: C) -> Result<(RunRecord, AnyModel), Box<dyn Error>>
+    where
+        C: FnMut(&EpochMetrics) -> TrainingControl,
+    {
+        config.validate()?;
+        let dataset = DatasetFingerprint::of(&config.data.path)
+            .map_err(|e| format!("failed to read {}: {}", config.data.path, e))?;
+        let (id, dir) = self.create_dir()?;
+        let mut record = RunRecord {
+            id,
+            created: format_timestamp(now()),
+            status: RunStatus::Running,
+            error: None,
+            dataset,
+            seed: config.seed,
+            metrics: BTreeMap::new(),
+            promoted: None,
+        };
+        self.save(&record)?;
+
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        let mut run_config = config.clone();
+        run_config.output.model = path(MODEL_FILE);
+        run_config.output.plot = path(PLOT_FILE);
+        run_config.output.history = Some(path(HISTORY_FILE));
+        run_config.output.diagnostics = (!config.data.is_regression()).then(|| path(DIAGNOSTICS_DIR));
+        if config.output.report.is_some() {
+            run_config.output.report = Some(path("report.html"));
+        }
+
+        let started = SystemTime::now();
+        let trained = train_any_with_progress(&run_config, on_progress).and_then(|model| {
+            save_any_model(&run_config.output.model, &model)?;
+            fs::write(dir.join(CONFIG_FILE), model.config().to_toml_string()?)?;
+            Ok(model)
+        });
+        match trained {
+            Ok(model) => {
+                let seconds = started.elapsed().map(|d| d.as_secs_f64()).unwrap_or(0.0);
+                record.status = RunStatus::Completed;
+                record.seed = model.config().seed;
+                record.metrics = model_metrics(&model);
+                record.metrics.insert("seconds".to_string(), seconds);
+                self.save(&record)?;
+                Ok((record, model))
+            }
+            Err(e) => {
+                record.status = RunStatus::Failed;
+                record.error = Some(e.to_string());
+                self.save(&record)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Every run, oldest first. Directories without a readable `run.json`
+    /// are skipped.
+    pub fn list(&self) -> Result<Vec<RunRecord>, Box<dyn Error>> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut runs = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir()
+                && let Ok(record) = self.get(&entry.file_name().to_string_lossy())
+            {
+                runs.push(record);
+            }
+        }
+        runs.sort_by(|a, b| (&a.created, &a.id).cmp(&(&b.created, &b.id)));
+        Ok(runs)
+    }
+
+    pub fn get(&self, id: &str) -> Result<RunRecord, Box<dyn Error>> {
+        let mut text = String::new();
+        fs::File::open(self.dir(id).join(RUN_FILE))
+            .map_err(|e| format!("run {} not found in {}: {}", id, self.root.display(), e))?
+            .read_to_string(&mut text)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Every metric of either run, with the change from `a` to `b`.
+    pub fn compare(&self, a: &str, b: &str) -> Result<Vec<MetricDiff>, Box<dyn Error>> {
+        let (a, b) = (self.get(a)?, self.get(b)?);
+        let mut names: Vec<&String> = a.metrics.keys().chain(b.metrics.keys()).collect();
+        names.sort();
+        names.dedup();
+        Ok(names.into_iter()
+            .map(|name| {
+                let (a, b) = (a.metrics.get(name).copied(), b.metrics.get(name).copied());
+                MetricDiff { metric: name.clone(), a, b, diff: a.zip(b).map(|(a, b)| b - a) }
+            })
+            .collect())
+    }
+
+    /// Copies the model of a completed run to `destination` and marks the
+    /// run as the promoted one, unmarking any other.
+    pub fn promote(&self, id: &str, destination: &str) -> Result<RunRecord, Box<dyn Error>> {
+        let mut record = self.get(id)?;
+        if record.status != RunStatus::Completed {
+            return Err(format!("run {} did not complete", id).into());
+        }
+        fs::copy(self.dir(id).join(MODEL_FILE), destination)
+            .map_err(|e| format!("failed to copy the model of run {} to {}: {}", id, destination, e))?;
+        for mut other in self.list()? {
+            if other.id != id && other.promoted.take().is_some() {
+                self.save(&other)?;
+            }
+        }
+        record.promoted = Some(format_timestamp(now()));
+        self.save(&record)?;
+        Ok(record)
+    }
+
+    /// Creates a directory named after the current time and a random suffix.
+    fn create_dir(&self) -> Result<(String, PathBuf), Box<dyn Error>> {
+        fs::create_dir_all(&self.root)?;
+        let stamp = format_timestamp(now()).replace(['-', ':'], "").replace(' ', "-");
+        loop {
+            let id = format!("{}-{:06x}", stamp, rand::random::<u32>() & 0xffffff);
+            let dir = self.dir(&id);
+            match fs::create_dir(&dir) {
+                Ok(()) => return Ok((id, dir)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn save(&self, record: &RunRecord) -> Result<(), Box<dyn Error>> {
+        fs::write(self.dir(&record.id).join(RUN_FILE), serde_json::to_string_pretty(record)?)?;
+        Ok(())
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+/// Final metrics of a trained model: `train_accuracy`, `val_accuracy`,
+/// `train_loss`, `val_loss` (`train_r2`/`val_r2` for regressors) and `epochs`.
+pub fn model_metrics(model: &AnyModel) -> BTreeMap<String, f64> {
+    let history: TrainingHistory = model.cast::<f64>().history;
+    let score = if model.is_regression() { "r2" } else { "accuracy" };
+    let last = |values: &[f64]| values.iter().rev().copied().find(|v| v.is_finite());
+    let mut metrics = BTreeMap::new();
+    metrics.insert(format!("train_{}", score), model.final_accuracy());
+    for (name, values) in [
+        (format!("val_{}", score), &history.val_accuracies),
+        ("train_loss".to_string(), &history.losses),
+        ("val_loss".to_string(), &history.val_losses),
+    ] {
+        if let Some(value) = last(values) {
+            metrics.insert(name, value);
+        }
+    }
+    metrics.insert("epochs".to_string(), history.epochs.last().map_or(0.0, |&e| (e + 1) as f64));
+    metrics
+}
@@ -0,0 +1,501 @@
+use crate::data::preprocessing::{k_fold_splits, train_validation_split};
+use crate::model::float::Precision;
+use crate::model::layers::Activation;
+use crate::training::config::{parse_file, ConfigError, TrainingConfig};
+use crate::training::trainer::{
+    fit, resolve_seed, train_any_with_progress, training_rng, AnyModel, EpochMetrics, TrainingControl,
+    TrainingDiverged,
+};
+use crate::utils::parallel;
+use ndarray::Array2;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+use std::time::Instant;
+
+/// A hyperparameter search: which values to try, how to pick combinations
+/// and how to score them.
+///
+/// ```toml
+/// threads = 4
+///
+/// [base.schedule]
+/// epochs = 500
+///
+/// [space]
+/// hidden_layers = [[10, 10, 10], [16, 16], [32]]
+/// learning_rate = [0.001, 0.0003]
+/// activation = ["relu", "tanh"]
+///
+/// [strategy]
+/// kind = "hyperband"
+/// min_epochs = 50
+///
+/// [scoring]
+/// kind = "cross_validation"
+/// folds = 5
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// Settings shared by every trial, and the full training budget.
+    pub base: TrainingConfig,
+    pub space: SearchSpace,
+    pub strategy: Strategy,
+    pub scoring: Scoring,
+    /// Trials run in parallel on this many threads; 0 uses the shared pool.
+    pub threads: usize,
+    pub output: SearchOutput,
+}
+
+/// Candidate values per hyperparameter. An empty list keeps the base value.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchSpace {
+    pub hidden_layers: Vec<Vec<usize>>,
+    pub learning_rate: Vec<f64>,
+    pub l2: Vec<f64>,
+    /// Rows per gradient step; 0 means full batch.
+    pub batch_size: Vec<usize>,
+    pub activation: Vec<Activation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Strategy {
+    /// Every combination of the search space.
+    #[default]
+    Grid,
+    /// `trials` combinations drawn at random from the search space.
+    Random { trials: usize },
+    /// Starts `candidates` random combinations with `min_epochs` epochs, then
+    /// repeatedly keeps the best `1 / eta` and multiplies their epochs by
+    /// `eta` until one is left or the base epoch budget is reached.
+    SuccessiveHalving {
+        candidates: usize,
+        min_epochs: usize,
+        #[serde(default = "default_eta")]
+        eta: usize,
+    },
+    /// Successive halving in several brackets that trade candidates against
+    /// starting epochs: the first starts the most candidates with
+    /// `min_epochs`, the last trains a few with the full base budget.
+    Hyperband {
+        min_epochs: usize,
+        #[serde(default = "default_eta")]
+        eta: usize,
+    },
+}
+
+fn default_eta() -> usize {
+    3
+}
+
+/// Trials are ranked by validation accuracy, then by validation loss.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Scoring {
+    /// Hold out `base.split.validation` of the rows.
+    #[default]
+    Validation,
+    /// Average over `folds` train/validation splits.
+    CrossValidation { folds: usize },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchOutput {
+    pub leaderboard_csv: Option<String>,
+    pub leaderboard_json: Option<String>,
+}
+
+impl Default for SearchOutput {
+    fn default() -> Self {
+        SearchOutput {
+            leaderboard_csv: Some("leaderboard.csv".to_string()),
+            leaderboard_json: Some("leaderboard.json".to_string()),
+        }
+    }
+}
+
+/// One point of the search space.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub hidden_layers: Vec<usize>,
+    pub learning_rate: f64,
+    pub l2: f64,
+    pub batch_size: usize,
+    pub activation: Activation,
+}
+
+impl Candidate {
+    /// The base config with this candidate's hyperparameters.
+    pub fn apply(&self, base: &TrainingConfig) -> TrainingConfig {
+        let mut config = base.clone();
+        config.architecture.hidden_layers = self.hidden_layers.clone();
+        config.optimizer.learning_rate = self.learning_rate;
+        config.regularization.l2 = self.l2;
+        config.optimizer.batch_size = self.batch_size;
+        config.architecture.activation = self.activation;
+        config
+    }
+}
+
+/// Scores of one trained candidate. Losses are per row. A trial whose
+/// training diverged has no scores.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrialResult {
+    pub trial: usize,
+    /// Hyperband bracket; always 0 for the other strategies.
+    #[serde(default)]
+    pub bracket: usize,
+    /// Successive halving round within the bracket; always 0 for grid and
+    /// random search.
+    pub round: usize,
+    pub epochs: usize,
+    #[serde(flatten)]
+    pub candidate: Candidate,
+    pub train_accuracy: Option<f64>,
+    pub val_accuracy: Option<f64>,
+    pub val_loss: Option<f64>,
+    pub duration_secs: f64,
+    #[serde(default)]
+    pub diverged: bool,
+}
+
+pub struct SearchOutcome {
+    /// Every trial, best first.
+    pub leaderboard: Vec<TrialResult>,
+    /// The winning candidate retrained with the full base budget.
+    pub best: AnyModel,
+}
+
+impl SearchConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let config: SearchConfig = parse_file(path.as_ref())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = match self.base.validate() {
+            Err(ConfigError::Invalid(errors)) => errors.into_iter().map(|e| format!("base.{}", e)).collect(),
+            Err(e) => return Err(e),
+            Ok(()) => Vec::new(),
+        };
+        let mut check = |ok: bool, field: &str, message: &str| {
+            if !ok {
+                errors.push(format!("{}: {}", field, message));
+            }
+        };
+
+        check(self.threads <= parallel::MAX_THREADS, "threads", &format!("must be at most {}", parallel::MAX_THREADS));
+        // Folds and random splits would leak future readings into training
+        check(self.base.time_series.is_none(), "base.time_series", "is not supported by the search; use backtest");
+        for (i, layers) in self.space.hidden_layers.iter().enumerate() {
+            check(layers.iter().all(|&units| units > 0), &format!("space.hidden_layers[{}]", i),
+                "layer sizes must be at least 1");
+        }
+        for (i, &rate) in self.space.learning_rate.iter().enumerate() {
+            check(rate > 0.0 && rate.is_finite(), &format!("space.learning_rate[{}]", i), "must be a positive number");
+        }
+        for (i, &l2) in self.space.l2.iter().enumerate() {
+            check(l2 >= 0.0 && l2.is_finite(), &format!("space.l2[{}]", i), "must be a non-negative number");
+        }
+        for (i, &activation) in self.space.activation.iter().enumerate() {
+            check(activation != Activation::Softmax, &format!("space.activation[{}]", i),
+                "softmax is reserved for the output layer");
+        }
+
+        match self.strategy {
+            Strategy::Grid => {}
+            Strategy::Random { trials } => check(trials > 0, "strategy.trials", "must be at least 1"),
+            Strategy::SuccessiveHalving { candidates, min_epochs, eta } => {
+                check(candidates > 0, "strategy.candidates", "must be at least 1");
+                check(min_epochs > 0, "strategy.min_epochs", "must be at least 1");
+                check(eta >= 2, "strategy.eta", "must be at least 2");
+            }
+            Strategy::Hyperband { min_epochs, eta } => {
+                check(min_epochs > 0, "strategy.min_epochs", "must be at least 1");
+                check(eta >= 2, "strategy.eta", "must be at least 2");
+            }
+        }
+        match self.scoring {
+            Scoring::Validation => check(self.base.split.validation > 0.0, "base.split.validation",
+                "must be positive when scoring on a validation split"),
+            Scoring::CrossValidation { folds } => check(folds >= 2, "scoring.folds", "must be at least 2"),
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(ConfigError::Invalid(errors)) }
+    }
+}
+
+impl SearchSpace {
+    /// Every combination, with empty lists replaced by the base value.
+    pub fn grid(&self, base: &TrainingConfig) -> Vec<Candidate> {
+        let (layers, rates, l2s, batches, activations) = self.values(base);
+        let mut candidates = Vec::new();
+        for hidden_layers in &layers {
+            for &learning_rate in &rates {
+                for &l2 in &l2s {
+                    for &batch_size in &batches {
+                        for &activation in &activations {
+                            candidates.push(Candidate {
+                                hidden_layers: hidden_layers.clone(),
+                                learning_rate,
+                                l2,
+                                batch_size,
+                                activation,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    /// `count` candidates with every hyperparameter drawn independently.
+    pub fn sample<R: Rng>(&self, base: &TrainingConfig, count: usize, rng: &mut R) -> Vec<Candidate> {
+        let (layers, rates, l2s, batches, activations) = self.values(base);
+        (0..count)
+            .map(|_| Candidate {
+                hidden_layers: layers.choose(rng).unwrap().clone(),
+                learning_rate: *rates.choose(rng).unwrap(),
+                l2: *l2s.choose(rng).unwrap(),
+                batch_size: *batches.choose(rng).unwrap(),
+                activation: *activations.choose(rng).unwrap(),
+            })
+            .collect()
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn values(&self, base: &TrainingConfig) -> (Vec<Vec<usize>>, Vec<f64>, Vec<f64>, Vec<usize>, Vec<Activation>) {
+        fn or_base<T: Clone>(values: &[T], base: T) -> Vec<T> {
+            if values.is_empty() { vec![base] } else { values.to_vec() }
+        }
+        (
+            or_base(&self.hidden_layers, base.architecture.hidden_layers.clone()),
+            or_base(&self.learning_rate, base.optimizer.learning_rate),
+            or_base(&self.l2, base.regularization.l2),
+            or_base(&self.batch_size, base.optimizer.batch_size),
+            or_base(&self.activation, base.architecture.activation),
+        )
+    }
+}
+
+/// Runs the search, then retrains the best candidate with the base epoch
+/// budget, split and outputs. With a fixed `base.seed` the leaderboard and
+/// model are identical for any thread count. Saving the model is left to the caller.
+pub fn run_search(config: &SearchConfig) -> Result<SearchOutcome, Box<dyn Error>> {
+    config.validate()?;
+    let base = &config.base;
+    let data = &base.data;
+    let (x, y) = data.load(&data.path)?;
+
+    // Every trial sees the same splits and initialization seed
+    let seed = resolve_seed(base.seed);
+    let mut rng = training_rng(seed);
+
+    let pool = parallel::pool_with(config.threads)?;
+    let run_round = |candidates: &[Candidate], epochs: usize, bracket: usize, round: usize, first_trial: usize| {
+        pool.install(|| {
+            candidates.par_iter()
+                .enumerate()
+                .map(|(i, candidate)| {
+                    let mut trial_config = candidate.apply(base);
+                    trial_config.schedule.epochs = epochs;
+                    trial_config.output.log_every = 0;
+                    // Trials already run in parallel, so each one trains on a single thread
+                    trial_config.threads = 1;
+                    let started = Instant::now();
+                    // A diverged trial is recorded as failed rather than ending the search
+                    let scores = match run_trial(&trial_config, &x, &y, config.scoring, seed) {
+                        Ok(scores) => Some(scores),
+                        Err(e) if e.is::<TrainingDiverged>() => None,
+                        Err(e) => return Err(format!("trial {}: {}", first_trial + i, e)),
+                    };
+                    Ok(TrialResult {
+                        trial: first_trial + i,
+                        bracket,
+                        round,
+                        epochs,
+                        candidate: candidate.clone(),
+                        train_accuracy: scores.as_ref().map(|s| s.train_accuracy),
+                        val_accuracy: scores.as_ref().map(|s| s.val_accuracy),
+                        val_loss: scores.as_ref().map(|s| s.val_loss),
+                        duration_secs: started.elapsed().as_secs_f64(),
+                        diverged: scores.is_none(),
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()
+        })
+    };
+
+    // Appends the rounds of one successive halving bracket to `all`
+    let max_epochs = base.schedule.epochs;
+    let halve = |all: &mut Vec<TrialResult>, mut survivors: Vec<Candidate>, mut epochs: usize, eta: usize, bracket: usize| {
+        for round in 0.. {
+            let mut results = run_round(&survivors, epochs, bracket, round, all.len())?;
+            results.sort_by(compare_trials);
+            let done = results.len() <= 1 || epochs >= max_epochs;
+            survivors = results.iter()
+                .take(results.len().div_ceil(eta))
+                .filter(|r| !r.diverged)
+                .map(|r| r.candidate.clone())
+                .collect();
+            all.extend(results);
+            if done || survivors.is_empty() {
+                break;
+            }
+            epochs = (epochs * eta).min(max_epochs);
+        }
+        Ok::<_, String>(())
+    };
+
+    let mut leaderboard = match config.strategy {
+        Strategy::Grid => run_round(&config.space.grid(base), max_epochs, 0, 0, 0)?,
+        Strategy::Random { trials } => {
+            run_round(&config.space.sample(base, trials, &mut rng), max_epochs, 0, 0, 0)?
+        }
+        Strategy::SuccessiveHalving { candidates, min_epochs, eta } => {
+            let mut all = Vec::new();
+            halve(&mut all, config.space.sample(base, candidates, &mut rng), min_epochs.min(max_epochs), eta, 0)?;
+            all
+        }
+        Strategy::Hyperband { min_epochs, eta } => {
+            let mut all = Vec::new();
+            for (bracket, (candidates, epochs)) in hyperband_brackets(min_epochs, max_epochs, eta).into_iter().enumerate() {
+                halve(&mut all, config.space.sample(base, candidates, &mut rng), epochs, eta, bracket)?;
+            }
+            all
+        }
+    };
+
+    // Later rounds trained longer, so they outrank earlier ones
+    leaderboard.sort_by(|a, b| {
+        a.diverged.cmp(&b.diverged)
+            .then_with(|| b.epochs.cmp(&a.epochs))
+            .then_with(|| compare_trials(a, b))
+    });
+    let best = leaderboard.first().ok_or("the search space is empty")?;
+    if best.diverged {
+        return Err(format!("every one of the {} trials diverged", leaderboard.len()).into());
+    }
+    let mut best_config = best.candidate.apply(base);
+    best_config.seed = Some(seed);
+    let best = train_any_with_progress(&best_config, |_| TrainingControl::Continue)?;
+
+    Ok(SearchOutcome { leaderboard, best })
+}
+
+/// Candidates and starting epochs of each Hyperband bracket. With `s`
+/// halvings left to reach `max_epochs`, a bracket starts
+/// `ceil((s_max + 1) / (s + 1) * eta^s)` candidates with `max_epochs / eta^s`
+/// epochs.
+pub fn hyperband_brackets(min_epochs: usize, max_epochs: usize, eta: usize) -> Vec<(usize, usize)> {
+    let mut s_max = 0;
+    while eta.checked_pow(s_max + 1).is_some_and(|step| min_epochs.saturating_mul(step) <= max_epochs) {
+        s_max += 1;
+    }
+    (0..=s_max)
+        .rev()
+        .map(|s| {
+            let candidates = ((s_max + 1) as usize * eta.pow(s)).div_ceil(s as usize + 1);
+            (candidates, (max_epochs / eta.pow(s)).max(1))
+        })
+        .collect()
+}
+
+/// Orders trials best first: higher validation accuracy, then lower loss.
+/// Diverged trials come last.
+fn compare_trials(a: &TrialResult, b: &TrialResult) -> std::cmp::Ordering {
+    let accuracy = |t: &TrialResult| t.val_accuracy.unwrap_or(f64::NEG_INFINITY);
+    let loss = |t: &TrialResult| t.val_loss.unwrap_or(f64::INFINITY);
+    accuracy(b).total_cmp(&accuracy(a))
+        .then_with(|| loss(a).total_cmp(&loss(b)))
+}
+
+/// Accuracies and per-row loss, averaged over folds.
+struct Scores {
+    train_accuracy: f64,
+    val_accuracy: f64,
+    val_loss: f64,
+}
+
+fn run_trial(config: &TrainingConfig, x: &Array2<f64>, y: &Array2<f64>, scoring: Scoring, seed: u64) -> Result<Scores, Box<dyn Error>> {
+    let mut rng = training_rng(seed);
+    let splits = match scoring {
+        Scoring::Validation => vec![train_validation_split(x, y, config.split.validation, &mut rng)],
+        Scoring::CrossValidation { folds } => k_fold_splits(x, y, folds, &mut rng),
+    };
+
+    let folds = splits.len() as f64;
+    let (mut train_accuracy, mut val_accuracy, mut val_loss) = (0.0, 0.0, 0.0);
+    for split in splits {
+        let mut last = None;
+        let record = |metrics: &EpochMetrics| {
+            last = Some(*metrics);
+            TrainingControl::Continue
+        };
+        match config.precision {
+            Precision::F32 => drop(fit::<f32, _, _>(config, split, &mut rng, record)?),
+            Precision::F64 => drop(fit::<f64, _, _>(config, split, &mut rng, record)?),
+        }
+
+        // The last epoch is always evaluated
+        let last = last.ok_or("training ran no epochs")?;
+        train_accuracy += last.train_accuracy / folds;
+        val_accuracy += last.val_accuracy / folds;
+        val_loss += last.val_loss / folds;
+    }
+
+    Ok(Scores { train_accuracy, val_accuracy, val_loss })
+}
+
+/// Diverged trials leave their score columns empty.
+pub fn write_leaderboard_csv(path: &str, leaderboard: &[TrialResult]) -> Result<(), Box<dyn Error>> {
+    let score = |value: Option<f64>| value.map(|v| format!("{:.6}", v)).unwrap_or_default();
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record([
+        "rank", "trial", "bracket", "round", "epochs", "hidden_layers", "learning_rate", "l2", "batch_size",
+        "activation", "train_accuracy", "val_accuracy", "val_loss", "duration_secs", "diverged",
+    ])?;
+    for (rank, result) in leaderboard.iter().enumerate() {
+        let candidate = &result.candidate;
+        let layers: Vec<String> = candidate.hidden_layers.iter().map(|u| u.to_string()).collect();
+        let activation = serde_json::to_value(candidate.activation)?;
+        writer.write_record([
+            (rank + 1).to_string(),
+            result.trial.to_string(),
+            result.bracket.to_string(),
+            result.round.to_string(),
+            result.epochs.to_string(),
+            layers.join("-"),
+            candidate.learning_rate.to_string(),
+            candidate.l2.to_string(),
+            candidate.batch_size.to_string(),
+            activation.as_str().unwrap_or_default().to_string(),
+            score(result.train_accuracy),
+            score(result.val_accuracy),
+            score(result.val_loss),
+            format!("{:.3}", result.duration_secs),
+            result.diverged.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_leaderboard_json(path: &str, leaderboard: &[TrialResult]) -> Result<(), Box<dyn Error>> {
+    serde_json::to_writer_pretty(File::create(path)?, leaderboard)?;
+    Ok(())
+}
+
+pub fn read_leaderboard_json(path: &str) -> Result<Vec<TrialResult>, Box<dyn Error>> {
+    Ok(serde_json::from_reader(std::io::BufReader::new(File::open(path)?))?)
+}
@@ -1,59 +1,622 @@
+use crate::model::float::{Float, Precision};
 use crate::model::network::NeuralNetwork;
+use crate::training::config::{DivergencePolicy, TrainingConfig};
+use crate::training::diagnostics::diagnose;
+use crate::training::drift::InputProfile;
+use crate::training::evaluation::mean_r_squared;
 use crate::training::history::TrainingHistory;
-use crate::data::preprocessing::{load_and_preprocess_data, DataStats};
+use crate::data::preprocessing::{train_validation_split, DataSplit, DataStats, Normalization};
+use crate::data::time_series::chronological_split;
+use crate::utils::io::load_any_model;
+use crate::utils::parallel;
+use crate::utils::diagnostic_plots::write_diagnostic_plots;
+use crate::utils::plot::create_plot_with;
+use crate::utils::report::write_report;
+use ndarray::{Array2, Axis};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
 
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct TrainedModel {
-    pub network: NeuralNetwork,
-    pub stats: DataStats,
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(bound = "F: Float")]
+pub struct TrainedModel<F: Float = f64> {
+    pub network: NeuralNetwork<F>,
+    pub stats: DataStats<F>,
+    /// Mean and standard deviation of the targets of a regressor, whose
+    /// network predicts standardized values. `None` for classifiers.
+    pub target_stats: Option<DataStats<F>>,
+    /// Training accuracy, or the mean R² over the targets for regressors.
+    pub final_accuracy: f64,
+    /// The configuration the model was trained with. `seed` and `threads` are
+    /// always set, so training again with this config reproduces the model
+    /// exactly.
+    #[serde(with = "embedded_config")]
+    pub config: TrainingConfig,
+    /// Metrics of every evaluation step of the training run. Empty for model
+    /// files saved before it was recorded (see `utils::io`).
+    pub history: TrainingHistory,
+    /// Distributions of the raw training inputs, which drift checks compare
+    /// new readings with. `None` for model files saved before they were
+    /// recorded.
+    pub profile: Option<InputProfile>,
 }
 
+/// Binary formats such as bincode cannot read the internally tagged enums of
+/// a config, so they store it as JSON text. Readable formats nest it as is.
+pub(crate) mod embedded_config {
+    use super::TrainingConfig;
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(config: &TrainingConfig, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            config.serialize(serializer)
+        } else {
+            serializer.serialize_str(&serde_json::to_string(config).map_err(ser::Error::custom)?)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TrainingConfig, D::Error> {
+        if deserializer.is_human_readable() {
+            TrainingConfig::deserialize(deserializer)
+        } else {
+            serde_json::from_str(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+        }
+    }
+}
+
+impl<F: Float> TrainedModel<F> {
+    pub fn precision(&self) -> Precision {
+        F::PRECISION
+    }
+
+    /// The same model with its parameters converted to another precision.
+    pub fn cast<T: Float>(&self) -> TrainedModel<T> {
+        TrainedModel {
+            network: self.network.cast(),
+            stats: self.stats.cast(),
+            target_stats: self.target_stats.as_ref().map(DataStats::cast),
+            final_accuracy: self.final_accuracy,
+            config: TrainingConfig { precision: T::PRECISION, ..self.config.clone() },
+            history: self.history.clone(),
+            profile: self.profile.clone(),
+        }
+    }
+
+    /// Whether every parameter and normalization statistic is finite.
+    pub fn is_finite(&self) -> bool {
+        let finite = |stats: &DataStats<F>| stats.mean.iter().chain(&stats.std).all(|v| v.is_finite());
+        self.network.is_finite() && finite(&self.stats) && self.target_stats.as_ref().is_none_or(finite)
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.target_stats.is_some()
+    }
+
+    /// Predicted targets of a regressor for raw `f64` readings, in the units
+    /// of the training data. Classifiers return their class probabilities.
+    pub fn predict_values(&self, x: &Array2<f64>) -> Array2<f64> {
+        let output = self.network.predict_batch(&x.mapv(F::cast), &self.stats);
+        match &self.target_stats {
+            Some(stats) => stats.denormalize(&output).mapv(F::widen),
+            None => output.mapv(F::widen),
+        }
+    }
+
+    /// Class probabilities for raw `f64` readings, computed in the model's precision.
+    pub fn predict_batch(&self, x: &Array2<f64>) -> Array2<f64> {
+        self.network.predict_batch(&x.mapv(F::cast), &self.stats).mapv(F::widen)
+    }
+
+    /// Input columns, in the order the network expects them.
+    pub fn features(&self) -> &[String] {
+        &self.config.data.features
+    }
+
+    /// Class names, in the order of the network outputs.
+    pub fn classes(&self) -> &[String] {
+        &self.config.data.classes
+    }
+
+    /// Names of the network outputs: the targets of a regressor, otherwise
+    /// the classes.
+    pub fn outputs(&self) -> &[String] {
+        self.config.data.outputs()
+    }
+}
+
+/// A trained model of either precision. Model files store this, so they
+/// record the precision of the parameters.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub enum AnyModel {
+    F32(TrainedModel<f32>),
+    F64(TrainedModel<f64>),
+}
+
+impl AnyModel {
+    pub fn new<F: Float>(model: &TrainedModel<F>) -> Self {
+        match F::PRECISION {
+            Precision::F32 => AnyModel::F32(model.cast()),
+            Precision::F64 => AnyModel::F64(model.cast()),
+        }
+    }
+
+    pub fn precision(&self) -> Precision {
+        match self {
+            AnyModel::F32(_) => Precision::F32,
+            AnyModel::F64(_) => Precision::F64,
+        }
+    }
+
+    /// The model in the requested precision, converting if needed.
+    pub fn cast<T: Float>(&self) -> TrainedModel<T> {
+        match self {
+            AnyModel::F32(model) => model.cast(),
+            AnyModel::F64(model) => model.cast(),
+        }
+    }
+
+    pub fn predict_batch(&self, x: &Array2<f64>) -> Array2<f64> {
+        match self {
+            AnyModel::F32(model) => model.predict_batch(x),
+            AnyModel::F64(model) => model.predict_batch(x),
+        }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        match self {
+            AnyModel::F32(model) => model.is_finite(),
+            AnyModel::F64(model) => model.is_finite(),
+        }
+    }
+
+    pub fn predict_values(&self, x: &Array2<f64>) -> Array2<f64> {
+        match self {
+            AnyModel::F32(model) => model.predict_values(x),
+            AnyModel::F64(model) => model.predict_values(x),
+        }
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.config().data.is_regression()
+    }
+
+    pub fn final_accuracy(&self) -> f64 {
+        self.config_and_accuracy().1
+    }
+
+    pub fn config(&self) -> &TrainingConfig {
+        self.config_and_accuracy().0
+    }
+
+    fn config_and_accuracy(&self) -> (&TrainingConfig, f64) {
+        match self {
+            AnyModel::F32(model) => (&model.config, model.final_accuracy),
+            AnyModel::F64(model) => (&model.config, model.final_accuracy),
+        }
+    }
+}
+
+/// Metrics reported to the progress callback at every evaluation step.
+/// Validation values are NaN when training without a validation split.
+#[derive(Debug, Clone, Copy)]
+pub struct EpochMetrics {
+    pub epoch: usize,
+    pub total_epochs: usize,
+    pub train_loss: f64,
+    /// Accuracy, or the mean R² over the targets for regressors.
+    pub train_accuracy: f64,
+    pub val_loss: f64,
+    pub val_accuracy: f64,
+    pub learning_rate: f64,
+    pub elapsed: Duration,
+    pub remaining: Duration,
+    /// Mean gradient norm over the mini-batches of the epoch.
+    pub gradient_norm: f64,
+}
+
+/// Returned by a progress callback to tell the training loop whether to go on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingControl {
+    Continue,
+    Stop,
+}
+
+/// Error returned when a progress callback asked training to stop.
+#[derive(Debug)]
+pub struct TrainingCancelled {
+    pub epoch: usize,
+}
+
+impl fmt::Display for TrainingCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "training cancelled at epoch {}", self.epoch)
+    }
+}
+
+impl Error for TrainingCancelled {}
+
+/// Error returned when the loss, gradients or weights stopped being finite
+/// and `divergence.policy` did not allow (or ran out of) recoveries.
+#[derive(Debug)]
+pub struct TrainingDiverged {
+    pub epoch: usize,
+    /// `loss`, `gradients` or `weights`.
+    pub reason: &'static str,
+}
+
+impl fmt::Display for TrainingDiverged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "training diverged at epoch {}: non-finite {}", self.epoch, self.reason)
+    }
+}
+
+impl Error for TrainingDiverged {}
+
 pub fn train_model(
     csv_path: &str,
     epochs: usize,
     plot_path: &str,
 ) -> Result<TrainedModel, Box<dyn Error>> {
-    // Load and preprocess data
-    let (x_normalized, y, stats) = load_and_preprocess_data(csv_path)?;
+    let mut config = TrainingConfig::default();
+    config.data.path = csv_path.to_string();
+    config.schedule.epochs = epochs;
+    config.output.plot = plot_path.to_string();
+    train_model_with_progress(&config, |_| TrainingControl::Continue)
+}
+
+/// Smallest number of rows worth computing a gradient shard for.
+const MIN_SHARD_ROWS: usize = 128;
+
+/// Generator behind every random choice of a training run. ChaCha8 yields
+/// the same stream on every platform and `rand` release, unlike `StdRng`.
+pub type TrainingRng = ChaCha8Rng;
+
+pub fn training_rng(seed: u64) -> TrainingRng {
+    TrainingRng::seed_from_u64(seed)
+}
+
+/// The configured seed, or a fresh random one for unseeded runs. Random
+/// seeds stay below 2^63 because TOML integers are signed, and the stored
+/// config must stay writable as TOML.
+pub fn resolve_seed(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(|| rand::random::<u64>() >> 1)
+}
+
+/// Trains a network in the precision chosen by `config.precision`.
+pub fn train_any_with_progress<C>(config: &TrainingConfig, on_progress: C) -> Result<AnyModel, Box<dyn Error>>
+where
+    C: FnMut(&EpochMetrics) -> TrainingControl,
+{
+    Ok(match config.precision {
+        Precision::F32 => AnyModel::F32(train_model_with_progress(config, on_progress)?),
+        Precision::F64 => AnyModel::F64(train_model_with_progress(config, on_progress)?),
+    })
+}
+
+/// Trains a network as described by `config`, which must ask for the
+/// precision `F`. The plot is written to `config.output.plot`; saving the
+/// model is left to the caller.
+pub fn train_model_with_progress<F, C>(
+    config: &TrainingConfig,
+    on_progress: C,
+) -> Result<TrainedModel<F>, Box<dyn Error>>
+where
+    F: Float,
+    C: FnMut(&EpochMetrics) -> TrainingControl,
+{
+    config.validate()?;
+    if config.precision != F::PRECISION {
+        return Err(format!("the config asks for {} precision, not {}", config.precision, F::PRECISION).into());
+    }
+
+    // Record the seed and thread count actually used, so that any run can be
+    // reproduced from the configuration stored in the model
+    let seed = resolve_seed(config.seed);
+    let threads = match config.threads {
+        0 => parallel::threads(),
+        threads => threads,
+    };
+    let config = &TrainingConfig { seed: Some(seed), threads, ..config.clone() };
+    let mut rng = training_rng(seed);
+
+    // Load and split data
+    let data = &config.data;
+    // Forecasters validate on the latest readings instead of a random sample
+    let split = match &config.time_series {
+        Some(series) => chronological_split(&config.load_windows(&data.path, true)?, config.split.validation, series),
+        None => {
+            let (x, y) = data.load(&data.path)?;
+            train_validation_split(&x, &y, config.split.validation, &mut rng)
+        }
+    };
+
+    // Diagnostics and reports describe the validation data, or the training
+    // data without a split
+    let output = &config.output;
+    let held_out = (output.diagnostics.is_some() || output.report.is_some()).then(|| match split.x_val.nrows() {
+        0 => (split.x_train.clone(), split.y_train.clone(), "training data"),
+        _ => (split.x_val.clone(), split.y_val.clone(), "validation data"),
+    });
+
+    let model = fit(config, split, &mut rng, on_progress)?;
+
+    // Save training plot, history, diagnostics and report
+    create_plot_with(&model.history, &output.plot, &output.plot_options)?;
+    if let Some(path) = &output.history {
+        model.history.save(path).map_err(|e| format!("failed to write history {}: {}", path, e))?;
+    }
+    if let (Some(dir), Some((x, y, _))) = (&output.diagnostics, &held_out) {
+        write_diagnostic_plots(&diagnose(&model, x, y), dir, output.diagnostics_format)
+            .map_err(|e| format!("failed to write diagnostic plots to {}: {}", dir, e))?;
+    }
+    if let (Some(path), Some((x, y, part))) = (&output.report, &held_out) {
+        let source = format!("{} of {}", part, data.path);
+        write_report(path, &model, "Training report", &source, x, y)
+            .map_err(|e| format!("failed to write report {}: {}", path, e))?;
+    }
+
+    Ok(model)
+}
+
+/// Trains a network on an existing split of raw (unnormalized) data. All
+/// randomness (initialization, batch order) comes from `rng`.
+pub(crate) fn fit<F, R, C>(
+    config: &TrainingConfig,
+    split: DataSplit,
+    rng: &mut R,
+    mut on_progress: C,
+) -> Result<TrainedModel<F>, Box<dyn Error>>
+where
+    F: Float,
+    R: Rng + Send,
+    C: FnMut(&EpochMetrics) -> TrainingControl,
+{
+    let epochs = config.schedule.epochs;
+    let eval_interval = config.schedule.eval_interval;
+    let log_every = config.output.log_every;
+
+    // Normalize data
+    if split.x_train.nrows() == 0 {
+        return Err("validation split leaves no rows for training".into());
+    }
+    // A warm start keeps the normalization the source model was trained with
+    let source = match &config.architecture.warm_start {
+        Some(path) => Some(warm_start_model::<F>(path, config)?),
+        None => None,
+    };
+    let stats = match &source {
+        Some(source) => source.stats.clone(),
+        None => DataStats::fit(&split.x_train, config.preprocessing.normalization).cast::<F>(),
+    };
+    let x_train = stats.normalize(&split.x_train.mapv(F::cast));
+    let x_val = stats.normalize(&split.x_val.mapv(F::cast));
+    // Regression targets are standardized too, so that the loss and the
+    // learning rate do not depend on their units
+    let target_stats = if !config.data.is_regression() {
+        None
+    } else if let Some(source) = &source {
+        source.target_stats.clone()
+    } else {
+        Some(DataStats::fit(&split.y_train, Normalization::Standard).cast::<F>())
+    };
+    let scale_targets = |y: &Array2<f64>| {
+        let y = y.mapv(F::cast);
+        match &target_stats {
+            Some(stats) => stats.normalize(&y),
+            None => y,
+        }
+    };
+    let y_train = scale_targets(&split.y_train);
+    let y_val = scale_targets(&split.y_val);
+    // Accuracy for classifiers, mean R² for regressors
+    let score = |nn: &NeuralNetwork<F>, y: &Array2<F>, output: &Array2<F>| {
+        if target_stats.is_some() {
+            mean_r_squared(&output.mapv(F::widen), &y.mapv(F::widen))
+        } else {
+            nn.accuracy(y, output)
+        }
+    };
 
     // Initialize network
-    let mut nn = NeuralNetwork::new(5, 10, 10, 10, 3);
-    let mut history = TrainingHistory::default();
+    let architecture = &config.architecture;
+    let mut nn = NeuralNetwork::<F>::with_initializers(&config.layer_sizes(), architecture.activation, &config.initializers(), rng)
+        .with_output_activation(config.output_activation())
+        .with_regularization(architecture.batch_norm, config.regularization.dropout);
+    if let Some(source) = &source {
+        nn.warm_start(&source.network)?;
+    }
+    let mut history = TrainingHistory { regression: target_stats.is_some(), ..Default::default() };
 
-    // Training parameters
-    let initial_learning_rate = 0.001;
-    let lambda = 0.01;
+    let rows = x_train.nrows();
+    let batch_size = match config.optimizer.batch_size {
+        0 => rows,
+        size => size.min(rows),
+    };
+    let mut order: Vec<usize> = (0..rows).collect();
+
+    // Batches are split into one gradient shard per thread, but never into
+    // shards so small that the threading overhead dominates
+    let pool = parallel::pool_with(config.threads)?;
+    let threads = pool.current_num_threads();
+    // Returns the gradient norm before clipping, or None without touching the
+    // network if the gradients are not finite
+    let optimizer = &config.optimizer;
+    let step = |nn: &mut NeuralNetwork<F>, x: &Array2<F>, y: &Array2<F>, learning_rate: f64, rng: &mut R| {
+        let shards = (x.nrows() / MIN_SHARD_ROWS).clamp(1, threads);
+        let gradients = if shards == 1 {
+            nn.gradients(x, y, &config.loss, rng)
+        } else {
+            pool.install(|| nn.gradients_sharded(x, y, &config.loss, shards, rng))
+        };
+        let (learning_rate, l2) = config.mean_loss_step(learning_rate, x.nrows());
+        let mut gradients = gradients.with_l2(nn, l2);
+        if !gradients.is_finite() {
+            return None;
+        }
+        let gradient_norm = gradients.norm();
+        if optimizer.clip_value > 0.0 {
+            gradients.clip_value(optimizer.clip_value);
+        }
+        if optimizer.clip_norm > 0.0 {
+            gradients.clip_norm(optimizer.clip_norm);
+        }
+        nn.descend(gradients, learning_rate);
+        Some(gradient_norm)
+    };
 
-    println!("Starting training with {} epochs...", epochs);
+    // Network to go back to when training diverges, refreshed at every
+    // evaluation with a finite loss
+    let divergence = &config.divergence;
+    let mut checkpoint = (divergence.policy != DivergencePolicy::Abort).then(|| nn.clone());
+    let mut recoveries = 0;
+    let mut lr_scale = 1.0;
+
+    if log_every > 0 {
+        println!("Starting training with {} epochs ({} training, {} validation rows)...",
+            epochs, x_train.nrows(), x_val.nrows());
+    }
+
+    let started = Instant::now();
 
     // Training loop
     for epoch in 0..epochs {
-        let learning_rate = initial_learning_rate * (1.0 / (1.0 + 0.1 * (epoch as f64)));
-        
-        // Forward and backward pass
-        nn.train(&x_normalized, &y, learning_rate, lambda);
-        
-        // Evaluate every 10 epochs
-        if epoch % 10 == 0 || epoch == epochs - 1 {
-            let (_, _, _, output) = nn.forward(&x_normalized);
-            let loss = nn.loss(&y, &output);
-            let accuracy = nn.accuracy(&y, &output);
-            
-            history.record(epoch, accuracy, loss);
-            
-            if epoch % 100 == 0 || epoch == epochs - 1 {
-                println!("Epoch {}/{} - loss: {:.4}, accuracy: {:.2}%", 
-                    epoch, epochs, loss, accuracy * 100.0);
+        let learning_rate = lr_scale * config.optimizer.learning_rate * (1.0 / (1.0 + config.schedule.lr_decay * (epoch as f64)));
+
+        // Forward and backward pass, over shuffled mini-batches if requested
+        let mut diverged = None;
+        let gradient_norm = if batch_size == rows {
+            step(&mut nn, &x_train, &y_train, learning_rate, rng).unwrap_or_else(|| {
+                diverged = Some("gradients");
+                f64::NAN
+            })
+        } else {
+            order.shuffle(rng);
+            let mut total = 0.0;
+            let mut count = 0;
+            for batch in order.chunks(batch_size) {
+                let x_batch = x_train.select(Axis(0), batch);
+                let y_batch = y_train.select(Axis(0), batch);
+                match step(&mut nn, &x_batch, &y_batch, learning_rate, rng) {
+                    Some(norm) => total += norm,
+                    None => {
+                        diverged = Some("gradients");
+                        break;
+                    }
+                }
+                count += 1;
+            }
+            total / count.max(1) as f64
+        };
+        if diverged.is_none() && !nn.is_finite() {
+            diverged = Some("weights");
+        }
+
+        // Evaluate every `eval_interval` epochs
+        let evaluation = (diverged.is_none() && (epoch % eval_interval == 0 || epoch == epochs - 1)).then(|| {
+            // The softmax does not change the arg max, so logits give the accuracy too
+            let output = nn.forward_logits(&x_train);
+            let loss = config.loss.value(&output, &y_train);
+            let accuracy = score(&nn, &y_train, &output);
+
+            let (val_loss, val_accuracy) = if x_val.nrows() > 0 {
+                let val_output = nn.forward_logits(&x_val);
+                (config.loss.value(&val_output, &y_val), score(&nn, &y_val, &val_output))
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            (loss, accuracy, val_loss, val_accuracy)
+        });
+        if let Some((loss, _, val_loss, _)) = evaluation
+            && (!loss.is_finite() || (x_val.nrows() > 0 && !val_loss.is_finite()))
+        {
+            diverged = Some("loss");
+        }
+
+        if let Some(reason) = diverged {
+            recoveries += 1;
+            let Some(good) = checkpoint.as_ref().filter(|_| recoveries <= divergence.max_recoveries) else {
+                return Err(Box::new(TrainingDiverged { epoch, reason }));
+            };
+            nn = good.clone();
+            if divergence.policy == DivergencePolicy::ReduceLr {
+                lr_scale *= divergence.lr_factor;
+            }
+            if log_every > 0 {
+                println!("Epoch {}: non-finite {}, rolled back to the last good network (learning rate x{})",
+                    epoch, reason, lr_scale);
+            }
+            continue;
+        }
+
+        if let Some((loss, accuracy, val_loss, val_accuracy)) = evaluation {
+            if let Some(checkpoint) = checkpoint.as_mut() {
+                checkpoint.clone_from(&nn);
+            }
+
+            if log_every > 0 && (epoch % log_every == 0 || epoch == epochs - 1) {
+                if target_stats.is_some() {
+                    println!("Epoch {}/{} - loss: {:.4}, r2: {:.4}, val_loss: {:.4}, val_r2: {:.4}",
+                        epoch, epochs, loss, accuracy, val_loss, val_accuracy);
+                } else {
+                    println!("Epoch {}/{} - loss: {:.4}, accuracy: {:.2}%, val_loss: {:.4}, val_accuracy: {:.2}%",
+                        epoch, epochs, loss, accuracy * 100.0, val_loss, val_accuracy * 100.0);
+                }
+            }
+
+            let elapsed = started.elapsed();
+            let remaining = elapsed.div_f64((epoch + 1) as f64).mul_f64((epochs - epoch - 1) as f64);
+
+            let metrics = EpochMetrics {
+                epoch,
+                total_epochs: epochs,
+                train_loss: loss,
+                train_accuracy: accuracy,
+                val_loss,
+                val_accuracy,
+                learning_rate,
+                elapsed,
+                remaining,
+                gradient_norm,
+            };
+            history.record(&metrics);
+            if on_progress(&metrics) == TrainingControl::Stop {
+                if log_every > 0 {
+                    println!("Training stopped at epoch {}", epoch);
+                }
+                return Err(Box::new(TrainingCancelled { epoch }));
             }
         }
     }
 
-    // Save training plot
-    crate::utils::plot::create_plot(&history, plot_path)?;
+    // Calculate final accuracy
+    let output = nn.forward(&x_train);
+    let final_accuracy = score(&nn, &y_train, &output);
 
-    Ok(TrainedModel {
+    let model = TrainedModel {
         network: nn,
         stats,
-    })
-}
\ No newline at end of file
+        target_stats,
+        final_accuracy,
+        config: config.clone(),
+        history,
+        profile: Some(InputProfile::fit(&config.input_names(), &split.x_train)),
+    };
+    Ok(model)
+}
+
+/// Loads the model named by `architecture.warm_start` and checks that it was
+/// trained on the same inputs and classes.
+fn warm_start_model<F: Float>(path: &str, config: &TrainingConfig) -> Result<TrainedModel<F>, Box<dyn Error>> {
+    let source = load_any_model(path)
+        .map_err(|e| format!("failed to load warm start model {}: {}", path, e))?
+        .cast::<F>();
+    if source.features() != config.data.features.as_slice() || source.outputs() != config.data.outputs()
+        || source.is_regression() != config.data.is_regression()
+    {
+        return Err(format!("warm start model {} was trained on different features or outputs", path).into());
+    }
+    Ok(source)
+}
@@ -0,0 +1,206 @@
+use crate::model::float::Float;
+use crate::model::network::PredictionResult;
+use crate::training::trainer::TrainedModel;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+/// Pollutants of the air quality index, bounded by `SUB_INDEX_RANGE`.
+pub const POLLUTANTS: [&str; 5] = ["pm10", "so2", "co", "o3", "no2"];
+
+/// Range of a pollutant reading. The shipped dataset records each pollutant
+/// as its ISPU (Indonesian air pollution standard index) sub-index, which
+/// runs from 0 to 500, not as a raw concentration. Models trained on
+/// concentrations in µg/m³ or ppm need other `ValidationRules::bounds`.
+pub const SUB_INDEX_RANGE: (f64, f64) = (0.0, 500.0);
+
+/// Standard deviations beyond the training range at which an input is
+/// flagged as out of distribution, unless asked otherwise.
+pub const DEFAULT_OOD_THRESHOLD: f64 = 1.0;
+
+/// Standard deviations either side of the mean standing in for the training
+/// range of models saved without an input profile.
+const FALLBACK_RANGE_STDS: f64 = 3.0;
+
+/// What is wrong with one input value.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputIssue {
+    NotFinite,
+    BelowMinimum,
+    AboveMaximum,
+    OutOfDistribution,
+}
+
+impl InputIssue {
+    /// Bit of the issue in the flags of the C prediction result.
+    pub fn flag(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+impl fmt::Display for InputIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            InputIssue::NotFinite => "not_finite",
+            InputIssue::BelowMinimum => "below_minimum",
+            InputIssue::AboveMaximum => "above_maximum",
+            InputIssue::OutOfDistribution => "out_of_distribution",
+        })
+    }
+}
+
+/// Physical bounds per input and how far outside the training range an input
+/// may lie before it is flagged.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ValidationRules {
+    /// Inclusive `(min, max)` per input name. Inputs without bounds are only
+    /// checked for finite values and their distance from the training range.
+    pub bounds: BTreeMap<String, (f64, f64)>,
+    /// Out-of-distribution score, in training standard deviations beyond the
+    /// training range, from which an input is flagged.
+    pub ood_threshold: f64,
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        ValidationRules {
+            bounds: POLLUTANTS.iter().map(|name| (name.to_string(), SUB_INDEX_RANGE)).collect(),
+            ood_threshold: DEFAULT_OOD_THRESHOLD,
+        }
+    }
+}
+
+/// One problem with one input of a reading.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InputWarning {
+    /// Position of the input in the reading.
+    pub input: usize,
+    pub feature: String,
+    pub value: f64,
+    pub issue: InputIssue,
+    /// The bound crossed, or the end of the training range for
+    /// `OutOfDistribution`.
+    pub limit: f64,
+}
+
+impl fmt::Display for InputWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.issue {
+            InputIssue::NotFinite => write!(f, "{} = {} is not a finite number", self.feature, self.value),
+            InputIssue::BelowMinimum => write!(f, "{} = {} is below the minimum of {}", self.feature, self.value, self.limit),
+            InputIssue::AboveMaximum => write!(f, "{} = {} is above the maximum of {}", self.feature, self.value, self.limit),
+            InputIssue::OutOfDistribution => write!(
+                f, "{} = {} is outside the training range, which ends at {}", self.feature, self.value, self.limit
+            ),
+        }
+    }
+}
+
+/// The checks of one reading.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InputValidation {
+    pub warnings: Vec<InputWarning>,
+    /// Distance of each input from the training range, in training standard
+    /// deviations: 0 inside the range, NaN for values that are not finite.
+    pub scores: Vec<f64>,
+    /// The largest score of the finite inputs.
+    pub ood_score: f64,
+}
+
+impl InputValidation {
+    pub fn is_valid(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Issue flags of every warning, combined.
+    pub fn flags(&self) -> u32 {
+        self.warnings.iter().fold(0, |flags, w| flags | w.issue.flag())
+    }
+
+    /// Issue flags of the warnings about input `input`.
+    pub fn input_flags(&self, input: usize) -> u32 {
+        self.warnings.iter().filter(|w| w.input == input).fold(0, |flags, w| flags | w.issue.flag())
+    }
+}
+
+/// A classification together with the checks of the reading it is for.
+#[derive(Debug)]
+pub struct CheckedPrediction {
+    pub prediction: PredictionResult,
+    pub validation: InputValidation,
+}
+
+impl<F: Float> TrainedModel<F> {
+    /// Training range and standard deviation of network input `input`: those
+    /// of the input profile, or mean ± 3 standard deviations for models
+    /// saved without one.
+    fn training_range(&self, input: usize) -> (f64, f64, f64) {
+        if let Some(feature) = self.profile.as_ref().and_then(|p| p.features.get(input))
+            && feature.count > 0
+        {
+            let (min, max) = (feature.percentiles[0], feature.percentiles[feature.percentiles.len() - 1]);
+            return (min, max, feature.std);
+        }
+        let (mean, std) = (self.stats.mean[input].widen(), self.stats.std[input].widen());
+        (mean - FALLBACK_RANGE_STDS * std, mean + FALLBACK_RANGE_STDS * std, std)
+    }
+
+    /// Checks one raw reading of the network inputs against physical bounds
+    /// and the training range.
+    pub fn validate_input(&self, reading: &[f64], rules: &ValidationRules) -> Result<InputValidation, String> {
+        let names = self.config.input_names();
+        if reading.len() != names.len() {
+            return Err(format!("expected {} inputs per reading, got {}", names.len(), reading.len()));
+        }
+
+        let mut warnings = Vec::new();
+        let mut scores = Vec::with_capacity(reading.len());
+        for (input, (name, &value)) in names.iter().zip(reading).enumerate() {
+            let mut warn = |issue, limit| {
+                warnings.push(InputWarning { input, feature: name.clone(), value, issue, limit });
+            };
+            if !value.is_finite() {
+                warn(InputIssue::NotFinite, f64::NAN);
+                scores.push(f64::NAN);
+                continue;
+            }
+            if let Some(&(min, max)) = rules.bounds.get(name) {
+                if value < min {
+                    warn(InputIssue::BelowMinimum, min);
+                } else if value > max {
+                    warn(InputIssue::AboveMaximum, max);
+                }
+            }
+
+            let (min, max, std) = self.training_range(input);
+            let distance = (min - value).max(value - max).max(0.0);
+            let score = if distance == 0.0 { 0.0 } else { distance / if std > 0.0 { std } else { 1.0 } };
+            if score >= rules.ood_threshold && score > 0.0 {
+                warn(InputIssue::OutOfDistribution, if value < min { min } else { max });
+            }
+            scores.push(score);
+        }
+        let ood_score = scores.iter().copied().filter(|s| s.is_finite()).fold(0.0, f64::max);
+        Ok(InputValidation { warnings, scores, ood_score })
+    }
+
+    /// Classifies one raw reading and reports what is suspicious about it.
+    /// Readings with values that are not finite get class -1.
+    pub fn predict_checked(&self, reading: &[f64], rules: &ValidationRules) -> Result<CheckedPrediction, Box<dyn Error>> {
+        if self.is_regression() {
+            return Err("the model is a regressor, not a classifier".into());
+        }
+        if self.config.time_series.is_some() {
+            return Err("the model forecasts from a time series and cannot classify single readings".into());
+        }
+        let validation = self.validate_input(reading, rules)?;
+        let mut prediction = self.network.predict(reading, &self.stats);
+        if validation.warnings.iter().any(|w| w.issue == InputIssue::NotFinite) {
+            prediction.class = -1;
+        }
+        Ok(CheckedPrediction { prediction, validation })
+    }
+}
@@ -0,0 +1,267 @@
+use crate::training::diagnostics::{Curve, Diagnostics};
+use crate::utils::plot::PlotFormat;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// The plots `write_diagnostic_plots` produces, one file each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticPlot {
+    ConfusionMatrix,
+    Roc,
+    PrecisionRecall,
+    Calibration,
+    Probabilities,
+}
+
+impl DiagnosticPlot {
+    pub const ALL: [DiagnosticPlot; 5] = [
+        DiagnosticPlot::ConfusionMatrix,
+        DiagnosticPlot::Roc,
+        DiagnosticPlot::PrecisionRecall,
+        DiagnosticPlot::Calibration,
+        DiagnosticPlot::Probabilities,
+    ];
+
+    /// File name of the plot, without extension.
+    pub fn name(self) -> &'static str {
+        match self {
+            DiagnosticPlot::ConfusionMatrix => "confusion_matrix",
+            DiagnosticPlot::Roc => "roc",
+            DiagnosticPlot::PrecisionRecall => "precision_recall",
+            DiagnosticPlot::Calibration => "calibration",
+            DiagnosticPlot::Probabilities => "probabilities",
+        }
+    }
+
+    fn size(self, diagnostics: &Diagnostics) -> (u32, u32) {
+        match self {
+            DiagnosticPlot::Probabilities => {
+                let (columns, rows) = grid(diagnostics.classes.len());
+                (400 * columns as u32, 80 + 300 * rows as u32)
+            }
+            _ => (800, 700),
+        }
+    }
+}
+
+/// Writes every diagnostic plot into `dir`, creating it if needed, and
+/// returns the paths written.
+pub fn write_diagnostic_plots(diagnostics: &Diagnostics, dir: impl AsRef<Path>, format: PlotFormat) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for plot in DiagnosticPlot::ALL {
+        let path = dir.join(format!("{}.{}", plot.name(), format.extension()));
+        save_diagnostic_plot(diagnostics, plot, &path)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Draws one plot; `.svg` paths are written as SVG, anything else as PNG.
+pub fn save_diagnostic_plot(diagnostics: &Diagnostics, plot: DiagnosticPlot, path: &Path) -> Result<(), Box<dyn Error>> {
+    let size = plot.size(diagnostics);
+    if PlotFormat::of_path(path) == PlotFormat::Svg {
+        draw(SVGBackend::new(path, size).into_drawing_area(), diagnostics, plot)
+    } else {
+        draw(BitMapBackend::new(path, size).into_drawing_area(), diagnostics, plot)
+    }
+}
+
+/// Draws one plot as an SVG document.
+pub fn diagnostic_svg(diagnostics: &Diagnostics, plot: DiagnosticPlot) -> Result<String, Box<dyn Error>> {
+    let mut svg = String::new();
+    draw(SVGBackend::with_string(&mut svg, plot.size(diagnostics)).into_drawing_area(), diagnostics, plot)?;
+    Ok(svg)
+}
+
+fn draw<DB: DrawingBackend>(root: DrawingArea<DB, Shift>, diagnostics: &Diagnostics, plot: DiagnosticPlot) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    match plot {
+        DiagnosticPlot::ConfusionMatrix => draw_confusion_matrix(&root, diagnostics)?,
+        DiagnosticPlot::Roc => draw_curves(&root, "ROC curves (one vs rest)", ("False positive rate", "True positive rate"), "AUC", &diagnostics.roc, true)?,
+        DiagnosticPlot::PrecisionRecall => draw_curves(&root, "Precision-recall curves (one vs rest)", ("Recall", "Precision"), "AP", &diagnostics.precision_recall, false)?,
+        DiagnosticPlot::Calibration => draw_calibration(&root, diagnostics)?,
+        DiagnosticPlot::Probabilities => draw_probabilities(&root, diagnostics)?,
+    }
+    root.present()?;
+    Ok(())
+}
+
+fn draw_confusion_matrix<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, diagnostics: &Diagnostics) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let classes = &diagnostics.classes;
+    let n = classes.len() as u32;
+    let root = root.titled("Confusion matrix", ("sans-serif", 28))?;
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(50)
+        .y_label_area_size(110)
+        // Integer ranges include their end, so this is one segment per class
+        .build_cartesian_2d((0..n.max(1) - 1).into_segmented(), (0..n.max(1) - 1).into_segmented())?;
+    // True classes run top to bottom, as in the printed matrix
+    let label = |value: &SegmentValue<u32>, flip: bool| match value {
+        SegmentValue::CenterOf(i) if *i < n => classes[if flip { (n - 1 - i) as usize } else { *i as usize }].clone(),
+        _ => String::new(),
+    };
+    chart.configure_mesh()
+        .disable_mesh()
+        .x_desc("Predicted")
+        .y_desc("True")
+        .x_label_formatter(&|v| label(v, false))
+        .y_label_formatter(&|v| label(v, true))
+        .draw()?;
+
+    for (truth, row) in diagnostics.confusion_matrix.iter().enumerate() {
+        let total = row.iter().sum::<usize>().max(1) as f64;
+        let y = n - 1 - truth as u32;
+        for (predicted, &count) in row.iter().enumerate() {
+            // Colour by the share of the true class, so rare classes stay visible
+            let share = count as f64 / total;
+            let x = predicted as u32;
+            chart.draw_series(std::iter::once(Rectangle::new(
+                [(SegmentValue::Exact(x), SegmentValue::Exact(y)), (SegmentValue::Exact(x + 1), SegmentValue::Exact(y + 1))],
+                heat(share).filled(),
+            )))?;
+            let color = if share > 0.5 { WHITE } else { BLACK };
+            chart.draw_series(std::iter::once(Text::new(
+                format!("{} ({:.0}%)", count, share * 100.0),
+                (SegmentValue::CenterOf(x), SegmentValue::CenterOf(y)),
+                ("sans-serif", 18).into_font().color(&color).pos(Pos::new(HPos::Center, VPos::Center)),
+            )))?;
+        }
+    }
+    Ok(())
+}
+
+/// White to dark blue.
+fn heat(share: f64) -> RGBColor {
+    let share = share.clamp(0.0, 1.0);
+    let channel = |to: f64| (255.0 - (255.0 - to) * share) as u8;
+    RGBColor(channel(20.0), channel(70.0), channel(160.0))
+}
+
+fn draw_curves<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    (x_desc, y_desc): (&str, &str),
+    area: &str,
+    curves: &[Curve],
+    diagonal: bool,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let root = root.titled(title, ("sans-serif", 28))?;
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(45)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..1.0, 0.0..1.02)?;
+    chart.configure_mesh().x_desc(x_desc).y_desc(y_desc).draw()?;
+    if diagonal {
+        chart.draw_series(LineSeries::new([(0.0, 0.0), (1.0, 1.0)], BLACK.mix(0.3)))?;
+    }
+    for (i, curve) in curves.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        chart.draw_series(LineSeries::new(curve.points.iter().copied(), color.stroke_width(2)))?
+            .label(format!("{} ({} {:.3})", curve.class, area, curve.area))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+    }
+    chart.configure_series_labels()
+        .position(if diagonal { SeriesLabelPosition::LowerRight } else { SeriesLabelPosition::LowerLeft })
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+fn draw_calibration<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, diagnostics: &Diagnostics) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let title = format!("Reliability diagram (ECE {:.3})", diagnostics.expected_calibration_error);
+    let root = root.titled(&title, ("sans-serif", 28))?;
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(45)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..1.0, 0.0..1.02)?;
+    chart.configure_mesh().x_desc("Predicted probability of the top class").y_desc("Observed accuracy").draw()?;
+
+    let filled: Vec<_> = diagnostics.calibration.iter().filter(|bin| bin.count > 0).collect();
+    chart.draw_series(filled.iter().map(|bin| {
+        Rectangle::new([(bin.lower, 0.0), (bin.upper, bin.accuracy)], BLUE.mix(0.25).filled())
+    }))?
+        .label("Accuracy per bin")
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], BLUE.mix(0.25).filled()));
+    chart.draw_series(LineSeries::new([(0.0, 0.0), (1.0, 1.0)], BLACK.mix(0.4)))?
+        .label("Perfect calibration")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK.mix(0.4)));
+    chart.draw_series(LineSeries::new(filled.iter().map(|bin| (bin.confidence, bin.accuracy)), RED.stroke_width(2))
+        .point_size(4))?
+        .label("Model")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED.stroke_width(2)));
+    chart.configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+/// Columns and rows of a grid with one cell per class.
+fn grid(classes: usize) -> (usize, usize) {
+    let columns = classes.clamp(1, 3);
+    (columns, classes.div_ceil(columns).max(1))
+}
+
+fn draw_probabilities<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, diagnostics: &Diagnostics) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let root = root.titled("Predicted probabilities per class", ("sans-serif", 28))?;
+    let (columns, rows) = grid(diagnostics.histograms.len());
+    let cells = root.split_evenly((rows, columns));
+    for (cell, histogram) in cells.iter().zip(&diagnostics.histograms) {
+        // Each group is scaled to its own size, so that a rare class is
+        // as visible as the rest
+        let share = |counts: &[usize]| {
+            let total = counts.iter().sum::<usize>().max(1) as f64;
+            counts.iter().map(|&c| c as f64 / total).collect::<Vec<_>>()
+        };
+        let positives = share(&histogram.positives);
+        let negatives = share(&histogram.negatives);
+        let highest = positives.iter().chain(&negatives).copied().fold(0.0, f64::max).max(0.05);
+        let width = 1.0 / positives.len().max(1) as f64;
+
+        let mut chart = ChartBuilder::on(cell)
+            .caption(&histogram.class, ("sans-serif", 20))
+            .margin(10)
+            .x_label_area_size(35)
+            .y_label_area_size(45)
+            .build_cartesian_2d(0.0..1.0, 0.0..highest * 1.05)?;
+        chart.configure_mesh().x_desc(format!("P({})", histogram.class)).y_desc("Share").draw()?;
+        for (values, label, color) in [(&negatives, "Other classes", RED), (&positives, "This class", BLUE)] {
+            chart.draw_series(values.iter().enumerate().filter(|(_, v)| **v > 0.0).map(|(i, &v)| {
+                Rectangle::new([(i as f64 * width, 0.0), ((i + 1) as f64 * width, v)], color.mix(0.4).filled())
+            }))?
+                .label(label)
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.mix(0.4).filled()));
+        }
+        chart.configure_series_labels()
+            .position(SeriesLabelPosition::UpperMiddle)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+    Ok(())
+}
@@ -1,16 +1,20 @@
 use std::io::{self, Write};
 
-pub fn get_input(prompt: &str) -> f64 {
+/// Prompts until a number is entered. Fails once stdin is closed, so that
+/// callers without a terminal do not loop forever.
+pub fn get_input(prompt: &str) -> io::Result<f64> {
     loop {
         print!("{}", prompt);
-        io::stdout().flush().unwrap();
+        io::stdout().flush()?;
         
         let mut input = String::new();
-        io::stdin().read_line(&mut input).expect("Failed to read input");
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stdin closed before a number was entered"));
+        }
         
         match input.trim().parse() {
-            Ok(num) => return num,
+            Ok(num) => return Ok(num),
             Err(_) => println!("Please enter a valid number"),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,14 +1,130 @@
-use crate::training::trainer::TrainedModel;
+use crate::model::float::Float;
+use crate::training::trainer::{AnyModel, TrainedModel};
 use std::error::Error;
 
-pub fn save_model(path: &str, model: &TrainedModel) -> Result<(), Box<dyn Error>> {
-    let model_data = bincode::serialize(model)?;
-    std::fs::write(path, model_data)?;
+/// First bytes of every model file written since the format was versioned.
+const MAGIC: &[u8; 8] = b"AQMODEL\0";
+
+/// Layout of the model following the header. Bump it whenever a field of
+/// `TrainedModel` or the network changes, and keep a loader for the old one.
+pub const MODEL_FORMAT_VERSION: u32 = 1;
+
+pub fn save_model<F: Float>(path: &str, model: &TrainedModel<F>) -> Result<(), Box<dyn Error>> {
+    save_any_model(path, &AnyModel::new(model))
+}
+
+/// Writes `model` to `path`, refusing models with NaN or infinite parameters.
+pub fn save_any_model(path: &str, model: &AnyModel) -> Result<(), Box<dyn Error>> {
+    if !model.is_finite() {
+        return Err(format!("refusing to save {}: the model has non-finite parameters", path).into());
+    }
+    std::fs::write(path, encode_model(model)?)?;
     Ok(())
 }
 
+/// Loads a model of either precision, converted to `f64`.
 pub fn load_model(path: &str) -> Result<TrainedModel, Box<dyn Error>> {
-    let model_data = std::fs::read(path)?;
-    let model = bincode::deserialize(&model_data)?;
-    Ok(model)
-}
\ No newline at end of file
+    Ok(load_any_model(path)?.cast())
+}
+
+/// Loads a model in the precision it was saved with.
+pub fn load_any_model(path: &str) -> Result<AnyModel, Box<dyn Error>> {
+    decode_model(&std::fs::read(path)?)
+}
+
+/// The bytes of a model file: the header, then the model in bincode.
+pub fn encode_model(model: &AnyModel) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut data = MAGIC.to_vec();
+    data.extend(MODEL_FORMAT_VERSION.to_le_bytes());
+    data.extend(bincode::serialize(model)?);
+    Ok(data)
+}
+
+/// Reads the bytes of a model file of any supported format version.
+pub fn decode_model(data: &[u8]) -> Result<AnyModel, Box<dyn Error>> {
+    let Some(body) = data.strip_prefix(MAGIC) else {
+        return legacy::decode(data);
+    };
+    let (version, model) = body.split_at_checked(4).ok_or("truncated model file header")?;
+    match u32::from_le_bytes(version.try_into().unwrap()) {
+        MODEL_FORMAT_VERSION => Ok(bincode::deserialize(model)?),
+        version => Err(format!(
+            "unsupported model format version {} (this build reads versions up to {})", version, MODEL_FORMAT_VERSION
+        ).into()),
+    }
+}
+
+/// Files written before the header, which hold a bare `AnyModel`.
+mod legacy {
+    use crate::data::preprocessing::DataStats;
+    use crate::model::float::Float;
+    use crate::model::network::NeuralNetwork;
+    use crate::training::config::TrainingConfig;
+    use crate::training::history::TrainingHistory;
+    use crate::training::trainer::{embedded_config, AnyModel, TrainedModel};
+    use serde::Deserialize;
+    use std::error::Error;
+
+    #[derive(Deserialize)]
+    enum Precisions<A, B> {
+        F32(A),
+        F64(B),
+    }
+
+    /// Models saved before input profiles were recorded.
+    #[derive(Deserialize)]
+    #[serde(bound = "F: Float")]
+    struct WithHistory<F: Float> {
+        network: NeuralNetwork<F>,
+        stats: DataStats<F>,
+        target_stats: Option<DataStats<F>>,
+        final_accuracy: f64,
+        #[serde(with = "embedded_config")]
+        config: TrainingConfig,
+        history: TrainingHistory,
+    }
+
+    /// Models saved before the training history was recorded.
+    #[derive(Deserialize)]
+    #[serde(bound = "F: Float")]
+    struct WithoutHistory<F: Float> {
+        network: NeuralNetwork<F>,
+        stats: DataStats<F>,
+        target_stats: Option<DataStats<F>>,
+        final_accuracy: f64,
+        #[serde(with = "embedded_config")]
+        config: TrainingConfig,
+    }
+
+    impl<F: Float> From<WithoutHistory<F>> for WithHistory<F> {
+        fn from(m: WithoutHistory<F>) -> Self {
+            let WithoutHistory { network, stats, target_stats, final_accuracy, config } = m;
+            WithHistory { network, stats, target_stats, final_accuracy, config, history: TrainingHistory::default() }
+        }
+    }
+
+    impl<F: Float> From<WithHistory<F>> for TrainedModel<F> {
+        fn from(m: WithHistory<F>) -> Self {
+            let WithHistory { network, stats, target_stats, final_accuracy, config, history } = m;
+            TrainedModel { network, stats, target_stats, final_accuracy, config, history, profile: None }
+        }
+    }
+
+    pub fn decode(data: &[u8]) -> Result<AnyModel, Box<dyn Error>> {
+        // bincode ignores trailing bytes, so the longer layout goes first
+        let model = if let Ok(model) = bincode::deserialize::<Precisions<WithHistory<f32>, WithHistory<f64>>>(data) {
+            model
+        } else if let Ok(model) = bincode::deserialize::<Precisions<WithoutHistory<f32>, WithoutHistory<f64>>>(data) {
+            match model {
+                Precisions::F32(m) => Precisions::F32(m.into()),
+                Precisions::F64(m) => Precisions::F64(m.into()),
+            }
+        } else {
+            return Err("unsupported model format: not a model file, or saved by a release too old to read; retrain the model".into());
+        };
+        Ok(match model {
+            Precisions::F32(m) => AnyModel::F32(m.into()),
+            Precisions::F64(m) => AnyModel::F64(m.into()),
+        })
+    }
+}
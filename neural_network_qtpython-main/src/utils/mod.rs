@@ -1,3 +1,6 @@
 pub mod io;
 pub mod plot;
-pub mod input;
\ No newline at end of file
+pub mod diagnostic_plots;
+pub mod report;
+pub mod input;
+pub mod parallel;
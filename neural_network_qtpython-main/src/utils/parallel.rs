@@ -0,0 +1,62 @@
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Most threads a pool may have. Far larger counts are typos, and starting
+/// them can take the process down.
+pub const MAX_THREADS: usize = 1024;
+
+/// Pool used for batch inference and for training runs that do not set
+/// their own thread count, with the number of threads it has.
+static SHARED_POOL: Mutex<Option<(usize, Arc<ThreadPool>)>> = Mutex::new(None);
+
+/// Pools of other sizes, kept so that repeated runs with the same thread
+/// count (every trial of a search, say) do not start new threads each time.
+static POOLS: Mutex<BTreeMap<usize, Arc<ThreadPool>>> = Mutex::new(BTreeMap::new());
+
+/// Sets the number of threads used by batch inference and by training runs
+/// with `threads = 0`. 0 uses every core.
+pub fn set_threads(threads: usize) -> Result<(), rayon::ThreadPoolBuildError> {
+    let pool = build(threads)?;
+    *SHARED_POOL.lock().unwrap() = Some((pool.current_num_threads(), pool));
+    Ok(())
+}
+
+/// Number of threads in the shared pool.
+pub fn threads() -> usize {
+    pool().current_num_threads()
+}
+
+/// The shared pool, created with every core on first use.
+pub fn pool() -> Arc<ThreadPool> {
+    let mut shared = SHARED_POOL.lock().unwrap();
+    let (_, pool) = shared.get_or_insert_with(|| {
+        let pool = build(0).expect("failed to start thread pool");
+        (pool.current_num_threads(), pool)
+    });
+    Arc::clone(pool)
+}
+
+/// A pool with `threads` threads: the shared pool for 0 or its own size,
+/// otherwise one built on first use and reused afterwards.
+pub fn pool_with(threads: usize) -> Result<Arc<ThreadPool>, rayon::ThreadPoolBuildError> {
+    let shared = pool();
+    if threads == 0 || threads == shared.current_num_threads() {
+        return Ok(shared);
+    }
+    let mut pools = POOLS.lock().unwrap();
+    if let Some(pool) = pools.get(&threads) {
+        return Ok(Arc::clone(pool));
+    }
+    let pool = build(threads)?;
+    pools.insert(threads, Arc::clone(&pool));
+    Ok(pool)
+}
+
+fn build(threads: usize) -> Result<Arc<ThreadPool>, rayon::ThreadPoolBuildError> {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("airquality-worker-{}", i))
+        .build()
+        .map(Arc::new)
+}
@@ -1,47 +1,195 @@
 use crate::training::history::TrainingHistory;
+use plotters::coord::Shift;
 use plotters::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlotFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl PlotFormat {
+    /// SVG for `.svg` paths, PNG for anything else.
+    pub fn of_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("svg") => PlotFormat::Svg,
+            _ => PlotFormat::Png,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            PlotFormat::Png => "png",
+            PlotFormat::Svg => "svg",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlotOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Plot the losses on a logarithmic axis.
+    pub log_loss: bool,
+    /// Plot the learning rate on a logarithmic axis.
+    pub log_learning_rate: bool,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        PlotOptions { width: 1000, height: 800, log_loss: false, log_learning_rate: false }
+    }
+}
 
 pub fn create_plot(history: &TrainingHistory, path: &str) -> Result<(), Box<dyn Error>> {
-    let root = BitMapBackend::new(path, (800, 600)).into_drawing_area();
+    create_plot_with(history, path, &PlotOptions::default())
+}
+
+/// Draws a loss panel (with the learning rate on a secondary axis) above an
+/// accuracy panel, each with training and validation curves. Paths ending in
+/// `.svg` are written as SVG, anything else as PNG.
+pub fn create_plot_with(history: &TrainingHistory, path: &str, options: &PlotOptions) -> Result<(), Box<dyn Error>> {
+    let size = (options.width, options.height);
+    if PlotFormat::of_path(path) == PlotFormat::Svg {
+        draw(SVGBackend::new(path, size).into_drawing_area(), history, options)
+    } else {
+        draw(BitMapBackend::new(path, size).into_drawing_area(), history, options)
+    }
+}
+
+/// The training plot as an SVG document.
+pub fn training_plot_svg(history: &TrainingHistory, options: &PlotOptions) -> Result<String, Box<dyn Error>> {
+    let mut svg = String::new();
+    draw(SVGBackend::with_string(&mut svg, (options.width, options.height)).into_drawing_area(), history, options)?;
+    Ok(svg)
+}
+
+const ORANGE: RGBColor = RGBColor(230, 120, 0);
+
+fn draw<DB: DrawingBackend>(root: DrawingArea<DB, Shift>, history: &TrainingHistory, options: &PlotOptions) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
     root.fill(&WHITE)?;
+    let root = root.titled("Training Progress", ("sans-serif", 28))?;
+    let (top, bottom) = root.split_vertically(root.dim_in_pixel().1 / 2);
+    let epochs = 0..history.epochs.last().copied().unwrap_or(0).max(1) as u32;
 
-    let max_epoch = *history.epochs.last().unwrap_or(&1) as u32;
-    let max_loss = history.losses.iter().cloned().fold(f64::NAN, f64::max);
+    // Logarithmic axes plot log10 of the values and label them back
+    let loss_scale = Scale::new(options.log_loss);
+    let lr_scale = Scale::new(options.log_learning_rate);
+    let losses = loss_scale.range(history.losses.iter().chain(&history.val_losses));
+    let rates = lr_scale.range(history.learning_rates.iter());
 
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Training Progress", ("sans-serif", 30))
+    let mut loss_chart = ChartBuilder::on(&top)
         .margin(10)
-        .x_label_area_size(40)
-        .y_label_area_size(40)
-        .build_cartesian_2d(0u32..max_epoch, 0f64..1f64)?;
-
-    chart.configure_mesh()
-        .disable_x_mesh()
-        .disable_y_mesh()
+        .x_label_area_size(35)
+        .y_label_area_size(60)
+        .right_y_label_area_size(60)
+        .build_cartesian_2d(epochs.clone(), losses)?
+        .set_secondary_coord(epochs.clone(), rates);
+    loss_chart.configure_mesh()
         .x_desc("Epoch")
-        .y_desc("Value")
+        .y_desc(if options.log_loss { "Loss (log)" } else { "Loss" })
+        .y_label_formatter(&|v| loss_scale.label(*v))
+        .draw()?;
+    loss_chart.configure_secondary_axes()
+        .y_desc(if options.log_learning_rate { "Learning rate (log)" } else { "Learning rate" })
+        .y_label_formatter(&|v| lr_scale.label(*v))
+        .draw()?;
+    for (values, label, color) in [(&history.losses, "Train loss", BLUE), (&history.val_losses, "Validation loss", ORANGE)] {
+        loss_chart.draw_series(LineSeries::new(points(&history.epochs, values, loss_scale), color.stroke_width(2)))?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+    }
+    loss_chart.draw_secondary_series(LineSeries::new(points(&history.epochs, &history.learning_rates, lr_scale), BLACK.mix(0.4)))?
+        .label("Learning rate")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK.mix(0.4)));
+    loss_chart.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()?;
 
-    // Plot accuracy
-    chart.draw_series(LineSeries::new(
-        history.epochs.iter().zip(history.accuracies.iter()).map(|(&x, &y)| (x as u32, y)),
-        &RED,
-    ))?.label("Accuracy")
-    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
-
-    // Plot scaled loss
-    let loss_scale = 1.0 / max_loss;
-    chart.draw_series(LineSeries::new(
-        history.epochs.iter().zip(history.losses.iter()).map(|(&x, &y)| (x as u32, y * loss_scale)),
-        &BLUE,
-    ))?.label("Loss (scaled)")
-    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
-
-    chart.configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+    // R² of a regressor can start far below 0, so its axis stops at -1
+    let score = if history.regression { "R²" } else { "Accuracy" };
+    let lowest = history.accuracies.iter().chain(&history.val_accuracies)
+        .copied()
+        .filter(|v| v.is_finite())
+        .fold(0.0, f64::min)
+        .max(-1.0);
+    let mut score_chart = ChartBuilder::on(&bottom)
+        .margin(10)
+        .x_label_area_size(35)
+        .y_label_area_size(60)
+        .right_y_label_area_size(60)
+        .build_cartesian_2d(epochs, lowest..1.0)?;
+    score_chart.configure_mesh().x_desc("Epoch").y_desc(score).draw()?;
+    let clamp = |values: &[f64]| values.iter().map(|v| v.max(lowest)).collect::<Vec<_>>();
+    for (values, label, color) in [(&history.accuracies, "Train", RED), (&history.val_accuracies, "Validation", GREEN)] {
+        let values = clamp(values);
+        score_chart.draw_series(LineSeries::new(points(&history.epochs, &values, Scale::new(false)), color.stroke_width(2)))?
+            .label(format!("{} {}", label, score.to_lowercase()))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+    }
+    score_chart.configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .draw()?;
 
+    root.present()?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The finite points of a curve, on the scale of its axis.
+fn points<'a>(epochs: &'a [usize], values: &'a [f64], scale: Scale) -> impl Iterator<Item = (u32, f64)> + 'a {
+    epochs.iter()
+        .zip(values)
+        .filter_map(move |(&epoch, &value)| scale.apply(value).map(|v| (epoch as u32, v)))
+}
+
+#[derive(Clone, Copy)]
+struct Scale {
+    log: bool,
+}
+
+impl Scale {
+    fn new(log: bool) -> Self {
+        Scale { log }
+    }
+
+    /// The plotted value, or `None` for values the axis cannot show.
+    fn apply(self, value: f64) -> Option<f64> {
+        match self.log {
+            _ if !value.is_finite() => None,
+            true if value > 0.0 => Some(value.log10()),
+            true => None,
+            false => Some(value),
+        }
+    }
+
+    /// Axis range covering every plottable value, padded by 5%. Empty or
+    /// constant curves still get a non-empty range.
+    fn range<'a>(self, values: impl Iterator<Item = &'a f64>) -> Range<f64> {
+        let (low, high) = values.filter_map(|&v| self.apply(v))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), v| (low.min(v), high.max(v)));
+        if low > high {
+            return 0.0..1.0;
+        }
+        let low = if self.log { low } else { low.min(0.0) };
+        let pad = ((high - low) * 0.05).max(if self.log { 0.1 } else { 1e-3 });
+        (low - pad)..(high + pad)
+    }
+
+    fn label(self, value: f64) -> String {
+        if self.log { format!("{:.0e}", 10f64.powf(value)) } else { format!("{:.3}", value) }
+    }
+}
@@ -0,0 +1,266 @@
+use crate::data::time_series::format_timestamp;
+use crate::model::float::Float;
+use crate::training::diagnostics::{diagnose, Diagnostics};
+use crate::training::evaluation::{argmax, evaluate, evaluate_regression};
+use crate::training::importance::{permutation_importance, FeatureImportance};
+use crate::training::trainer::{training_rng, TrainedModel};
+use crate::utils::diagnostic_plots::{diagnostic_svg, DiagnosticPlot};
+use crate::utils::plot::training_plot_svg;
+use ndarray::{Array2, Axis};
+use plotters::prelude::*;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Shuffles per input when measuring permutation importance.
+const IMPORTANCE_REPEATS: usize = 5;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:1100px;color:#222}\
+h1{color:#0d47a1}h2{border-bottom:2px solid #0d47a1;padding-bottom:4px;margin-top:2em}\
+table{border-collapse:collapse;margin:1em 0}th,td{border:1px solid #ccc;padding:4px 10px;text-align:right}\
+th{background:#e3eafc}td:first-child,th:first-child{text-align:left}\
+pre{background:#f5f5f5;padding:1em;overflow-x:auto}svg{max-width:100%;height:auto}.meta{color:#666}";
+
+/// Renders a self-contained HTML report of `model` on the raw inputs `x` and
+/// one-hot labels or targets `y`, read from `source`. Every chart is an
+/// embedded SVG, so the file can be archived and opened offline.
+pub fn render_report<F: Float>(
+    model: &TrainedModel<F>,
+    title: &str,
+    source: &str,
+    x: &Array2<f64>,
+    y: &Array2<f64>,
+) -> Result<String, Box<dyn Error>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>{}</title>\n<style>{}</style>\n</head>\n<body>", escape(title), STYLE)?;
+    writeln!(html, "<h1>{}</h1>", escape(title))?;
+    writeln!(html, "<p class=\"meta\">Generated {} UTC from {} ({} samples)</p>",
+        format_timestamp(now), escape(source), x.nrows())?;
+
+    model_section(&mut html, model)?;
+    dataset_section(&mut html, model, x, y)?;
+
+    html.push_str("<h2>Training curves</h2>\n");
+    if model.history.is_empty() {
+        html.push_str("<p>The model stores no training history.</p>\n");
+    } else {
+        html.push_str(&training_plot_svg(&model.history, &model.config.output.plot_options)?);
+    }
+
+    if model.is_regression() {
+        regression_section(&mut html, model, x, y)?;
+    } else {
+        classification_section(&mut html, model, x, y)?;
+    }
+
+    let mut rng = training_rng(model.config.seed.unwrap_or(0));
+    importance_section(&mut html, &permutation_importance(model, x, y, IMPORTANCE_REPEATS, &mut rng), model.is_regression())?;
+
+    // TOML cannot hold every value JSON can, such as seeds of 2^63 and above
+    let config = model.config.to_toml_string()
+        .or_else(|_| serde_json::to_string_pretty(&model.config))?;
+    writeln!(html, "<h2>Configuration</h2>\n<pre>{}</pre>", escape(&config))?;
+    html.push_str("</body>\n</html>\n");
+    Ok(html)
+}
+
+/// Writes the report of `render_report` to `path`.
+pub fn write_report<F: Float>(
+    path: &str,
+    model: &TrainedModel<F>,
+    title: &str,
+    source: &str,
+    x: &Array2<f64>,
+    y: &Array2<f64>,
+) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, render_report(model, title, source, x, y)?)?;
+    Ok(())
+}
+
+fn model_section<F: Float>(html: &mut String, model: &TrainedModel<F>) -> Result<(), Box<dyn Error>> {
+    let config = &model.config;
+    let kind = match (model.is_regression(), &config.time_series) {
+        (false, None) => "Classifier".to_string(),
+        (true, None) => "Regressor".to_string(),
+        (false, Some(series)) => format!("Classifier, forecasting {} steps ahead", series.horizon),
+        (true, Some(series)) => format!("Regressor, forecasting {} steps ahead", series.horizon),
+    };
+    let score = if model.is_regression() { "Final training R²" } else { "Final training accuracy" };
+    let rows = [
+        ("Type", kind),
+        ("Outputs", model.outputs().join(", ")),
+        ("Layer sizes", format!("{:?}", model.network.layer_sizes())),
+        ("Parameters", model.network.parameter_count().to_string()),
+        ("Precision", F::PRECISION.to_string()),
+        ("Seed", config.seed.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string())),
+        ("Threads", config.threads.to_string()),
+        ("Evaluation steps", model.history.len().to_string()),
+        (score, format!("{:.4}", model.final_accuracy)),
+    ];
+    html.push_str("<h2>Model</h2>\n<table>\n");
+    for (name, value) in rows {
+        writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, escape(&value))?;
+    }
+    html.push_str("</table>\n");
+    Ok(())
+}
+
+fn dataset_section<F: Float>(html: &mut String, model: &TrainedModel<F>, x: &Array2<f64>, y: &Array2<f64>) -> Result<(), Box<dyn Error>> {
+    html.push_str("<h2>Dataset</h2>\n");
+    summary_table(html, "input", &model.config.input_names(), x)?;
+
+    if model.is_regression() {
+        html.push_str("<h3>Targets</h3>\n");
+        return summary_table(html, "target", model.outputs(), y);
+    }
+
+    let counts: Vec<usize> = (0..y.ncols())
+        .map(|class| y.axis_iter(Axis(0)).filter(|row| argmax(*row) == class).count())
+        .collect();
+    let total = y.nrows().max(1) as f64;
+    html.push_str("<h3>Class distribution</h3>\n<table>\n<tr><th>class</th><th>samples</th><th>share</th></tr>\n");
+    for (class, &count) in model.classes().iter().zip(&counts) {
+        writeln!(html, "<tr><td>{}</td><td>{}</td><td>{:.1}%</td></tr>", escape(class), count, count as f64 / total * 100.0)?;
+    }
+    html.push_str("</table>\n");
+    let shares: Vec<f64> = counts.iter().map(|&c| c as f64 / total).collect();
+    html.push_str(&bar_chart_svg("Class distribution", "Share of samples", model.classes(), &shares)?);
+    Ok(())
+}
+
+fn summary_table(html: &mut String, label: &str, names: &[String], values: &Array2<f64>) -> Result<(), Box<dyn Error>> {
+    writeln!(html, "<table>\n<tr><th>{}</th><th>mean</th><th>std</th><th>min</th><th>max</th></tr>", label)?;
+    for (name, column) in names.iter().zip(values.axis_iter(Axis(1))) {
+        let min = column.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = column.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        writeln!(html, "<tr><td>{}</td><td>{:.4}</td><td>{:.4}</td><td>{:.4}</td><td>{:.4}</td></tr>",
+            escape(name), column.mean().unwrap_or(f64::NAN), column.std(0.0), min, max)?;
+    }
+    html.push_str("</table>\n");
+    Ok(())
+}
+
+fn classification_section<F: Float>(html: &mut String, model: &TrainedModel<F>, x: &Array2<f64>, y: &Array2<f64>) -> Result<(), Box<dyn Error>> {
+    let evaluation = evaluate(model, x, y);
+    let diagnostics = diagnose(model, x, y);
+    writeln!(html, "<h2>Metrics</h2>\n<table>\n<tr><th>accuracy</th><td>{:.4}</td></tr>", evaluation.accuracy)?;
+    writeln!(html, "<tr><th>loss</th><td>{:.4}</td></tr>", evaluation.loss)?;
+    writeln!(html, "<tr><th>expected calibration error</th><td>{:.4}</td></tr>\n</table>", diagnostics.expected_calibration_error)?;
+    per_class_table(html, &diagnostics)?;
+
+    html.push_str("<h2>Confusion matrix</h2>\n<p>Rows are true classes, columns are predicted classes.</p>\n<table>\n<tr><th></th>");
+    for class in &diagnostics.classes {
+        write!(html, "<th>{}</th>", escape(class))?;
+    }
+    html.push_str("</tr>\n");
+    for (class, row) in diagnostics.classes.iter().zip(&diagnostics.confusion_matrix) {
+        write!(html, "<tr><th>{}</th>", escape(class))?;
+        for count in row {
+            write!(html, "<td>{}</td>", count)?;
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html.push_str(&diagnostic_svg(&diagnostics, DiagnosticPlot::ConfusionMatrix)?);
+
+    html.push_str("<h2>Diagnostic plots</h2>\n");
+    for plot in [DiagnosticPlot::Roc, DiagnosticPlot::PrecisionRecall, DiagnosticPlot::Calibration, DiagnosticPlot::Probabilities] {
+        html.push_str(&diagnostic_svg(&diagnostics, plot)?);
+    }
+    Ok(())
+}
+
+fn per_class_table(html: &mut String, diagnostics: &Diagnostics) -> Result<(), Box<dyn Error>> {
+    html.push_str("<table>\n<tr><th>class</th><th>precision</th><th>recall</th><th>F1</th><th>support</th><th>ROC AUC</th><th>average precision</th></tr>\n");
+    let matrix = &diagnostics.confusion_matrix;
+    for (class, name) in diagnostics.classes.iter().enumerate() {
+        let correct = matrix[class][class] as f64;
+        let predicted: usize = matrix.iter().map(|row| row[class]).sum();
+        let support: usize = matrix[class].iter().sum();
+        let precision = correct / predicted as f64;
+        let recall = correct / support as f64;
+        let f1 = 2.0 * precision * recall / (precision + recall);
+        writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(name), number(precision), number(recall), number(f1), support,
+            number(diagnostics.roc[class].area), number(diagnostics.precision_recall[class].area))?;
+    }
+    html.push_str("</table>\n");
+    Ok(())
+}
+
+fn regression_section<F: Float>(html: &mut String, model: &TrainedModel<F>, x: &Array2<f64>, y: &Array2<f64>) -> Result<(), Box<dyn Error>> {
+    let evaluation = evaluate_regression(model, x, y);
+    html.push_str("<h2>Metrics</h2>\n<table>\n<tr><th>target</th><th>RMSE</th><th>MAE</th><th>R²</th></tr>\n");
+    for metrics in &evaluation.targets {
+        writeln!(html, "<tr><td>{}</td><td>{:.4}</td><td>{:.4}</td><td>{:.4}</td></tr>",
+            escape(&metrics.target), metrics.rmse, metrics.mae, metrics.r2)?;
+    }
+    html.push_str("</table>\n");
+    Ok(())
+}
+
+fn importance_section(html: &mut String, importances: &[FeatureImportance], regression: bool) -> Result<(), Box<dyn Error>> {
+    let score = if regression { "R²" } else { "accuracy" };
+    let mut sorted = importances.to_vec();
+    sorted.sort_by(|a, b| b.importance.total_cmp(&a.importance));
+    writeln!(html, "<h2>Feature importance</h2>\n<p>Drop in {} when one input is shuffled, over {} shuffles.</p>",
+        score, IMPORTANCE_REPEATS)?;
+    writeln!(html, "<table>\n<tr><th>input</th><th>importance</th><th>std</th></tr>")?;
+    for importance in &sorted {
+        writeln!(html, "<tr><td>{}</td><td>{:.4}</td><td>{:.4}</td></tr>", escape(&importance.feature), importance.importance, importance.std)?;
+    }
+    html.push_str("</table>\n");
+    let names: Vec<String> = sorted.iter().map(|i| i.feature.clone()).collect();
+    let values: Vec<f64> = sorted.iter().map(|i| i.importance).collect();
+    html.push_str(&bar_chart_svg("Permutation importance", &format!("Drop in {}", score), &names, &values)?);
+    Ok(())
+}
+
+/// Horizontal bars, first label at the top.
+fn bar_chart_svg(title: &str, x_desc: &str, labels: &[String], values: &[f64]) -> Result<String, Box<dyn Error>> {
+    let n = labels.len().max(1) as u32;
+    let low = values.iter().copied().filter(|v| v.is_finite()).fold(0.0, f64::min);
+    let high = values.iter().copied().filter(|v| v.is_finite()).fold(0.0, f64::max);
+    let pad = ((high - low) * 0.05).max(1e-3);
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, (800, 100 + 30 * n)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let root = root.titled(title, ("sans-serif", 22))?;
+        let mut chart = ChartBuilder::on(&root)
+            .margin(15)
+            .x_label_area_size(40)
+            .y_label_area_size(140)
+            .build_cartesian_2d((low - pad)..(high + pad), (0..n - 1).into_segmented())?;
+        chart.configure_mesh()
+            .disable_y_mesh()
+            .x_desc(x_desc)
+            .y_label_formatter(&|v| match v {
+                SegmentValue::CenterOf(i) if *i < n => labels.get((n - 1 - i) as usize).cloned().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .draw()?;
+        chart.draw_series(values.iter().enumerate().filter(|(_, v)| v.is_finite()).map(|(i, &v)| {
+            let row = n - 1 - i as u32;
+            let mut bar = Rectangle::new(
+                [(0.0, SegmentValue::Exact(row)), (v, SegmentValue::Exact(row + 1))],
+                RGBColor(13, 71, 161).mix(0.7).filled(),
+            );
+            bar.set_margin(4, 4, 0, 0);
+            bar
+        }))?;
+        root.present()?;
+    }
+    Ok(svg)
+}
+
+/// A metric, or "-" when it is undefined.
+fn number(value: f64) -> String {
+    if value.is_finite() { format!("{:.4}", value) } else { "-".to_string() }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,47 @@
+//! Fixtures shared by the integration tests. Each test crate uses a subset.
+#![allow(dead_code)]
+
+use airquality_predictor::training::config::TrainingConfig;
+use airquality_predictor::training::trainer::{
+    train_any_with_progress, train_model_with_progress, AnyModel, TrainedModel, TrainingControl,
+};
+use std::path::PathBuf;
+
+/// The readings shipped with the repository.
+pub const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/airquality.csv");
+
+/// `name` in the temp directory, unique to this test process.
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("airquality_{}_{}", std::process::id(), name))
+}
+
+/// A quiet config training on `DATA` from seed 4, plotting to a temp file
+/// named after `name`.
+pub fn config(name: &str, epochs: usize) -> TrainingConfig {
+    let mut config = TrainingConfig::default();
+    config.data.path = DATA.to_string();
+    config.schedule.epochs = epochs;
+    config.seed = Some(4);
+    config.output.log_every = 0;
+    config.output.plot = temp_path(&format!("{}.png", name)).to_string_lossy().into_owned();
+    config
+}
+
+/// Trains `config` and removes its plot.
+pub fn fit(config: &TrainingConfig) -> TrainedModel {
+    let model = train_model_with_progress(config, |_| TrainingControl::Continue).unwrap();
+    let _ = std::fs::remove_file(&config.output.plot);
+    model
+}
+
+/// Trains `config` in its configured precision and removes its plot.
+pub fn fit_any(config: &TrainingConfig) -> AnyModel {
+    let model = train_any_with_progress(config, |_| TrainingControl::Continue).unwrap();
+    let _ = std::fs::remove_file(&config.output.plot);
+    model
+}
+
+/// A model trained with `config(name, epochs)`.
+pub fn train(name: &str, epochs: usize) -> TrainedModel {
+    fit(&config(name, epochs))
+}
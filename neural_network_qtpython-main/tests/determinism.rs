@@ -0,0 +1,46 @@
+mod common;
+
+use airquality_predictor::training::trainer::TrainedModel;
+
+fn train(seed: Option<u64>, batch_size: usize, name: &str) -> TrainedModel {
+    train_with_threads(seed, batch_size, 1, name)
+}
+
+fn train_with_threads(seed: Option<u64>, batch_size: usize, threads: usize, name: &str) -> TrainedModel {
+    let mut config = common::config(name, 20);
+    config.optimizer.batch_size = batch_size;
+    config.seed = seed;
+    config.threads = threads;
+    common::fit(&config)
+}
+
+fn weights(model: &TrainedModel) -> Vec<u8> {
+    bincode::serialize(&(&model.network, &model.stats)).unwrap()
+}
+
+#[test]
+fn same_seed_gives_bit_identical_models() {
+    let first = train(Some(7), 256, "first");
+    let second = train(Some(7), 256, "second");
+    assert_eq!(weights(&first), weights(&second));
+    assert_eq!(first.final_accuracy.to_bits(), second.final_accuracy.to_bits());
+}
+
+#[test]
+fn different_seeds_give_different_models() {
+    assert_ne!(weights(&train(Some(1), 0, "seed1")), weights(&train(Some(2), 0, "seed2")));
+}
+
+#[test]
+fn unseeded_runs_record_a_reproducible_seed() {
+    let model = train(None, 0, "unseeded");
+    let seed = model.config.seed.expect("seed recorded in the model");
+    assert_eq!(weights(&model), weights(&train(Some(seed), 0, "replay")));
+}
+
+#[test]
+fn same_seed_and_thread_count_give_bit_identical_models() {
+    let first = train_with_threads(Some(11), 0, 4, "threads_first");
+    let second = train_with_threads(Some(11), 0, 4, "threads_second");
+    assert_eq!(weights(&first), weights(&second));
+}
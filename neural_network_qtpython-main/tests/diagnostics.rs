@@ -0,0 +1,83 @@
+use airquality_predictor::training::diagnostics::{precision_recall_curve, roc_curve, Diagnostics};
+use airquality_predictor::utils::diagnostic_plots::{diagnostic_svg, write_diagnostic_plots, DiagnosticPlot};
+use airquality_predictor::utils::plot::PlotFormat;
+use ndarray::{array, Array1};
+
+fn classes() -> Vec<String> {
+    ["BAIK", "SEDANG", "TIDAK SEHAT"].iter().map(|c| c.to_string()).collect()
+}
+
+fn diagnostics() -> Diagnostics {
+    let probabilities = array![
+        [0.8, 0.15, 0.05],
+        [0.6, 0.3, 0.1],
+        [0.2, 0.7, 0.1],
+        [0.1, 0.85, 0.05],
+        [0.3, 0.4, 0.3],
+        [0.05, 0.15, 0.8],
+    ];
+    let y = array![
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+        [0.0, 0.0, 1.0],
+    ];
+    Diagnostics::new(&classes(), &probabilities, &y)
+}
+
+#[test]
+fn roc_area_matches_ranking() {
+    let scores = Array1::from(vec![0.9, 0.8, 0.7, 0.6]);
+    let (_, perfect) = roc_curve(scores.view(), &[true, true, false, false]);
+    assert!((perfect - 1.0).abs() < 1e-12);
+    let (_, inverted) = roc_curve(scores.view(), &[false, false, true, true]);
+    assert!(inverted.abs() < 1e-12);
+    // Tied scores count as half right
+    let tied = Array1::from(vec![0.5, 0.5]);
+    let (points, area) = roc_curve(tied.view(), &[true, false]);
+    assert_eq!(points, vec![(0.0, 0.0), (1.0, 1.0)]);
+    assert!((area - 0.5).abs() < 1e-12);
+    // A class that never occurs has no curve to speak of
+    assert!(roc_curve(scores.view(), &[false; 4]).1.is_nan());
+}
+
+#[test]
+fn average_precision_of_a_perfect_ranking_is_one() {
+    let scores = Array1::from(vec![0.9, 0.8, 0.7, 0.6]);
+    let (points, area) = precision_recall_curve(scores.view(), &[true, true, false, false]);
+    assert_eq!(points.first(), Some(&(0.0, 1.0)));
+    assert!((area - 1.0).abs() < 1e-12);
+    let (_, area) = precision_recall_curve(scores.view(), &[false, true, false, true]);
+    assert!((area - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn diagnostics_count_every_sample() {
+    let diagnostics = diagnostics();
+    assert_eq!(diagnostics.confusion_matrix, vec![vec![1, 0, 0], vec![1, 2, 0], vec![0, 1, 1]]);
+    assert_eq!(diagnostics.calibration.iter().map(|bin| bin.count).sum::<usize>(), 6);
+    for histogram in &diagnostics.histograms {
+        assert_eq!(histogram.positives.iter().chain(&histogram.negatives).sum::<usize>(), 6);
+    }
+    assert!((0.0..=1.0).contains(&diagnostics.expected_calibration_error));
+    assert!(diagnostics.roc.iter().all(|curve| (0.0..=1.0).contains(&curve.area)));
+}
+
+#[test]
+fn plots_are_written_in_either_format() {
+    let dir = std::env::temp_dir().join(format!("airquality_diagnostics_{}", std::process::id()));
+    for format in [PlotFormat::Png, PlotFormat::Svg] {
+        let paths = write_diagnostic_plots(&diagnostics(), &dir, format).unwrap();
+        assert_eq!(paths.len(), DiagnosticPlot::ALL.len());
+        for path in &paths {
+            assert_eq!(path.extension().unwrap(), format.extension());
+            assert!(std::fs::metadata(path).unwrap().len() > 0);
+        }
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let svg = diagnostic_svg(&diagnostics(), DiagnosticPlot::ConfusionMatrix).unwrap();
+    assert!(svg.starts_with("<svg") && svg.contains("TIDAK SEHAT"));
+}
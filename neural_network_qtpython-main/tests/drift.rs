@@ -0,0 +1,108 @@
+mod common;
+
+use airquality_predictor::training::drift::{DriftMetric, DriftMonitor, DriftSeverity, DriftThresholds, FeatureProfile, InputProfile};
+use airquality_predictor::training::trainer::training_rng;
+use common::DATA;
+use ndarray::{Array1, Array2};
+use rand::Rng;
+
+fn names() -> Vec<String> {
+    vec!["a".to_string(), "b".to_string()]
+}
+
+/// Uniform readings in [0, 100) for `a` and [0, 10) for `b`.
+fn readings(rows: usize, seed: u64) -> Array2<f64> {
+    let mut rng = training_rng(seed);
+    Array2::from_shape_fn((rows, 2), |(_, j)| rng.gen_range(0.0..100.0) / if j == 0 { 1.0 } else { 10.0 })
+}
+
+#[test]
+fn profiles_summarise_the_training_distribution() {
+    let profile = FeatureProfile::fit("a", Array1::from_iter((0..=100).map(f64::from)).view());
+    assert_eq!((profile.count, profile.mean), (101, 50.0));
+    assert_eq!(profile.percentiles.len(), 101);
+    assert_eq!(profile.edges.len(), 9);
+    assert!((profile.frequencies.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    assert_eq!(profile.cdf(-1.0), 0.0);
+    assert!((profile.cdf(25.5) - 0.255).abs() < 1e-9);
+    assert_eq!(profile.cdf(100.0), 1.0);
+
+    // Constant inputs collapse to a single bin
+    let constant = FeatureProfile::fit("c", Array1::from_elem(20, 3.0).view());
+    assert!(constant.edges.len() <= 1 && constant.std == 0.0);
+}
+
+#[test]
+fn merged_profiles_match_a_profile_of_every_reading() {
+    let (old, new) = (Array1::from_iter((0..100).map(f64::from)), Array1::from_iter((100..300).map(f64::from)));
+    let merged = FeatureProfile::fit("a", old.view()).merge(new.view());
+    let all = FeatureProfile::fit("a", Array1::from_iter((0..300).map(f64::from)).view());
+    assert_eq!(merged.count, all.count);
+    assert!((merged.mean - all.mean).abs() < 1e-9 && (merged.std - all.std).abs() < 1e-9, "{:?}", merged);
+    assert_eq!((merged.percentiles[0], merged.percentiles[100]), (0.0, 299.0));
+    assert!((merged.percentiles[50] - all.percentiles[50]).abs() < 2.0, "{:?}", merged.percentiles);
+    assert!((merged.frequencies.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn shifted_inputs_raise_alerts_on_the_shifted_feature() {
+    let profile = InputProfile::fit(&names(), &readings(2000, 1));
+    let thresholds = DriftThresholds::default();
+    let same = profile.check(&readings(400, 2), &thresholds).unwrap();
+    assert_eq!(same.severity(), DriftSeverity::None, "{:?}", same.alerts);
+
+    let mut shifted = readings(400, 3);
+    shifted.column_mut(1).mapv_inplace(|v| v + 5.0);
+    let report = profile.check(&shifted, &thresholds).unwrap();
+    assert_eq!(report.severity(), DriftSeverity::Alert);
+    assert_eq!(report.features[0].severity, DriftSeverity::None);
+    assert!(report.features[1].mean_shift > 1.0);
+    assert!(report.alerts.iter().all(|a| a.feature == "b"));
+    assert!([DriftMetric::Psi, DriftMetric::Ks, DriftMetric::MeanShift]
+        .iter()
+        .all(|m| report.alerts.iter().any(|a| a.metric == *m)));
+    assert!(profile.check(&Array2::zeros((5, 3)), &thresholds).is_err());
+}
+
+#[test]
+fn small_batches_report_metrics_without_alerts() {
+    let profile = InputProfile::fit(&names(), &readings(2000, 4));
+    let shifted = readings(10, 5).mapv(|v| v + 50.0);
+    let report = profile.check(&shifted, &DriftThresholds::default()).unwrap();
+    assert_eq!(report.severity(), DriftSeverity::None);
+    assert!(report.features[0].ks > 0.2);
+    let eager = DriftThresholds { min_samples: 5, ..Default::default() };
+    assert_eq!(profile.check(&shifted, &eager).unwrap().severity(), DriftSeverity::Alert);
+}
+
+#[test]
+fn monitors_keep_the_latest_window() {
+    let profile = InputProfile::fit(&names(), &readings(2000, 6));
+    let mut monitor = DriftMonitor::new(profile, DriftThresholds::default(), 100);
+    assert!(monitor.is_empty());
+    monitor.observe(&readings(80, 7).mapv(|v| v + 50.0));
+    assert_eq!(monitor.report().severity(), DriftSeverity::Alert);
+    monitor.observe(&Array2::zeros((3, 5)));
+    assert_eq!(monitor.len(), 80);
+
+    // Fresh in-distribution readings push the shifted ones out
+    monitor.observe(&readings(100, 8));
+    assert_eq!(monitor.len(), 100);
+    let report = monitor.report();
+    assert!(report.severity() < DriftSeverity::Alert, "{:?}", report.alerts);
+    assert!(report.features.iter().all(|f| f.mean_shift.abs() < 0.5));
+}
+
+#[test]
+fn trained_models_carry_an_input_profile() {
+    let mut model = common::train("drift", 5);
+
+    let profile = model.profile.as_ref().unwrap();
+    let features: Vec<&str> = profile.features.iter().map(|f| f.feature.as_str()).collect();
+    assert_eq!(features, model.config.input_names());
+    let report = model.check_drift_csv(DATA, &DriftThresholds::default()).unwrap();
+    assert_eq!(report.severity(), DriftSeverity::None, "{:?}", report.alerts);
+
+    model.profile = None;
+    assert!(model.check_drift_csv(DATA, &DriftThresholds::default()).is_err());
+}
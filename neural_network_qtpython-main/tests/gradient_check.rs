@@ -0,0 +1,134 @@
+use airquality_predictor::model::gradcheck::check_gradients;
+use airquality_predictor::model::layers::{relu, relu_derivative, Activation, BatchNorm, Layer};
+use airquality_predictor::model::loss::Loss;
+use airquality_predictor::model::network::NeuralNetwork;
+use ndarray::Array2;
+use ndarray_rand::rand_distr::Normal;
+use ndarray_rand::RandomExt;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+const TOLERANCE: f64 = 1e-6;
+const EPSILON: f64 = 1e-6;
+
+fn batch(rows: usize, features: usize, classes: usize, rng: &mut ChaCha8Rng) -> (Array2<f64>, Array2<f64>) {
+    let x = Array2::random_using((rows, features), Normal::new(0.0, 1.0).unwrap(), rng);
+    let mut y = Array2::zeros((rows, classes));
+    for row in 0..rows {
+        y[[row, rng.gen_range(0..classes)]] = 1.0;
+    }
+    (x, y)
+}
+
+fn network(activation: Activation, rng: &mut ChaCha8Rng) -> NeuralNetwork {
+    NeuralNetwork::with_rng(&[4, 6, 5, 3], activation, rng)
+}
+
+fn assert_gradients_match(network: &NeuralNetwork, label: &str) {
+    assert_loss_gradients_match(network, &Loss::default(), label);
+}
+
+fn assert_loss_gradients_match(network: &NeuralNetwork, loss: &Loss, label: &str) {
+    let mut rng = ChaCha8Rng::seed_from_u64(17);
+    let (x, y) = batch(8, 4, 3, &mut rng);
+    let check = check_gradients(network, &x, &y, loss, EPSILON, 23);
+    assert!(check.passed(TOLERANCE), "{}: {:#?}", label, check.parameters);
+}
+
+#[test]
+fn dense_layers_match_finite_differences_for_every_activation() {
+    for activation in [Activation::Relu, Activation::LeakyRelu, Activation::Tanh, Activation::Sigmoid] {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        assert_gradients_match(&network(activation, &mut rng), &format!("{:?}", activation));
+    }
+}
+
+#[test]
+fn batch_norm_matches_finite_differences() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let mut network = network(Activation::Tanh, &mut rng).with_regularization(true, 0.0);
+    // Move gamma and beta away from their initial values
+    for layer in &mut network.layers {
+        if let Layer::BatchNorm(norm) = layer {
+            norm.gamma.mapv_inplace(|_| rng.gen_range(0.5..1.5));
+            norm.beta.mapv_inplace(|_| rng.gen_range(-0.5..0.5));
+        }
+    }
+    assert_gradients_match(&network, "batch norm");
+}
+
+#[test]
+fn batch_norm_at_the_input_matches_finite_differences() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let mut network = network(Activation::Sigmoid, &mut rng);
+    network.layers.insert(0, Layer::BatchNorm(BatchNorm::new(4)));
+    assert_gradients_match(&network, "input batch norm");
+}
+
+#[test]
+fn dropout_matches_finite_differences_with_fixed_masks() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let network = network(Activation::LeakyRelu, &mut rng).with_regularization(true, 0.3);
+    assert_gradients_match(&network, "dropout");
+}
+
+#[test]
+fn classification_losses_match_finite_differences() {
+    let losses = [
+        Loss::CrossEntropy { label_smoothing: 0.1, class_weights: vec![] },
+        Loss::CrossEntropy { label_smoothing: 0.0, class_weights: vec![1.0, 0.5, 3.0] },
+        Loss::Focal { gamma: 2.0, class_weights: vec![] },
+        Loss::Focal { gamma: 0.5, class_weights: vec![1.0, 1.0, 4.0] },
+    ];
+    for loss in losses {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        assert_loss_gradients_match(&network(Activation::Tanh, &mut rng), &loss, &format!("{:?}", loss));
+    }
+}
+
+#[test]
+fn regression_losses_match_finite_differences() {
+    for loss in [Loss::Mse, Loss::Mae, Loss::Huber { delta: 0.5 }] {
+        for head in [Activation::Tanh, Activation::Linear] {
+            let mut rng = ChaCha8Rng::seed_from_u64(8);
+            // A softmax head only pairs with classification losses
+            let network = network(Activation::Tanh, &mut rng).with_output_activation(head);
+            assert_loss_gradients_match(&network, &loss, &format!("{:?} with a {:?} head", loss, head));
+        }
+    }
+}
+
+#[test]
+fn gradients_are_averaged_over_the_batch() {
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let network = network(Activation::Tanh, &mut rng);
+    let (x, y) = batch(6, 4, 3, &mut rng);
+    let doubled = |a: &Array2<f64>| ndarray::concatenate(ndarray::Axis(0), &[a.view(), a.view()]).unwrap();
+
+    let single = network.gradients(&x, &y, &Loss::default(), &mut rng);
+    let twice = network.gradients(&doubled(&x), &doubled(&y), &Loss::default(), &mut rng);
+    for ((w1, b1), (w2, b2)) in single.layers.iter().zip(&twice.layers) {
+        assert!((w1 - w2).iter().all(|d| d.abs() < 1e-12));
+        assert!((b1 - b2).iter().all(|d| d.abs() < 1e-12));
+    }
+}
+
+#[test]
+fn sharded_steps_match_a_single_step() {
+    let mut rng = ChaCha8Rng::seed_from_u64(6);
+    let mut single = network(Activation::Relu, &mut rng);
+    let mut sharded = single.clone();
+    let (x, y) = batch(40, 4, 3, &mut rng);
+
+    single.train(&x, &y, &Loss::default(), 0.1, 0.01, &mut rng);
+    let gradients = sharded.gradients_sharded(&x, &y, &Loss::default(), 4, &mut rng);
+    sharded.apply_gradients(gradients, 0.1, 0.01);
+    let probe = batch(5, 4, 3, &mut rng).0;
+    assert!((single.forward(&probe) - sharded.forward(&probe)).iter().all(|d| d.abs() < 1e-12));
+}
+
+#[test]
+fn relu_derivative_is_the_same_for_inputs_and_outputs() {
+    let x = Array2::from_shape_vec((1, 5), vec![-2.0, -0.5, 0.0, 0.5, 2.0]).unwrap();
+    assert_eq!(relu_derivative(&x), relu_derivative(&relu(&x)));
+}
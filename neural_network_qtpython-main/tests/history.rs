@@ -0,0 +1,87 @@
+mod common;
+
+use airquality_predictor::training::history::TrainingHistory;
+use airquality_predictor::utils::plot::{create_plot_with, PlotOptions};
+use common::temp_path;
+
+fn history() -> TrainingHistory {
+    TrainingHistory {
+        epochs: vec![0, 10, 20],
+        accuracies: vec![0.5, 0.7, 0.8],
+        losses: vec![1.2, 0.6, 0.4],
+        val_accuracies: vec![0.45, 0.65, f64::NAN],
+        val_losses: vec![1.3, 0.7, f64::NAN],
+        learning_rates: vec![1.0, 0.5, 0.25],
+        gradient_norms: vec![2.0, 1.0, 0.5],
+        elapsed_secs: vec![0.1, 0.2, 0.3],
+        regression: false,
+    }
+}
+
+#[test]
+fn csv_leaves_missing_values_empty() {
+    let mut csv = Vec::new();
+    history().write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("epoch,train_loss,train_accuracy,val_loss,val_accuracy"));
+    assert_eq!(lines[3], "20,0.4,0.8,,,0.25,0.5,0.3");
+
+    let mut regression = history();
+    regression.regression = true;
+    let mut csv = Vec::new();
+    regression.write_csv(&mut csv).unwrap();
+    assert!(String::from_utf8(csv).unwrap().contains("train_r2"));
+}
+
+#[test]
+fn json_round_trips() {
+    let mut json = Vec::new();
+    history().write_json(&mut json).unwrap();
+    let restored: TrainingHistory = serde_json::from_slice(&json).unwrap();
+    assert_eq!(restored.epochs, vec![0, 10, 20]);
+    assert_eq!(restored.losses, history().losses);
+    assert_eq!(restored.val_losses[1], 0.7);
+    assert!(restored.val_losses[2].is_nan());
+}
+
+#[test]
+fn plots_are_written_as_png_and_svg() {
+    let options = PlotOptions { log_loss: true, log_learning_rate: true, ..PlotOptions::default() };
+    for name in ["plot.png", "plot.svg"] {
+        let path = temp_path(name);
+        create_plot_with(&history(), path.to_str().unwrap(), &options).unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() > 0);
+        if name.ends_with(".svg") {
+            assert!(std::fs::read_to_string(&path).unwrap().starts_with("<svg"));
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+}
+
+#[test]
+fn empty_history_still_plots() {
+    let path = temp_path("empty.svg");
+    create_plot_with(&TrainingHistory::default(), path.to_str().unwrap(), &PlotOptions::default()).unwrap();
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn trained_model_keeps_its_history() {
+    let mut config = common::config("trained", 30);
+    config.seed = Some(1);
+    config.output.log_every = 10;
+    let exported = temp_path("trained.json");
+    config.output.plot = temp_path("trained.svg").to_string_lossy().into_owned();
+    config.output.history = Some(exported.to_string_lossy().into_owned());
+
+    let model = common::fit(&config);
+    assert_eq!(model.history.epochs.len(), model.history.learning_rates.len());
+    assert_eq!(model.history.epochs.last(), Some(&29));
+    assert!(model.history.val_accuracies.iter().all(|v| v.is_finite()));
+
+    let saved: TrainingHistory = serde_json::from_str(&std::fs::read_to_string(&exported).unwrap()).unwrap();
+    assert_eq!(saved.epochs, model.history.epochs);
+    let _ = std::fs::remove_file(&exported);
+}
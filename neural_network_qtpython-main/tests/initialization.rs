@@ -0,0 +1,139 @@
+mod common;
+
+use airquality_predictor::model::init::Initializer;
+use airquality_predictor::model::layers::{Activation, DenseLayer, Layer};
+use airquality_predictor::model::network::NeuralNetwork;
+use airquality_predictor::training::config::TrainingConfig;
+use airquality_predictor::training::trainer::TrainedModel;
+use airquality_predictor::utils::io::save_model;
+use ndarray::Array2;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+fn draw(initializer: Initializer, fan_in: usize, fan_out: usize, activation: Activation) -> Array2<f64> {
+    initializer.weights(fan_in, fan_out, activation, &mut ChaCha8Rng::seed_from_u64(9))
+}
+
+fn std_dev(weights: &Array2<f64>) -> f64 {
+    let mean = weights.mean().unwrap();
+    (weights.mapv(|v| (v - mean).powi(2)).mean().unwrap()).sqrt()
+}
+
+#[test]
+fn random_initializers_have_the_documented_scale() {
+    let (fan_in, fan_out) = (200, 300);
+    let cases = [
+        (Initializer::HeNormal, (2.0 / 200.0f64).sqrt()),
+        (Initializer::HeUniform, (6.0 / 200.0f64).sqrt() / 3f64.sqrt()),
+        (Initializer::XavierNormal, (2.0 / 500.0f64).sqrt()),
+        (Initializer::XavierUniform, (6.0 / 500.0f64).sqrt() / 3f64.sqrt()),
+        (Initializer::LecunNormal, (1.0 / 200.0f64).sqrt()),
+        (Initializer::LecunUniform, (3.0 / 200.0f64).sqrt() / 3f64.sqrt()),
+    ];
+    for (initializer, expected) in cases {
+        let weights = draw(initializer, fan_in, fan_out, Activation::Relu);
+        assert_eq!(weights.dim(), (fan_in, fan_out));
+        let actual = std_dev(&weights);
+        assert!((actual / expected - 1.0).abs() < 0.03, "{:?}: std {} instead of {}", initializer, actual, expected);
+    }
+
+    let limit = (6.0 / 200.0f64).sqrt();
+    assert!(draw(Initializer::HeUniform, fan_in, fan_out, Activation::Relu).iter().all(|v| v.abs() <= limit));
+}
+
+#[test]
+fn constant_initializers_fill_the_weights() {
+    assert!(draw(Initializer::Zeros, 4, 3, Activation::Tanh).iter().all(|&v| v == 0.0));
+    assert!(draw(Initializer::Constant(0.25), 4, 3, Activation::Tanh).iter().all(|&v| v == 0.25));
+}
+
+#[test]
+fn orthogonal_weights_are_orthogonal_up_to_the_gain() {
+    for (rows, cols) in [(8, 5), (5, 8), (6, 6)] {
+        let weights = draw(Initializer::Orthogonal, rows, cols, Activation::Tanh) / Activation::Tanh.gain();
+        let q = if rows >= cols { weights } else { weights.reversed_axes() };
+        let gram = q.t().dot(&q);
+        for ((i, j), &v) in gram.indexed_iter() {
+            let expected = if i == j { 1.0 } else { 0.0 };
+            assert!((v - expected).abs() < 1e-10, "{}x{}: gram[{}, {}] = {}", rows, cols, i, j, v);
+        }
+    }
+}
+
+#[test]
+fn auto_follows_the_activation() {
+    assert_eq!(Initializer::Auto.resolve(Activation::Relu), Initializer::HeUniform);
+    assert_eq!(Initializer::Auto.resolve(Activation::LeakyRelu), Initializer::HeUniform);
+    assert_eq!(Initializer::Auto.resolve(Activation::Tanh), Initializer::XavierUniform);
+    assert_eq!(Initializer::Auto.resolve(Activation::Softmax), Initializer::XavierUniform);
+    assert_eq!(Initializer::LecunNormal.resolve(Activation::Relu), Initializer::LecunNormal);
+}
+
+#[test]
+fn initializers_are_configurable_per_layer() {
+    let config = TrainingConfig::from_toml_str(r#"
+        [architecture]
+        hidden_layers = [8, 8]
+        initializer = "orthogonal"
+        layer_initializers = ["he_normal", { constant = 0.5 }]
+    "#).unwrap();
+    assert_eq!(config.initializers(), vec![Initializer::HeNormal, Initializer::Constant(0.5), Initializer::Orthogonal]);
+
+    let network = NeuralNetwork::<f64>::with_initializers(&config.layer_sizes(), Activation::Relu,
+        &config.initializers(), &mut ChaCha8Rng::seed_from_u64(1));
+    match &network.layers[1] {
+        Layer::Dense(layer) => assert!(layer.weights.iter().all(|&v| v == 0.5)),
+        layer => panic!("expected a dense layer, got {:?}", layer),
+    }
+
+    let error = TrainingConfig::from_toml_str(r#"
+        [architecture]
+        hidden_layers = [8]
+        layer_initializers = ["zeros", "zeros", "zeros"]
+    "#).unwrap_err();
+    assert!(error.to_string().contains("architecture.layer_initializers"), "{}", error);
+}
+
+#[test]
+fn warm_start_copies_parameters_of_matching_networks() {
+    let source = NeuralNetwork::<f64>::with_rng(&[5, 8, 3], Activation::Relu, &mut ChaCha8Rng::seed_from_u64(1));
+    let mut target = NeuralNetwork::<f64>::with_rng(&[5, 8, 3], Activation::Tanh, &mut ChaCha8Rng::seed_from_u64(2))
+        .with_regularization(false, 0.2);
+    target.warm_start(&source).unwrap();
+
+    let x = Array2::from_shape_fn((4, 5), |(i, j)| (i * 5 + j) as f64 / 10.0);
+    let relu_target = NeuralNetwork { layers: target.layers.iter().map(|layer| match layer {
+        Layer::Dense(dense) if dense.activation == Activation::Tanh => {
+            Layer::Dense(DenseLayer { activation: Activation::Relu, ..dense.clone() })
+        }
+        layer => layer.clone(),
+    }).collect() };
+    assert_eq!(relu_target.forward(&x), source.forward(&x));
+
+    let mut wider = NeuralNetwork::<f64>::with_rng(&[5, 16, 3], Activation::Relu, &mut ChaCha8Rng::seed_from_u64(3));
+    assert!(wider.warm_start(&source).is_err());
+}
+
+fn train(name: &str, epochs: usize, learning_rate: f64, warm_start: Option<String>) -> TrainedModel {
+    let mut config = common::config(name, epochs);
+    config.optimizer.learning_rate = learning_rate;
+    config.architecture.warm_start = warm_start;
+    config.threads = 1;
+    common::fit(&config)
+}
+
+#[test]
+fn training_can_warm_start_from_a_saved_model() {
+    let source = train("source", 20, 0.001, None);
+    let path = std::env::temp_dir().join(format!("airquality_init_{}.bin", std::process::id()));
+    save_model(path.to_str().unwrap(), &source).unwrap();
+
+    // A negligible step leaves the copied parameters as they were
+    let warm = train("warm", 1, 1e-9, Some(path.to_string_lossy().into_owned()));
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(warm.stats.mean, source.stats.mean);
+    assert_eq!(warm.stats.std, source.stats.std);
+    assert!((warm.final_accuracy - source.final_accuracy).abs() < 1e-3,
+        "warm start reached {} after one epoch, the source {}", warm.final_accuracy, source.final_accuracy);
+}
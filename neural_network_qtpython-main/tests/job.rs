@@ -0,0 +1,61 @@
+mod common;
+
+use airquality_predictor::{JobStatus, TrainingJob};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn epoch(job: &TrainingJob) -> Option<usize> {
+    job.latest_metrics().map(|m| m.epoch)
+}
+
+/// Polls until `done` holds, failing after ten seconds.
+fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+    let started = Instant::now();
+    while !done() {
+        assert!(started.elapsed() < Duration::from_secs(10), "timed out waiting for {}", what);
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn jobs_pause_resume_and_cancel() {
+    let mut job = TrainingJob::start(common::config("cancel", 1_000_000));
+    // Paused before its first evaluation, the job stops right after it
+    job.pause();
+    assert_eq!(job.status(), JobStatus::Paused);
+    wait_for("the first epoch", || epoch(&job).is_some());
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(epoch(&job), Some(0), "epochs advanced while paused");
+    assert_eq!(job.status(), JobStatus::Paused);
+
+    job.resume();
+    assert_eq!(job.status(), JobStatus::Running);
+    wait_for("training to resume", || epoch(&job) > Some(0));
+
+    // A paused job is woken up to be cancelled
+    job.pause();
+    job.cancel();
+    assert_eq!(job.wait(), JobStatus::Cancelled);
+    assert!(job.take_model().is_none() && job.error().is_none());
+}
+
+#[test]
+fn finished_jobs_keep_their_status() {
+    let config = common::config("complete", 3);
+    let mut job = TrainingJob::start(config.clone());
+    assert_eq!(job.wait(), JobStatus::Completed);
+    let _ = std::fs::remove_file(&config.output.plot);
+    assert_eq!(epoch(&job), Some(2));
+
+    job.pause();
+    job.cancel();
+    assert_eq!(job.status(), JobStatus::Completed);
+    assert!(job.take_model().is_some());
+    assert!(job.take_model().is_none());
+
+    let mut missing = common::config("missing", 3);
+    missing.data.path = "does_not_exist.csv".to_string();
+    let mut failed = TrainingJob::start(missing);
+    assert_eq!(failed.wait(), JobStatus::Failed);
+    assert!(failed.error().is_some());
+}
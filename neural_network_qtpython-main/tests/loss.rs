@@ -0,0 +1,87 @@
+use airquality_predictor::model::layers::softmax;
+use airquality_predictor::model::loss::{log_softmax, Loss};
+use airquality_predictor::training::config::TrainingConfig;
+use ndarray::{array, Array2};
+
+fn one_hot() -> Array2<f64> {
+    array![[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]
+}
+
+#[test]
+fn cross_entropy_matches_the_naive_formula() {
+    let logits = array![[2.0, -1.0, 0.5], [0.1, 0.2, 0.3]];
+    let naive = -(one_hot() * softmax(&logits).mapv(f64::ln)).sum() / 2.0;
+    assert!((Loss::default().value(&logits, &one_hot()) - naive).abs() < 1e-12);
+}
+
+#[test]
+fn saturated_logits_give_finite_losses_and_gradients() {
+    let logits = array![[-1000.0, 0.0, 1000.0], [1e4, -1e4, 0.0]];
+    for loss in [Loss::default(), Loss::Focal { gamma: 2.0, class_weights: vec![] }] {
+        let value = loss.value(&logits, &one_hot());
+        assert!(value.is_finite(), "{:?} gave {}", loss, value);
+        // The first row puts all mass on the wrong class
+        assert!(value >= 1000.0 / 2.0, "{:?} gave {}", loss, value);
+        assert!(loss.gradient(&logits, &one_hot()).iter().all(|g| g.is_finite()));
+    }
+    assert!(log_softmax(&logits).iter().all(|v| v.is_finite()));
+}
+
+#[test]
+fn focal_loss_without_focusing_is_cross_entropy() {
+    let logits = array![[0.3, -1.2, 2.0], [1.0, 0.0, -1.0]];
+    let weights = vec![1.0, 2.0, 3.0];
+    let focal = Loss::Focal { gamma: 0.0, class_weights: weights.clone() };
+    let cross_entropy = Loss::CrossEntropy { label_smoothing: 0.0, class_weights: weights };
+    assert!((focal.value(&logits, &one_hot()) - cross_entropy.value(&logits, &one_hot())).abs() < 1e-12);
+    let difference = focal.gradient(&logits, &one_hot()) - cross_entropy.gradient(&logits, &one_hot());
+    assert!(difference.iter().all(|d| d.abs() < 1e-12));
+}
+
+#[test]
+fn focal_loss_down_weights_easy_rows() {
+    let easy = array![[5.0, 0.0, 0.0]];
+    let hard = array![[0.0, 0.0, 5.0]];
+    let y = array![[1.0, 0.0, 0.0]];
+    let focal = Loss::Focal { gamma: 2.0, class_weights: vec![] };
+    let ratio = |logits: &Array2<f64>| focal.value(logits, &y) / Loss::default().value(logits, &y);
+    assert!(ratio(&easy) < 0.001);
+    assert!(ratio(&hard) > 0.9);
+}
+
+#[test]
+fn cross_entropy_gradient_is_probabilities_minus_smoothed_targets() {
+    let logits = array![[2.0, -1.0, 0.5], [0.1, 0.2, 0.3]];
+    let loss = Loss::CrossEntropy { label_smoothing: 0.3, class_weights: vec![] };
+    let targets = one_hot() * 0.7 + 0.1;
+    let expected = softmax(&logits) - targets;
+    assert!((loss.gradient(&logits, &one_hot()) - expected).iter().all(|d| d.abs() < 1e-12));
+}
+
+#[test]
+fn huber_is_quadratic_near_the_target_and_linear_beyond() {
+    let loss = Loss::Huber { delta: 1.0 };
+    let y = array![[0.0]];
+    assert!((loss.value(&array![[0.5]], &y) - 0.125).abs() < 1e-12);
+    assert!((loss.value(&array![[3.0]], &y) - 2.5).abs() < 1e-12);
+    assert_eq!(loss.gradient(&array![[3.0]], &y), array![[1.0]]);
+    assert!((Loss::Mse.value(&array![[3.0, 1.0]], &array![[0.0, 0.0]]) - 5.0).abs() < 1e-12);
+}
+
+#[test]
+fn loss_is_configurable_and_validated() {
+    let config = TrainingConfig::from_toml_str(r#"
+        [loss]
+        kind = "focal"
+        class_weights = [1.0, 1.0, 3.0]
+    "#).unwrap();
+    assert_eq!(config.loss, Loss::Focal { gamma: 2.0, class_weights: vec![1.0, 1.0, 3.0] });
+
+    let error = TrainingConfig::from_toml_str(r#"
+        [loss]
+        kind = "cross_entropy"
+        label_smoothing = 1.5
+        class_weights = [1.0]
+    "#).unwrap_err().to_string();
+    assert!(error.contains("loss.label_smoothing") && error.contains("loss.class_weights"), "{}", error);
+}
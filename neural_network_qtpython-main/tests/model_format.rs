@@ -0,0 +1,54 @@
+use airquality_predictor::training::trainer::AnyModel;
+use airquality_predictor::training::validation::ValidationRules;
+use airquality_predictor::utils::io::{decode_model, encode_model, load_model, MODEL_FORMAT_VERSION};
+use ndarray::Array2;
+
+// Saved by the releases before input profiles and before training histories
+const WITH_HISTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/model_with_history.bin");
+const WITHOUT_HISTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/model_without_history.bin");
+
+const READING: [f64; 5] = [50.0, 20.0, 25.0, 50.0, 15.0];
+
+#[test]
+fn models_saved_before_the_format_version_still_load() {
+    let with_history = load_model(WITH_HISTORY).unwrap();
+    assert!(with_history.profile.is_none());
+    assert_eq!(with_history.history.len(), 20);
+    assert_eq!(with_history.config.seed, Some(11));
+
+    let without_history = load_model(WITHOUT_HISTORY).unwrap();
+    assert!(without_history.profile.is_none() && without_history.history.is_empty());
+    // Both releases trained the same network from the same seed
+    let x = Array2::from_shape_vec((1, 5), READING.to_vec()).unwrap();
+    assert_eq!(with_history.predict_batch(&x), without_history.predict_batch(&x));
+
+    // Without a profile, validation falls back to the normalization statistics
+    let validation = with_history.validate_input(&[50.0, 20.0, 2500.0, 50.0, 15.0], &ValidationRules::default()).unwrap();
+    assert!(validation.ood_score > 10.0);
+    assert!(with_history.check_drift(&x, &Default::default()).is_err());
+}
+
+#[test]
+fn saved_models_carry_the_format_version() {
+    let model = AnyModel::new(&load_model(WITH_HISTORY).unwrap());
+    let data = encode_model(&model).unwrap();
+    assert!(data.starts_with(b"AQMODEL\0"));
+    assert_eq!(data[8..12], MODEL_FORMAT_VERSION.to_le_bytes());
+    assert_eq!(encode_model(&decode_model(&data).unwrap()).unwrap(), data);
+
+    let mut future = data.clone();
+    future[8..12].copy_from_slice(&(MODEL_FORMAT_VERSION + 1).to_le_bytes());
+    let error = decode_model(&future).err().unwrap().to_string();
+    assert!(error.contains(&format!("unsupported model format version {}", MODEL_FORMAT_VERSION + 1)), "{}", error);
+
+    let error = decode_model(b"not a model").err().unwrap().to_string();
+    assert!(error.starts_with("unsupported model format"), "{}", error);
+    assert!(decode_model(&data[..10]).is_err());
+}
+
+#[test]
+fn the_shipped_model_loads() {
+    let model = load_model(concat!(env!("CARGO_MANIFEST_DIR"), "/trained_model.bin")).unwrap();
+    assert_eq!(model.features(), ["pm10", "so2", "co", "o3", "no2"]);
+    assert!(model.profile.is_some());
+}
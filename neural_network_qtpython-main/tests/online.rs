@@ -0,0 +1,110 @@
+mod common;
+
+use airquality_predictor::training::online::{partial_fit_files, PartialFitOptions, ReplayBuffer, StatsUpdate};
+use airquality_predictor::training::trainer::{training_rng, TrainedModel};
+use airquality_predictor::utils::io::{load_model, save_model};
+use common::DATA;
+use airquality_predictor::data::preprocessing::DataStats;
+use ndarray::{concatenate, s, Array2, Axis};
+
+/// The last rows of the dataset, standing in for newly labelled readings.
+fn new_readings(model: &TrainedModel) -> (Array2<f64>, Array2<f64>) {
+    let (x, y) = model.config.load_dataset(DATA).unwrap();
+    let rows = x.nrows();
+    (x.slice(s![rows - 150.., ..]).to_owned(), y.slice(s![rows - 150.., ..]).to_owned())
+}
+
+#[test]
+fn replay_buffers_keep_a_bounded_sample() {
+    let mut rng = training_rng(1);
+    let mut buffer = ReplayBuffer::new(50, 2, 1);
+    let x = Array2::from_shape_fn((400, 2), |(i, j)| (i * 2 + j) as f64);
+    let y = Array2::from_shape_fn((400, 1), |(i, _)| i as f64);
+    buffer.add(&x.slice(s![..30, ..]).to_owned(), &y.slice(s![..30, ..]).to_owned(), &mut rng).unwrap();
+    assert_eq!((buffer.len(), buffer.seen), (30, 30));
+    buffer.add(&x, &y, &mut rng).unwrap();
+    assert_eq!((buffer.len(), buffer.seen), (50, 430));
+    // Rows stay paired and later readings made it in
+    assert!(buffer.x.rows().into_iter().zip(buffer.y.rows()).all(|(x, y)| x[0] == y[0] * 2.0));
+    assert!(buffer.y.iter().any(|&v| v >= 30.0));
+    assert!(buffer.add(&Array2::zeros((1, 3)), &Array2::zeros((1, 1)), &mut rng).is_err());
+}
+
+#[test]
+fn partial_fit_learns_the_new_readings() {
+    let mut model = common::train("learn", 60);
+    let (x, y) = new_readings(&model);
+    let mut replay = ReplayBuffer::from_csv(&model, DATA, 500, &mut training_rng(2)).unwrap();
+    let seen = replay.seen;
+    let options = PartialFitOptions { epochs: 30, seed: Some(5), ..Default::default() };
+    let report = model.partial_fit(&x, &y, Some(&mut replay), &options).unwrap();
+    assert_eq!((report.samples, report.replayed), (150, 150));
+    assert!(report.loss_after < report.loss_before, "{:?}", report);
+    assert!(report.replay_loss_after.is_finite());
+    assert_eq!(replay.seen, seen + 150);
+
+    let before = model.network.forward(&model.stats.normalize(&x));
+    assert!(model.partial_fit(&x.slice(s![.., ..3]).to_owned(), &y, None, &options).is_err());
+    assert_eq!(model.network.forward(&model.stats.normalize(&x)), before);
+}
+
+#[test]
+fn updating_stats_keeps_predictions_until_training() {
+    let mut model = common::train("stats", 60);
+    let (x, y) = new_readings(&model);
+    let before = model.predict_batch(&x);
+    let mut replay = ReplayBuffer::from_csv(&model, DATA, 100, &mut training_rng(3)).unwrap();
+    let options = PartialFitOptions { epochs: 0, stats: StatsUpdate::Update, ..Default::default() };
+    assert!(model.partial_fit(&x, &y, None, &options).is_err());
+
+    let mean = model.stats.mean.clone();
+    model.partial_fit(&x, &y, Some(&mut replay), &options).unwrap();
+    assert_ne!(model.stats.mean, mean);
+    let after = model.predict_batch(&x);
+    assert!(before.iter().zip(&after).all(|(a, b)| (a - b).abs() < 1e-9));
+}
+
+#[test]
+fn partial_fit_records_the_update_in_the_model() {
+    let mut model = common::train("record", 60);
+    let (x, y) = new_readings(&model);
+    let (epochs, count) = (model.history.len(), model.profile.as_ref().unwrap().features[0].count);
+    let options = PartialFitOptions { epochs: 5, seed: Some(7), ..Default::default() };
+    let report = model.partial_fit(&x, &y, None, &options).unwrap();
+    assert_eq!(model.final_accuracy, report.score_after);
+    assert_eq!(model.history.len(), epochs + 1);
+    assert_eq!(model.history.epochs.last(), Some(&(60 + 5 - 1)));
+    assert_eq!(model.history.losses.last(), Some(&report.loss_after));
+    assert_eq!(model.profile.as_ref().unwrap().features[0].count, count + 150);
+
+    // Merged statistics are sample ones, like those of the original fit
+    let (old, new) = (x.slice(s![..100, ..]).to_owned(), x.slice(s![100.., ..]).to_owned());
+    model.stats = DataStats::from_data(&old);
+    let mut replay = ReplayBuffer::new(10, old.ncols(), y.ncols());
+    replay.add(&old, &y.slice(s![..100, ..]).to_owned(), &mut training_rng(8)).unwrap();
+    let options = PartialFitOptions { epochs: 0, stats: StatsUpdate::Update, ..Default::default() };
+    model.partial_fit(&new, &y.slice(s![100.., ..]).to_owned(), Some(&mut replay), &options).unwrap();
+    let expected = DataStats::from_data(&concatenate(Axis(0), &[old.view(), new.view()]).unwrap());
+    assert!(model.stats.std.iter().zip(&expected.std).all(|(a, b)| (a - b).abs() < 1e-9), "{} {}", model.stats.std, expected.std);
+    assert!(model.stats.mean.iter().zip(&expected.mean).all(|(a, b)| (a - b).abs() < 1e-9));
+}
+
+#[test]
+fn partial_fit_files_update_the_model_and_replay_buffer() {
+    let dir = std::env::temp_dir().join(format!("airquality_online_files_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let model = common::train("files", 60);
+    save_model(&path("model.bin"), &model).unwrap();
+
+    let options = PartialFitOptions { seed: Some(6), ..Default::default() };
+    let report = partial_fit_files(&path("model.bin"), DATA, Some(&path("model.replay")), &path("updated.bin"), &options).unwrap();
+    // The replay buffer starts from the original training data
+    assert!(report.replayed > 0);
+    let replay = ReplayBuffer::load(&path("model.replay")).unwrap();
+    assert_eq!(replay.seen as usize, 2 * report.samples);
+    let updated = load_model(&path("updated.bin")).unwrap();
+    assert_ne!(updated.network.forward(&updated.stats.normalize(&new_readings(&model).0)),
+        model.network.forward(&model.stats.normalize(&new_readings(&model).0)));
+    let _ = std::fs::remove_dir_all(&dir);
+}
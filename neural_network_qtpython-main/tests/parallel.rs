@@ -0,0 +1,17 @@
+use airquality_predictor::utils::parallel::{pool, pool_with, set_threads, threads};
+use std::sync::Arc;
+
+#[test]
+fn runs_reuse_their_thread_pools() {
+    // Training resolves threads = 0 to the shared pool size before asking for a pool
+    assert!(Arc::ptr_eq(&pool_with(0).unwrap(), &pool()));
+    assert!(Arc::ptr_eq(&pool_with(threads()).unwrap(), &pool()));
+
+    let single = pool_with(1).unwrap();
+    assert_eq!(single.current_num_threads(), 1);
+    assert!(Arc::ptr_eq(&single, &pool_with(1).unwrap()));
+
+    set_threads(3).unwrap();
+    assert_eq!(threads(), 3);
+    assert!(Arc::ptr_eq(&pool_with(3).unwrap(), &pool()));
+}
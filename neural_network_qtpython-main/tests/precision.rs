@@ -0,0 +1,45 @@
+mod common;
+
+use airquality_predictor::model::float::Precision;
+use airquality_predictor::training::trainer::AnyModel;
+use airquality_predictor::utils::io::{load_any_model, save_any_model};
+use ndarray::Array2;
+
+fn train(precision: Precision) -> AnyModel {
+    let mut config = common::config(&precision.to_string(), 20);
+    config.seed = Some(3);
+    config.threads = 1;
+    config.precision = precision;
+    common::fit_any(&config)
+}
+
+fn readings() -> Array2<f64> {
+    Array2::from_shape_vec((2, 5), vec![50.0, 20.0, 25.0, 50.0, 15.0, 180.0, 60.0, 40.0, 120.0, 30.0]).unwrap()
+}
+
+#[test]
+fn f32_model_round_trips_through_a_file() {
+    let model = train(Precision::F32);
+    assert!(matches!(model, AnyModel::F32(_)));
+    assert_eq!(model.config().precision, Precision::F32);
+
+    let path = std::env::temp_dir().join(format!("airquality_precision_{}.bin", std::process::id()));
+    save_any_model(path.to_str().unwrap(), &model).unwrap();
+    let loaded = load_any_model(path.to_str().unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded.precision(), Precision::F32);
+    assert_eq!(loaded.predict_batch(&readings()), model.predict_batch(&readings()));
+}
+
+#[test]
+fn converted_model_predicts_like_the_original() {
+    let model = train(Precision::F64);
+    let converted = AnyModel::F32(model.cast());
+    assert_eq!(converted.config().precision, Precision::F32);
+
+    let difference = (model.predict_batch(&readings()) - converted.predict_batch(&readings()))
+        .mapv(f64::abs)
+        .fold(0.0, |a: f64, &b| a.max(b));
+    assert!(difference < 1e-4, "f32 and f64 predictions differ by {}", difference);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use airquality_predictor::ffi::prediction::predict_air_quality_production;
+use airquality_predictor::training::registry::{load_production_model, Bump, ModelRegistry, RegisterOptions, Stage, Version};
+use airquality_predictor::utils::io::save_model;
+use common::temp_path;
+use std::ffi::CString;
+use std::path::Path;
+
+/// Trains a small model into `dir` and returns its path.
+fn model_file(dir: &Path, name: &str, features: &[&str]) -> String {
+    let mut config = common::config(name, 10);
+    config.data.features = features.iter().map(|f| f.to_string()).collect();
+    config.seed = Some(3);
+    let model = common::fit(&config);
+    let path = dir.join(format!("{}.bin", name)).to_string_lossy().into_owned();
+    save_model(&path, &model).unwrap();
+    path
+}
+
+#[test]
+fn versions_parse_print_and_bump() {
+    let version: Version = "1.4.2".parse().unwrap();
+    assert_eq!(version, Version { major: 1, minor: 4, patch: 2 });
+    assert_eq!(version.to_string(), "1.4.2");
+    assert_eq!("v1.4.2".parse::<Version>(), Ok(version));
+    assert_eq!(version.bump(Bump::Major).to_string(), "2.0.0");
+    assert_eq!(version.bump(Bump::Minor).to_string(), "1.5.0");
+    assert_eq!(version.bump(Bump::Patch).to_string(), "1.4.3");
+    assert!("1.10.0".parse::<Version>().unwrap() > version);
+    for invalid in ["1.4", "1.4.x", "1.4.2.0", ""] {
+        assert!(invalid.parse::<Version>().is_err(), "parsed {:?}", invalid);
+    }
+    assert_eq!(serde_json::to_string(&version).unwrap(), "\"1.4.2\"");
+}
+
+#[test]
+fn promotion_and_rollback_move_production() {
+    let dir = temp_path("stages");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = model_file(&dir, "model", &["pm10", "so2", "co", "o3", "no2"]);
+    let registry = ModelRegistry::new(dir.join("registry"));
+    assert!(load_production_model(registry.root(), "aq").is_err());
+
+    let first = registry.register("aq", &path, RegisterOptions { production: true, ..Default::default() }).unwrap();
+    assert_eq!((first.version, first.stage), (Version::FIRST, Stage::Production));
+    assert_eq!(first.metrics["epochs"], 10.0);
+    let second = registry.register("aq", &path, RegisterOptions::default()).unwrap();
+    assert_eq!((second.version.to_string(), second.stage), ("1.1.0".to_string(), Stage::Staging));
+    assert_eq!(second.lineage.parent, Some(first.version));
+    assert!(registry.register("aq", &path, RegisterOptions { version: Some(second.version), ..Default::default() }).is_err());
+
+    registry.set_stage("aq", second.version, Stage::Production).unwrap();
+    assert_eq!(registry.get("aq", first.version).unwrap().stage, Stage::Archived);
+    assert_eq!(registry.production_model_path("aq").unwrap(), registry.model_path("aq", second.version));
+    assert!(load_production_model(registry.root(), "aq").is_ok());
+
+    let restored = registry.rollback("aq").unwrap();
+    assert_eq!((restored.version, restored.stage), (first.version, Stage::Production));
+    assert_eq!(registry.get("aq", second.version).unwrap().stage, Stage::Archived);
+    assert!(registry.rollback("aq").is_err());
+    assert_eq!(registry.names().unwrap(), vec!["aq".to_string()]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn changed_inputs_bump_the_major_version() {
+    let dir = temp_path("schema");
+    std::fs::create_dir_all(&dir).unwrap();
+    let all = model_file(&dir, "all", &["pm10", "so2", "co", "o3", "no2"]);
+    let fewer = model_file(&dir, "fewer", &["pm10", "co", "o3"]);
+    let registry = ModelRegistry::new(dir.join("registry"));
+
+    registry.register("aq", &all, RegisterOptions::default()).unwrap();
+    let changed = registry.register("aq", &fewer, RegisterOptions::default()).unwrap();
+    assert_eq!(changed.version.to_string(), "2.0.0");
+    assert_eq!(changed.inputs, ["pm10", "co", "o3"]);
+    let patched = registry.register("aq", &fewer, RegisterOptions { bump: Some(Bump::Patch), ..Default::default() }).unwrap();
+    assert_eq!(patched.version.to_string(), "2.0.1");
+    assert!(registry.register("bad/name", &all, RegisterOptions::default()).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn names_cannot_leave_the_registry() {
+    let dir = temp_path("traversal");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = model_file(&dir, "model", &["pm10", "so2", "co", "o3", "no2"]);
+    // A model in a neighbouring registry, reachable as ../other/aq without the check
+    let other = ModelRegistry::new(dir.join("other"));
+    let version = other.register("aq", &path, RegisterOptions { production: true, ..Default::default() }).unwrap().version;
+    let registry = ModelRegistry::new(dir.join("registry"));
+
+    for name in ["../other/aq", "..", "aq/../../other/aq", ""] {
+        let error = registry.index(name).unwrap_err().to_string();
+        assert!(error.starts_with("invalid model name"), "{}: {}", name, error);
+        assert!(registry.get(name, version).is_err());
+        assert!(registry.set_stage(name, version, Stage::Archived).is_err());
+        assert!(registry.rollback(name).is_err());
+        assert!(registry.production_model_path(name).is_err());
+        assert!(load_production_model(registry.root(), name).is_err());
+        assert!(registry.register(name, &path, RegisterOptions::default()).is_err());
+    }
+    assert_eq!(other.production("aq").unwrap().version, version);
+
+    let root = CString::new(registry.root().to_string_lossy().into_owned()).unwrap();
+    let name = CString::new("../other/aq").unwrap();
+    let result = unsafe { predict_air_quality_production(50.0, 20.0, 25.0, 50.0, 15.0, root.as_ptr(), name.as_ptr()) };
+    assert!(result.is_null());
+    let _ = std::fs::remove_dir_all(&dir);
+}
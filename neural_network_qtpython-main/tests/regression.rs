@@ -0,0 +1,87 @@
+mod common;
+
+use airquality_predictor::model::layers::{Activation, Layer};
+use airquality_predictor::model::loss::Loss;
+use airquality_predictor::training::config::TrainingConfig;
+use airquality_predictor::training::evaluation::{evaluate_csv, evaluate_regression_csv, mean_r_squared, RegressionMetrics};
+use airquality_predictor::utils::io::{load_model, save_model};
+use common::{fit, DATA};
+use ndarray::{array, Array2};
+
+fn config(name: &str) -> TrainingConfig {
+    let mut config = common::config(name, 150);
+    config.data.features = ["so2", "co", "o3", "no2"].map(String::from).to_vec();
+    config.data.targets = vec!["pm10".to_string()];
+    config.loss = Loss::Huber { delta: 1.0 };
+    config.optimizer.learning_rate = 0.00003;
+    config.seed = Some(1);
+    config
+}
+
+#[test]
+fn regressor_predicts_in_target_units() {
+    let model = fit(&config("units"));
+    assert!(model.is_regression());
+    assert!(matches!(model.network.layers.last(), Some(Layer::Dense(dense)) if dense.activation == Activation::Linear));
+    assert_eq!(model.network.layer_sizes().last(), Some(&1));
+    // The other pollutants explain part of the pm10 variance
+    assert!(model.final_accuracy > 0.1, "R² {}", model.final_accuracy);
+
+    let x = array![[2.0, 19.0, 20.0, 9.0], [30.0, 40.0, 90.0, 30.0]];
+    let predicted = model.predict_values(&x);
+    assert_eq!(predicted.dim(), (2, 1));
+    // pm10 averages about 52 with a standard deviation of 15
+    assert!(predicted.iter().all(|&v| (20.0..120.0).contains(&v)), "{:?}", predicted);
+    assert!(predicted[[1, 0]] > predicted[[0, 0]]);
+
+    let evaluation = evaluate_regression_csv(&model, DATA).unwrap();
+    assert_eq!(evaluation.targets.len(), 1);
+    let metrics = &evaluation.targets[0];
+    assert_eq!(metrics.target, "pm10");
+    assert!(metrics.rmse < 15.4 && metrics.mae < metrics.rmse && metrics.r2 > 0.1);
+    assert!(evaluate_csv(&model, DATA).is_err());
+}
+
+#[test]
+fn regressor_survives_a_save_and_load() {
+    let model = fit(&config("save"));
+    let path = std::env::temp_dir().join(format!("airquality_regression_{}.bin", std::process::id()));
+    let path = path.to_string_lossy();
+    save_model(&path, &model).unwrap();
+    let loaded = load_model(&path).unwrap();
+    let _ = std::fs::remove_file(path.as_ref());
+
+    let x = array![[5.0, 20.0, 40.0, 12.0]];
+    assert_eq!(loaded.predict_values(&x), model.predict_values(&x));
+    assert_eq!(loaded.config.data.targets, ["pm10"]);
+}
+
+#[test]
+fn loss_must_match_the_task() {
+    let mut regression = config("check");
+    regression.loss = Loss::default();
+    assert!(regression.validate().unwrap_err().to_string().contains("loss.kind"));
+    regression.loss = Loss::Huber { delta: 0.0 };
+    assert!(regression.validate().unwrap_err().to_string().contains("loss.delta"));
+    regression.loss = Loss::Mae;
+    regression.validate().unwrap();
+
+    let mut classification = TrainingConfig { loss: Loss::Mse, ..TrainingConfig::default() };
+    assert!(classification.validate().unwrap_err().to_string().contains("loss.kind"));
+    classification.data.targets = vec!["pm10".to_string()];
+    assert!(classification.validate().unwrap_err().to_string().contains("data.targets"));
+}
+
+#[test]
+fn regression_metrics_match_hand_computed_values() {
+    let actual = array![1.0, 2.0, 3.0, 4.0];
+    let predicted = array![1.0, 2.0, 3.0, 6.0];
+    let metrics = RegressionMetrics::of("y", predicted.view(), actual.view());
+    assert!((metrics.rmse - 1.0).abs() < 1e-12);
+    assert!((metrics.mae - 0.5).abs() < 1e-12);
+    // 1 - 4 / 5
+    assert!((metrics.r2 - 0.2).abs() < 1e-12);
+
+    let perfect = Array2::from_shape_fn((4, 2), |(r, c)| (r * (c + 1)) as f64);
+    assert_eq!(mean_r_squared(&perfect, &perfect), 1.0);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use airquality_predictor::model::layers::{Activation, Dropout, Layer};
+use airquality_predictor::model::network::NeuralNetwork;
+use airquality_predictor::training::trainer::TrainedModel;
+use airquality_predictor::utils::io::{load_model, save_model};
+use ndarray::Array2;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+fn train(name: &str, threads: usize) -> TrainedModel {
+    let mut config = common::config(name, 20);
+    config.optimizer.batch_size = 512;
+    config.architecture.batch_norm = true;
+    config.regularization.dropout = 0.3;
+    config.seed = Some(5);
+    config.threads = threads;
+    common::fit(&config)
+}
+
+fn readings() -> Array2<f64> {
+    Array2::from_shape_vec((2, 5), vec![50.0, 20.0, 25.0, 50.0, 15.0, 180.0, 60.0, 40.0, 120.0, 30.0]).unwrap()
+}
+
+#[test]
+fn dropout_is_the_identity_at_inference() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let plain = NeuralNetwork::<f64>::with_rng(&[5, 8, 3], Activation::Relu, &mut rng);
+    let dropout = plain.clone().with_regularization(false, 0.5);
+    assert!(matches!(dropout.layers[1], Layer::Dropout(Dropout { rate: 0.5 })));
+
+    let x = readings();
+    assert_eq!(plain.forward(&x), dropout.forward(&x));
+}
+
+#[test]
+fn dropout_masks_scale_the_kept_inputs() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let mask: Array2<f64> = Dropout { rate: 0.25 }.mask((200, 50), &mut rng);
+    let dropped = mask.iter().filter(|&&v| v == 0.0).count() as f64 / mask.len() as f64;
+    assert!((dropped - 0.25).abs() < 0.02, "dropped {}", dropped);
+    assert!(mask.iter().all(|&v| v == 0.0 || (v - 1.0 / 0.75).abs() < 1e-12));
+}
+
+#[test]
+fn batch_norm_running_statistics_are_saved_and_used() {
+    let model = train("saved", 1);
+    let norms: Vec<_> = model.network.layers.iter()
+        .filter_map(|layer| match layer {
+            Layer::BatchNorm(norm) => Some(norm),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(norms.len(), model.config.architecture.hidden_layers.len());
+    assert!(norms.iter().all(|norm| norm.running_mean.iter().any(|&v| v != 0.0)));
+
+    let path = std::env::temp_dir().join(format!("airquality_regularization_{}.bin", std::process::id()));
+    save_model(path.to_str().unwrap(), &model).unwrap();
+    let loaded = load_model(path.to_str().unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    // Inference is deterministic and matches after reloading
+    assert_eq!(model.predict_batch(&readings()), model.predict_batch(&readings()));
+    assert_eq!(loaded.predict_batch(&readings()), model.predict_batch(&readings()));
+}
+
+#[test]
+fn seeded_runs_with_dropout_are_reproducible() {
+    let first = train("first", 4);
+    let second = train("second", 4);
+    assert_eq!(bincode::serialize(&first.network).unwrap(), bincode::serialize(&second.network).unwrap());
+}
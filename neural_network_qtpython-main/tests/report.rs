@@ -0,0 +1,54 @@
+mod common;
+
+use airquality_predictor::model::loss::Loss;
+use airquality_predictor::training::importance::permutation_importance;
+use airquality_predictor::utils::report::render_report;
+use common::{fit, temp_path};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn training_writes_a_self_contained_report() {
+    let mut config = common::config("classifier", 60);
+    let path = temp_path("classifier.html");
+    config.output.report = Some(path.to_string_lossy().into_owned());
+    fit(&config);
+
+    let html = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    for section in ["Model", "Dataset", "Class distribution", "Training curves", "Metrics", "Confusion matrix",
+        "Diagnostic plots", "Feature importance", "Configuration"] {
+        assert!(html.contains(&format!(">{}</h", section)), "missing section {}", section);
+    }
+    assert!(html.contains("validation data of"));
+    assert!(html.matches("<svg").count() >= 8);
+    assert!(!html.contains("<img") && !html.contains("src=\"http"));
+}
+
+#[test]
+fn regression_reports_show_errors_per_target() {
+    let mut config = common::config("regressor", 60);
+    config.data.features = ["so2", "co", "o3", "no2"].iter().map(|f| f.to_string()).collect();
+    config.data.targets = vec!["pm10".to_string()];
+    config.loss = Loss::Huber { delta: 1.0 };
+    config.optimizer.learning_rate = 0.00003;
+    let model = fit(&config);
+
+    let (x, y) = config.load_dataset(&config.data.path).unwrap();
+    let html = render_report(&model, "Regression", "airquality.csv", &x, &y).unwrap();
+    assert!(html.contains("<th>RMSE</th>") && html.contains("<h3>Targets</h3>"));
+    assert!(!html.contains("Confusion matrix"));
+}
+
+#[test]
+fn shuffling_an_input_the_model_relies_on_costs_accuracy() {
+    let config = common::config("importance", 60);
+    let model = fit(&config);
+    let (x, y) = config.load_dataset(&config.data.path).unwrap();
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let importances = permutation_importance(&model, &x, &y, 2, &mut rng);
+
+    assert_eq!(importances.len(), 5);
+    let pm10 = importances.iter().find(|i| i.feature == "pm10").unwrap();
+    assert!(pm10.importance > 0.05, "pm10 importance {}", pm10.importance);
+}
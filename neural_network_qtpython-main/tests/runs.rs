@@ -0,0 +1,77 @@
+mod common;
+
+use airquality_predictor::training::config::TrainingConfig;
+use airquality_predictor::training::runs::{DatasetFingerprint, RunStatus, RunStore, CONFIG_FILE, HISTORY_FILE, MODEL_FILE, PLOT_FILE};
+use airquality_predictor::training::trainer::TrainingControl;
+use airquality_predictor::utils::io::load_model;
+use common::temp_path;
+
+fn config(epochs: usize) -> TrainingConfig {
+    let mut config = common::config("runs", epochs);
+    config.seed = Some(8);
+    config
+}
+
+#[test]
+fn fingerprints_follow_the_file_contents() {
+    let dir = temp_path("fingerprint");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("data.csv");
+    std::fs::write(&path, "a,b\n1,2\n3,4").unwrap();
+    let first = DatasetFingerprint::of(path.to_str().unwrap()).unwrap();
+    assert_eq!((first.rows, first.bytes), (2, 11));
+    assert_eq!(first, DatasetFingerprint::of(path.to_str().unwrap()).unwrap());
+
+    std::fs::write(&path, "a,b\n1,2\n3,5").unwrap();
+    assert_ne!(first.hash, DatasetFingerprint::of(path.to_str().unwrap()).unwrap().hash);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn runs_are_tracked_compared_and_promoted() {
+    let root = temp_path("store");
+    let store = RunStore::new(&root);
+    let (first, _) = store.train(&config(20), |_| TrainingControl::Continue).unwrap();
+    let (second, model) = store.train(&config(40), |_| TrainingControl::Continue).unwrap();
+    assert_ne!(first.id, second.id);
+    assert_eq!(second.status, RunStatus::Completed);
+    assert_eq!(second.seed, Some(8));
+    assert_eq!(second.metrics["epochs"], 40.0);
+    assert_eq!(second.metrics["train_accuracy"], model.final_accuracy());
+    for file in [CONFIG_FILE, MODEL_FILE, PLOT_FILE, HISTORY_FILE, "diagnostics/roc.png"] {
+        assert!(store.dir(&second.id).join(file).exists(), "missing {}", file);
+    }
+
+    let ids: Vec<String> = store.list().unwrap().into_iter().map(|run| run.id).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&first.id) && ids.contains(&second.id));
+
+    let diffs = store.compare(&first.id, &second.id).unwrap();
+    let epochs = diffs.iter().find(|d| d.metric == "epochs").unwrap();
+    assert_eq!(epochs.diff, Some(20.0));
+
+    let destination = root.join("production.bin");
+    let destination = destination.to_str().unwrap();
+    store.promote(&first.id, destination).unwrap();
+    store.promote(&second.id, destination).unwrap();
+    assert!(store.get(&first.id).unwrap().promoted.is_none());
+    assert!(store.get(&second.id).unwrap().promoted.is_some());
+    assert_eq!(load_model(destination).unwrap().final_accuracy, model.final_accuracy());
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn failed_runs_keep_their_record() {
+    let root = temp_path("failed");
+    let store = RunStore::new(&root);
+    let mut config = config(10);
+    config.architecture.warm_start = Some(root.join("missing.bin").to_string_lossy().into_owned());
+    assert!(store.train(&config, |_| TrainingControl::Continue).is_err());
+
+    let runs = store.list().unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].status, RunStatus::Failed);
+    assert!(runs[0].error.is_some());
+    assert!(store.promote(&runs[0].id, root.join("x.bin").to_str().unwrap()).is_err());
+    let _ = std::fs::remove_dir_all(&root);
+}
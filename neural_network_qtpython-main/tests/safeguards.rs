@@ -0,0 +1,110 @@
+mod common;
+
+use airquality_predictor::data::preprocessing::DataStats;
+use airquality_predictor::model::layers::{Activation, Layer};
+use airquality_predictor::model::loss::Loss;
+use airquality_predictor::model::network::NeuralNetwork;
+use airquality_predictor::training::config::{DivergencePolicy, TrainingConfig};
+use airquality_predictor::training::trainer::{train_model_with_progress, TrainedModel, TrainingControl, TrainingDiverged};
+use airquality_predictor::utils::io::save_model;
+use ndarray::{Array1, Array2};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::error::Error;
+
+fn config(name: &str, learning_rate: f64) -> TrainingConfig {
+    let mut config = common::config(name, 20);
+    config.optimizer.learning_rate = learning_rate;
+    config.seed = Some(3);
+    config
+}
+
+fn train(config: &TrainingConfig) -> Result<TrainedModel, Box<dyn Error>> {
+    let result = train_model_with_progress(config, |_| TrainingControl::Continue);
+    let _ = std::fs::remove_file(&config.output.plot);
+    result
+}
+
+#[test]
+fn clipping_bounds_the_gradients() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let network = NeuralNetwork::<f64>::with_rng(&[2, 4, 3], Activation::Relu, &mut rng);
+    let x = Array2::from_shape_vec((2, 2), vec![30.0, -20.0, 15.0, 40.0]).unwrap();
+    let y = Array2::from_shape_vec((2, 3), vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0]).unwrap();
+    let gradients = network.gradients(&x, &y, &Loss::default(), &mut rng);
+    assert!(gradients.norm() > 0.5);
+
+    let mut by_value = gradients.clone();
+    by_value.clip_value(0.01);
+    assert!(by_value.layers.iter().all(|(w, b)| w.iter().chain(b).all(|v| v.abs() <= 0.01)));
+
+    let mut by_norm = gradients.clone();
+    by_norm.clip_norm(0.5);
+    assert!((by_norm.norm() - 0.5).abs() < 1e-12);
+    // Only the length changes, not the direction
+    let scale = 0.5 / gradients.norm();
+    for ((w, _), (clipped, _)) in gradients.layers.iter().zip(&by_norm.layers) {
+        for (&v, &c) in w.iter().zip(clipped) {
+            assert!((v * scale - c).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn divergence_aborts_by_default() {
+    let error = train(&config("abort", 1e300)).err().expect("training should diverge");
+    let diverged = error.downcast_ref::<TrainingDiverged>().expect("a divergence error");
+    assert_eq!(diverged.epoch, 0);
+}
+
+#[test]
+fn rollback_gives_up_after_max_recoveries() {
+    let mut config = config("rollback", 1e300);
+    config.divergence.policy = DivergencePolicy::Rollback;
+    config.divergence.max_recoveries = 2;
+    let error = train(&config).err().expect("the same learning rate diverges again");
+    assert_eq!(error.downcast_ref::<TrainingDiverged>().unwrap().epoch, 2);
+}
+
+#[test]
+fn reduce_lr_recovers() {
+    let mut config = config("reduce_lr", 1e300);
+    config.divergence.policy = DivergencePolicy::ReduceLr;
+    config.divergence.lr_factor = 1e-150;
+    let model = train(&config).unwrap();
+    assert!(model.is_finite());
+}
+
+#[test]
+fn clipped_training_with_a_large_learning_rate_stays_finite() {
+    let mut config = config("clipped", 0.02);
+    config.optimizer.clip_norm = 1.0;
+    let model = train(&config).unwrap();
+    assert!(model.is_finite());
+}
+
+#[test]
+fn non_finite_models_are_not_saved() {
+    let mut model = train(&config("save", 0.001)).unwrap();
+    if let Layer::Dense(dense) = &mut model.network.layers[0] {
+        dense.weights[[0, 0]] = f64::NAN;
+    }
+    assert!(!model.is_finite());
+
+    let path = std::env::temp_dir().join(format!("airquality_safeguards_{}.bin", std::process::id()));
+    let path = path.to_string_lossy();
+    assert!(save_model(&path, &model).is_err());
+    assert!(!std::path::Path::new(path.as_ref()).exists());
+}
+
+#[test]
+fn predictions_without_finite_outputs_have_no_class() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let network = NeuralNetwork::<f64>::with_rng(&[2, 3], Activation::Softmax, &mut rng);
+    let stats = DataStats { mean: Array1::zeros(2), std: Array1::ones(2) };
+    let prediction = network.predict(&[f64::NAN, 1.0], &stats);
+    assert_eq!(prediction.class, -1);
+
+    let y = Array2::from_shape_vec((1, 3), vec![1.0, 0.0, 0.0]).unwrap();
+    assert_eq!(network.accuracy(&y, &Array2::from_elem((1, 3), f64::NAN)), 0.0);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use airquality_predictor::training::search::{
+    hyperband_brackets, read_leaderboard_json, run_search, write_leaderboard_csv, write_leaderboard_json, SearchConfig, SearchOutput, Strategy,
+};
+
+fn search(learning_rates: Vec<f64>) -> SearchConfig {
+    let mut config = SearchConfig { base: common::config("search", 6), ..Default::default() };
+    config.base.seed = Some(7);
+    config.space.learning_rate = learning_rates;
+    config.output = SearchOutput { leaderboard_csv: None, leaderboard_json: None };
+    config
+}
+
+#[test]
+fn diverged_trials_are_recorded_and_the_search_goes_on() {
+    let config = search(vec![1e300, 0.001]);
+    let outcome = run_search(&config).unwrap();
+    let _ = std::fs::remove_file(&config.base.output.plot);
+
+    let [best, diverged] = &outcome.leaderboard[..] else { panic!("{:?}", outcome.leaderboard) };
+    assert!(!best.diverged && best.val_accuracy.is_some_and(f64::is_finite));
+    assert_eq!(best.candidate.learning_rate, 0.001);
+    assert!(diverged.diverged && diverged.val_accuracy.is_none() && diverged.val_loss.is_none());
+    assert_eq!(diverged.candidate.learning_rate, 1e300);
+    assert!(outcome.best.is_finite());
+
+    let path = common::temp_path("leaderboard.csv");
+    write_leaderboard_csv(&path.to_string_lossy(), &outcome.leaderboard).unwrap();
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    let rows: Vec<&str> = csv.lines().collect();
+    assert!(rows[0].ends_with(",diverged") && rows[1].ends_with(",false") && rows[2].ends_with(",true"), "{}", csv);
+
+    // Diverged trials read back from the JSON leaderboard too
+    let path = common::temp_path("leaderboard.json");
+    write_leaderboard_json(&path.to_string_lossy(), &outcome.leaderboard).unwrap();
+    let read = read_leaderboard_json(&path.to_string_lossy()).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(read.len(), 2);
+    assert_eq!((read[0].val_accuracy, read[0].val_loss), (best.val_accuracy, best.val_loss));
+    assert!(read[1].diverged && read[1].val_accuracy.is_none() && read[1].candidate == diverged.candidate);
+}
+
+#[test]
+fn halving_drops_diverged_trials_and_fails_when_every_trial_diverges() {
+    let mut config = search(vec![1e300, 0.001]);
+    config.strategy = Strategy::SuccessiveHalving { candidates: 4, min_epochs: 2, eta: 2 };
+    let outcome = run_search(&config).unwrap();
+    let _ = std::fs::remove_file(&config.base.output.plot);
+    let first_round: Vec<bool> = outcome.leaderboard.iter().filter(|t| t.round == 0).map(|t| t.diverged).collect();
+    assert!(first_round.contains(&true) && first_round.contains(&false), "{:?}", outcome.leaderboard);
+    assert!(outcome.leaderboard.iter().filter(|t| t.round > 0).all(|t| !t.diverged));
+    assert!(!outcome.leaderboard[0].diverged);
+
+    let error = run_search(&search(vec![1e300])).err().unwrap().to_string();
+    assert!(error.contains("diverged"), "{}", error);
+}
+
+#[test]
+fn hyperband_runs_every_bracket_up_to_the_full_budget() {
+    assert_eq!(hyperband_brackets(1, 27, 3), vec![(27, 1), (12, 3), (6, 9), (4, 27)]);
+    assert_eq!(hyperband_brackets(10, 5, 3), vec![(1, 5)]);
+
+    let mut config = search(vec![0.01, 0.001]);
+    config.strategy = Strategy::Hyperband { min_epochs: 2, eta: 2 };
+    let outcome = run_search(&config).unwrap();
+    let _ = std::fs::remove_file(&config.base.output.plot);
+    let brackets: Vec<usize> = outcome.leaderboard.iter().map(|t| t.bracket).collect();
+    assert_eq!(brackets.iter().max(), Some(&1), "{:?}", outcome.leaderboard);
+    assert_eq!(outcome.leaderboard.len(), 2 + 1 + 2);
+    assert_eq!(outcome.leaderboard[0].epochs, 6);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use airquality_predictor::server::{ModelServer, ServerConfig, MAX_BODY};
+use airquality_predictor::training::trainer::TrainedModel;
+use airquality_predictor::utils::io::save_model;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+fn train(name: &str, hidden_layers: Vec<usize>) -> TrainedModel {
+    let mut config = common::config(name, 3);
+    config.architecture.hidden_layers = hidden_layers;
+    common::fit(&config)
+}
+
+/// Sends one request and returns the status and JSON reply.
+fn request(addr: SocketAddr, method: &str, path: &str, body: &[u8]) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", method, path, addr, body.len()).unwrap();
+    stream.write_all(body).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+    let (_, reply) = response.split_once("\r\n\r\n").unwrap();
+    (status, serde_json::from_str(reply).unwrap())
+}
+
+/// Stops the server when the test ends, even when an assertion fails.
+struct Shutdown<'a>(&'a ModelServer);
+
+impl Drop for Shutdown<'_> {
+    fn drop(&mut self) {
+        self.0.shutdown();
+    }
+}
+
+fn predict(addr: SocketAddr, body: Value) -> (u16, Value) {
+    request(addr, "POST", "/predict", body.to_string().as_bytes())
+}
+
+#[test]
+fn the_server_answers_every_route_and_reloads_the_model() {
+    let path = common::temp_path("server.bin");
+    save_model(&path.to_string_lossy(), &train("first", vec![8])).unwrap();
+    let config = ServerConfig {
+        addr: "127.0.0.1:0".to_string(),
+        model_path: PathBuf::from(&path),
+        workers: 2,
+        reload_interval: Duration::from_millis(100),
+        ..Default::default()
+    };
+    let server = ModelServer::bind(config).unwrap();
+    let addr = server.local_addr().unwrap();
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| server.run());
+        let _shutdown = Shutdown(&server);
+
+        let (status, health) = request(addr, "GET", "/health", b"");
+        assert_eq!((status, health["status"].as_str()), (200, Some("ok")));
+        let (status, model) = request(addr, "GET", "/model", b"");
+        assert_eq!(status, 200);
+        assert_eq!(model["features"], json!(["pm10", "so2", "co", "o3", "no2"]));
+        assert_eq!(model["layer_sizes"], json!([5, 8, 3]));
+
+        let (status, single) = predict(addr, json!({ "pm10": 50, "so2": 20, "co": 25, "o3": 50, "no2": 15 }));
+        assert_eq!(status, 200, "{}", single);
+        assert!(single["category"].is_string() && single["warnings"] == json!([]));
+        let (status, batch) = predict(addr, json!({ "instances": [[50, 20, 25, 50, 15], [-1, 20, 25, 50, 15]] }));
+        assert_eq!(status, 200, "{}", batch);
+        let predictions = batch["predictions"].as_array().unwrap();
+        assert_eq!(predictions.len(), 2);
+        assert_eq!(predictions[1]["warnings"][0]["issue"], "below_minimum");
+
+        assert_eq!(predict(addr, json!({ "pm10": "high" })).0, 400);
+        let (status, reply) = predict(addr, json!({ "instances": [[50, 20, 25, 50, 15], [1, 2]] }));
+        assert_eq!(status, 400);
+        assert!(reply["error"].as_str().unwrap().starts_with("instance 1"), "{}", reply);
+        assert_eq!(request(addr, "POST", "/predict", b"{").0, 400);
+        assert_eq!(request(addr, "GET", "/predict", b"").0, 405);
+        let (status, reply) = request(addr, "POST", "/predict", &vec![b' '; MAX_BODY as usize + 1]);
+        assert_eq!(status, 413, "{}", reply);
+
+        let (status, metrics) = request(addr, "GET", "/metrics", b"");
+        assert_eq!(status, 200);
+        assert_eq!(metrics["predictions"], 3);
+        assert_eq!(metrics["routes"]["POST /predict"]["requests"], 6);
+        assert_eq!(metrics["routes"]["POST /predict"]["errors"], 4);
+
+        // A future modification time makes the change visible whatever the clock resolution
+        save_model(&path.to_string_lossy(), &train("second", vec![4])).unwrap();
+        std::fs::File::options().write(true).open(&path).unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        let started = Instant::now();
+        while request(addr, "GET", "/metrics", b"").1["model_reloads"] == 0 {
+            assert!(started.elapsed() < Duration::from_secs(10), "the model was not reloaded");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(request(addr, "GET", "/model", b"").1["layer_sizes"], json!([5, 4, 3]));
+    });
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use airquality_predictor::data::time_series::{
+    chronological_split, format_timestamp, load_series, parse_timestamp, windows, Outputs, TimeSeriesConfig,
+};
+use airquality_predictor::model::loss::Loss;
+use airquality_predictor::training::backtest::backtest;
+use airquality_predictor::training::config::TrainingConfig;
+use airquality_predictor::training::search::SearchConfig;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Writes a CSV file to the temp directory and removes it when dropped.
+struct TempCsv(PathBuf);
+
+impl TempCsv {
+    fn new(name: &str, contents: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("airquality_series_{}_{}.csv", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        TempCsv(path)
+    }
+
+    fn path(&self) -> String {
+        self.0.to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempCsv {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Hourly readings of two stations whose pm10 follows a daily cycle.
+fn daily_cycles(days: usize) -> String {
+    let mut csv = "timestamp,station,pm10,no2,kategori\n".to_string();
+    for (station, offset) in [("north", 0.0), ("south", 10.0)] {
+        for hour in 0..days * 24 {
+            let pm10 = 55.0 + offset + 25.0 * (hour as f64 * std::f64::consts::TAU / 24.0).sin();
+            let category = if pm10 < 50.0 { "BAIK" } else if pm10 < 75.0 { "SEDANG" } else { "TIDAK SEHAT" };
+            writeln!(csv, "{},{},{:.2},{:.2},{}", 1_700_000_000 + hour * 3600, station, pm10, 10.0 + pm10 / 10.0, category).unwrap();
+        }
+    }
+    csv
+}
+
+fn names(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn timestamps_parse_in_every_supported_format() {
+    let expected = 1_704_067_200 + 5 * 3600;
+    for text in ["2024-01-01 05:00:00", "2024-01-01T05:00:00", "2024-01-01 05:00", "2024-01-01T07:00:00+02:00",
+        "1704085200"]
+    {
+        assert_eq!(parse_timestamp(text), Ok(expected), "{}", text);
+    }
+    assert_eq!(parse_timestamp("2024-01-01"), Ok(1_704_067_200));
+    assert!(parse_timestamp("yesterday").is_err());
+    assert_eq!(format_timestamp(expected), "2024-01-01 05:00:00");
+}
+
+#[test]
+fn windows_hold_lags_and_rolling_statistics_per_station() {
+    // Station b has no reading at 03:00, and its rows are interleaved with a's
+    let csv = TempCsv::new("windows", "timestamp,station,x,y\n\
+        2024-01-01 00:00,a,1,10\n2024-01-01 00:00,b,100,1\n2024-01-01 01:00,a,2,20\n2024-01-01 01:00,b,200,2\n\
+        2024-01-01 02:00,a,3,30\n2024-01-01 02:00,b,300,3\n2024-01-01 03:00,a,4,40\n2024-01-01 04:00,b,500,5\n\
+        2024-01-01 04:00,a,5,50\n");
+    let config = TimeSeriesConfig { station: Some("station".to_string()), lags: vec![1], rolling_windows: vec![2], ..Default::default() };
+    let series = load_series(&csv.path(), &config, &names(&["x"]), Some(Outputs::Targets(&names(&["y"])))).unwrap();
+    let windows = windows(&series, &config).unwrap();
+
+    assert_eq!(config.input_names(&names(&["x"])), ["x", "x_lag1", "x_mean2", "x_std2"]);
+    // a at 01:00, 02:00 and 03:00 have a previous and a next reading; b only at 01:00
+    assert_eq!(windows.stations, ["a", "b", "a", "a"]);
+    let x = windows.x;
+    assert_eq!(x.row(0).to_vec(), [2.0, 1.0, 1.5, 0.5]);
+    assert_eq!(x.row(1).to_vec(), [200.0, 100.0, 150.0, 50.0]);
+    assert_eq!(x.row(3).to_vec(), [4.0, 3.0, 3.5, 0.5]);
+    // The target is the reading of the same station one step later
+    assert_eq!(windows.y.unwrap().column(0).to_vec(), [30.0, 3.0, 40.0, 50.0]);
+}
+
+#[test]
+fn duplicate_timestamps_are_rejected() {
+    let csv = TempCsv::new("duplicates", "timestamp,x\n0,1\n3600,2\n3600,3\n");
+    let config = TimeSeriesConfig::default();
+    let series = load_series(&csv.path(), &config, &names(&["x"]), None).unwrap();
+    let error = windows(&series, &config).err().unwrap();
+    assert!(error.to_string().contains("row 3: duplicate timestamp"), "{}", error);
+}
+
+#[test]
+fn chronological_split_keeps_the_future_out_of_training() {
+    let csv = TempCsv::new("split", &daily_cycles(5));
+    let config = TimeSeriesConfig { station: Some("station".to_string()), horizon: 3, ..Default::default() };
+    let series = load_series(&csv.path(), &config, &names(&["pm10", "no2"]), Some(Outputs::Targets(&names(&["pm10"])))).unwrap();
+    let windows = windows(&series, &config).unwrap();
+    // 117 samples per station; the last 39 hours of both validate
+    let split = chronological_split(&windows, 1.0 / 3.0, &config);
+    assert_eq!(windows.x.nrows(), 234);
+    assert_eq!(split.x_val.nrows(), 78);
+    // The last 3 training hours of each station forecast validation readings
+    assert_eq!(split.x_train.nrows(), 234 - 78 - 2 * 3);
+}
+
+fn forecast_config(name: &str, csv: &TempCsv) -> TrainingConfig {
+    let mut config = common::config(name, 100);
+    config.data.path = csv.path();
+    config.data.features = names(&["pm10", "no2"]);
+    config.time_series = Some(TimeSeriesConfig {
+        station: Some("station".to_string()),
+        lags: vec![1, 2],
+        rolling_windows: vec![3],
+        ..Default::default()
+    });
+    config
+}
+
+#[test]
+fn backtest_reports_every_horizon() {
+    let csv = TempCsv::new("backtest", &daily_cycles(12));
+    let config = forecast_config("backtest", &csv);
+    assert_eq!(config.layer_sizes()[0], 2 * (1 + 2 + 2));
+
+    let results = backtest(&config, &[1, 6]).unwrap();
+    assert_eq!(results.iter().map(|r| r.horizon).collect::<Vec<_>>(), [1, 6]);
+    for result in &results {
+        let evaluation = result.classification.as_ref().unwrap();
+        assert!(result.regression.is_none());
+        assert!(evaluation.samples > 0 && result.train_samples > evaluation.samples);
+    }
+    // One hour ahead, the daily cycle is easy to follow
+    let accuracy = |i: usize| results[i].classification.as_ref().unwrap().accuracy;
+    assert!(accuracy(0) > 0.8, "{}", accuracy(0));
+
+    let mut regression = config.clone();
+    regression.data.targets = names(&["pm10"]);
+    regression.loss = Loss::Mse;
+    regression.optimizer.learning_rate = 0.00003;
+    let results = backtest(&regression, &[2]).unwrap();
+    let metrics = &results[0].regression.as_ref().unwrap().targets[0];
+    assert_eq!(metrics.target, "pm10");
+    assert!(metrics.r2 > 0.5, "{:?}", metrics);
+}
+
+#[test]
+fn time_series_settings_are_validated() {
+    let csv = TempCsv::new("validation", "");
+    let mut config = forecast_config("validation", &csv);
+    config.validate().unwrap();
+    // Forecasting may predict the future of an input column
+    config.data.targets = names(&["pm10"]);
+    config.loss = Loss::Mse;
+    config.validate().unwrap();
+
+    let series = config.time_series.as_mut().unwrap();
+    series.horizon = 0;
+    series.lags = vec![0];
+    series.rolling_windows = vec![1];
+    let message = config.validate().unwrap_err().to_string();
+    for field in ["time_series.horizon", "time_series.lags", "time_series.rolling_windows"] {
+        assert!(message.contains(field), "{}", message);
+    }
+
+    let search = SearchConfig { base: forecast_config("search", &csv), ..Default::default() };
+    assert!(search.validate().unwrap_err().to_string().contains("base.time_series"));
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use airquality_predictor::ffi::prediction::{free_prediction_result, predict_air_quality};
+use airquality_predictor::training::trainer::TrainedModel;
+use airquality_predictor::training::validation::{InputIssue, ValidationRules};
+use airquality_predictor::utils::io::save_model;
+use std::ffi::{CStr, CString};
+
+fn issues(model: &TrainedModel, reading: &[f64], rules: &ValidationRules) -> Vec<(String, InputIssue)> {
+    model.validate_input(reading, rules).unwrap().warnings.into_iter().map(|w| (w.feature, w.issue)).collect()
+}
+
+#[test]
+fn readings_are_checked_against_bounds_and_the_training_range() {
+    let model = common::train("rules", 10);
+    let rules = ValidationRules::default();
+    let typical = model.validate_input(&[50.0, 20.0, 25.0, 50.0, 15.0], &rules).unwrap();
+    assert!(typical.is_valid());
+    assert_eq!((typical.ood_score, typical.flags()), (0.0, 0));
+
+    assert_eq!(issues(&model, &[-3.0, 20.0, 25.0, 50.0, 15.0], &rules), [("pm10".to_string(), InputIssue::BelowMinimum)]);
+    assert_eq!(issues(&model, &[50.0, 20.0, 2500.0, 50.0, 15.0], &rules), [
+        ("co".to_string(), InputIssue::AboveMaximum),
+        ("co".to_string(), InputIssue::OutOfDistribution),
+    ]);
+    // Within the physical bounds yet far beyond anything seen in training
+    let far = model.validate_input(&[50.0, 20.0, 25.0, 50.0, 400.0], &rules).unwrap();
+    assert_eq!(far.warnings.len(), 1);
+    assert_eq!((far.warnings[0].input, far.warnings[0].limit), (4, 79.0));
+    assert!(far.ood_score > 10.0 && far.scores[..4].iter().all(|&s| s == 0.0));
+    assert_eq!(far.input_flags(4), InputIssue::OutOfDistribution.flag());
+
+    let nan = model.validate_input(&[50.0, f64::NAN, 25.0, 50.0, 15.0], &rules).unwrap();
+    assert_eq!(nan.flags(), InputIssue::NotFinite.flag());
+    assert!(nan.scores[1].is_nan() && nan.ood_score == 0.0);
+    assert!(model.validate_input(&[1.0, 2.0], &rules).is_err());
+
+    let lenient = ValidationRules { ood_threshold: 100.0, ..Default::default() };
+    assert!(model.validate_input(&[50.0, 20.0, 25.0, 50.0, 400.0], &lenient).unwrap().is_valid());
+}
+
+#[test]
+fn checked_predictions_carry_their_warnings() {
+    let mut model = common::train("predict", 10);
+    let rules = ValidationRules::default();
+    let checked = model.predict_checked(&[50.0, 20.0, 2500.0, 50.0, 15.0], &rules).unwrap();
+    assert!(checked.prediction.class >= 0);
+    assert_eq!(checked.validation.warnings.len(), 2);
+    assert_eq!(model.predict_checked(&[50.0, 20.0, f64::INFINITY, 50.0, 15.0], &rules).unwrap().prediction.class, -1);
+
+    // Models without an input profile fall back to mean ± 3 standard deviations
+    model.profile = None;
+    let (mean, std) = (model.stats.mean[2], model.stats.std[2]);
+    let edge = model.validate_input(&[50.0, 20.0, mean + 3.0 * std, 50.0, 15.0], &rules).unwrap();
+    assert_eq!(edge.ood_score, 0.0);
+    let beyond = model.validate_input(&[50.0, 20.0, mean + 5.0 * std, 50.0, 15.0], &rules).unwrap();
+    assert!((beyond.ood_score - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn the_c_result_reports_warnings() {
+    let path = std::env::temp_dir().join(format!("airquality_validation_ffi_{}.bin", std::process::id()));
+    save_model(&path.to_string_lossy(), &common::train("ffi", 10)).unwrap();
+    let path = CString::new(path.to_string_lossy().into_owned()).unwrap();
+
+    unsafe {
+        let result = predict_air_quality(50.0, 20.0, 25.0, 50.0, 15.0, path.as_ptr());
+        assert!(!result.is_null());
+        assert_eq!(((*result).warnings, (*result).probabilities_len), (0, 3));
+        assert!((*result).warning_messages.is_null());
+        let probabilities = std::slice::from_raw_parts((*result).probabilities, 3);
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        free_prediction_result(result);
+
+        let result = predict_air_quality(-1.0, 20.0, 25.0, 50.0, 900.0, path.as_ptr());
+        let flags = (*result).input_warnings;
+        assert_eq!(flags[0], InputIssue::BelowMinimum.flag());
+        assert_eq!(flags[4], InputIssue::AboveMaximum.flag() | InputIssue::OutOfDistribution.flag());
+        assert_eq!((*result).warnings, flags[0] | flags[4]);
+        assert!((*result).ood_score > 10.0);
+        let messages = CStr::from_ptr((*result).warning_messages).to_str().unwrap();
+        assert_eq!(messages.lines().count(), 3);
+        assert!(messages.starts_with("pm10 = -1 is below the minimum of 0"), "{}", messages);
+        free_prediction_result(result);
+    }
+    let _ = std::fs::remove_file(path.to_str().unwrap());
+}